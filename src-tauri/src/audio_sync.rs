@@ -0,0 +1,122 @@
+// Audio Sync Module
+//
+// Aligns separately recorded clips (e.g. multi-camera angles) by cross-correlating
+// their audio. FFmpegExecutor decodes the PCM; the correlation runs here.
+
+use serde::{Deserialize, Serialize};
+use crate::error::Error;
+
+/// Sample rate audio is decoded at for alignment (plenty for speech/transients)
+pub const SYNC_SAMPLE_RATE: u32 = 8000;
+
+/// Samples per envelope bin for the coarse search (100 bins per second)
+const ENVELOPE_BIN: usize = 80;
+
+/// Fine search radius around the coarse match, in samples (±20 ms)
+const FINE_RADIUS: i64 = 160;
+
+/// Most samples compared per lag in the fine search (20 seconds)
+const FINE_WINDOW: usize = SYNC_SAMPLE_RATE as usize * 20;
+
+/// Minimum overlap for a coarse match, in envelope bins (5 seconds)
+const MIN_COARSE_OVERLAP: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioOffset {
+    /// Seconds by which `other` lags `reference`: positive means a shared sound
+    /// occurs later in `other`, so `other` should start that much earlier on the timeline
+    #[serde(rename = "offsetSeconds")]
+    pub offset_seconds: f64,
+    /// Normalized correlation at the match (0–1); low values suggest the clips don't share audio
+    pub confidence: f64,
+}
+
+/// Find the offset between two mono PCM signals sampled at SYNC_SAMPLE_RATE
+/// A coarse search over loudness envelopes finds the region, then a sample-accurate
+/// search on the waveform refines it
+pub fn find_offset(reference: &[f32], other: &[f32], max_offset_secs: f64) -> Result<AudioOffset, Error> {
+    if !max_offset_secs.is_finite() || max_offset_secs <= 0.0 {
+        return Err(Error::InvalidInput(format!("Maximum offset must be positive, got {}", max_offset_secs)));
+    }
+
+    let reference_envelope = envelope(reference);
+    let other_envelope = envelope(other);
+
+    // Coarse: envelope bins are 10 ms, so lags are in units of 10 ms
+    let max_bins = (max_offset_secs * SYNC_SAMPLE_RATE as f64 / ENVELOPE_BIN as f64).ceil() as i64;
+    let (coarse_lag, _) = best_lag(
+        &reference_envelope,
+        &other_envelope,
+        -max_bins..=max_bins,
+        MIN_COARSE_OVERLAP,
+        usize::MAX,
+    )
+    .ok_or_else(|| {
+        Error::InvalidInput(
+            "Clips don't overlap enough to align (need at least 5 seconds of shared audio)".to_string(),
+        )
+    })?;
+
+    // Fine: search the waveform around the coarse match
+    let center = coarse_lag * ENVELOPE_BIN as i64;
+    let (fine_lag, confidence) = best_lag(
+        reference,
+        other,
+        (center - FINE_RADIUS)..=(center + FINE_RADIUS),
+        SYNC_SAMPLE_RATE as usize,
+        FINE_WINDOW,
+    )
+    .ok_or_else(|| Error::InvalidInput("Clips don't overlap enough to refine the alignment".to_string()))?;
+
+    Ok(AudioOffset {
+        offset_seconds: fine_lag as f64 / SYNC_SAMPLE_RATE as f64,
+        confidence: confidence.clamp(0.0, 1.0),
+    })
+}
+
+/// Mean-removed loudness envelope (mean absolute amplitude per bin)
+fn envelope(samples: &[f32]) -> Vec<f32> {
+    let bins: Vec<f32> = samples
+        .chunks(ENVELOPE_BIN)
+        .map(|chunk| chunk.iter().map(|s| s.abs()).sum::<f32>() / chunk.len() as f32)
+        .collect();
+
+    let mean = bins.iter().sum::<f32>() / bins.len().max(1) as f32;
+    bins.into_iter().map(|b| b - mean).collect()
+}
+
+/// Search a lag range for the highest normalized correlation
+fn best_lag(
+    a: &[f32],
+    b: &[f32],
+    lags: std::ops::RangeInclusive<i64>,
+    min_overlap: usize,
+    max_len: usize,
+) -> Option<(i64, f64)> {
+    lags.filter_map(|lag| correlation_at(a, b, lag, min_overlap, max_len).map(|c| (lag, c)))
+        .max_by(|x, y| x.1.total_cmp(&y.1))
+}
+
+/// Normalized cross-correlation of `a[i]` against `b[i + lag]` over their overlap
+fn correlation_at(a: &[f32], b: &[f32], lag: i64, min_overlap: usize, max_len: usize) -> Option<f64> {
+    let start = (-lag).max(0);
+    let end = (a.len() as i64).min(b.len() as i64 - lag);
+    if end - start < min_overlap as i64 {
+        return None;
+    }
+    let end = end.min(start.saturating_add(i64::try_from(max_len).unwrap_or(i64::MAX)));
+
+    let (mut dot, mut energy_a, mut energy_b) = (0.0f64, 0.0f64, 0.0f64);
+    for i in start..end {
+        let x = a[i as usize] as f64;
+        let y = b[(i + lag) as usize] as f64;
+        dot += x * y;
+        energy_a += x * x;
+        energy_b += y * y;
+    }
+
+    if energy_a == 0.0 || energy_b == 0.0 {
+        return None;
+    }
+    Some(dot / (energy_a * energy_b).sqrt())
+}
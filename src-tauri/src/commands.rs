@@ -3,17 +3,38 @@
 // Defines Tauri commands that expose FFmpeg operations to the frontend.
 // These commands are invoked from the React app and handle media operations.
 
-use tauri::Emitter;
-use crate::ffmpeg::{FFmpegExecutor, ClipInfo, CameraInfo, AudioFormat};
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde::Serialize;
+use tauri::{Emitter, State};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use crate::ffmpeg::{
+    FFmpegExecutor, FFmpegState, ClipInfo, CameraInfo, CameraMode, SubtitleStream, AudioFormat, Chapter, ExportOptions, LoudnessMeasurement,
+    TrimResult, ImageFormat, FrameSampling, FrameExtraction, AvSyncInfo,
+    DemuxResult, AudioDeviceInfo, path_str,
+};
+use crate::error::Error;
+use crate::audio_sync::{self, AudioOffset, SYNC_SAMPLE_RATE};
+use crate::whisper_cpp::{WhisperCpp, whisper_cpp_to_transcript};
 use crate::transcription::{
-    OpenAIClient, Transcript, TranscriptionConfig, whisper_to_transcript,
-    export_as_txt, export_as_srt, export_as_vtt, export_as_json,
+    OpenAIClient, AuthMode, AzureDeployment, CaptionGranularity, ClipSpec, ClipTranscription, Transcript, TranscriptionConfig, ApiKeyStatus, FailedRange, WhisperResponse, whisper_to_transcript,
+    stitch_whisper_responses, WHISPER_MAX_UPLOAD_BYTES,
+    export_as_txt, export_as_srt, export_as_vtt, export_as_json, export_as_csv, export_words_as_csv, chapters_from_pauses,
 };
 
+/// Whether a file has an audio stream, without probing its full metadata
+/// Lets the UI disable audio features (transcription, waveforms) for silent clips
+#[tauri::command]
+pub async fn has_audio(file_path: String, ffmpeg: State<'_, FFmpegState>) -> Result<bool, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.has_audio_stream(&file_path)
+}
+
 /// Get media metadata from a video file
 #[tauri::command]
-pub async fn get_media_metadata(file_path: String) -> Result<serde_json::Value, String> {
-    let executor = FFmpegExecutor::new()?;
+pub async fn get_media_metadata(file_path: String, ffmpeg: State<'_, FFmpegState>) -> Result<serde_json::Value, Error> {
+    let executor = ffmpeg.executor()?;
     let metadata = executor.get_metadata(&file_path)?;
     
     // Convert to JSON with camelCase field names
@@ -25,34 +46,43 @@ pub async fn get_media_metadata(file_path: String) -> Result<serde_json::Value,
         "codec": metadata.codec,
         "bitrate": metadata.bitrate,
         "fileSize": metadata.file_size,
+        "rotation": metadata.rotation,
+        "audioCodec": metadata.audio_codec,
+        "sampleRate": metadata.sample_rate,
+        "channels": metadata.channels,
+        "audioBitrate": metadata.audio_bitrate,
     }))
 }
 
 /// Generate a thumbnail image from a video at a specific timestamp
 /// Returns base64-encoded image data
+/// With `search_window` (seconds), the best-looking frame from that span starting at
+/// `timestamp` is used instead; omit it for exact frames when scrubbing
 #[tauri::command]
 pub async fn generate_thumbnail(
     file_path: String,
-    timestamp: f64
-) -> Result<String, String> {
+    timestamp: f64,
+    search_window: Option<f64>,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<String, Error> {
     use std::fs;
     use std::io::Read;
     
     // Create temporary output path
     let temp_dir = std::env::temp_dir();
     let temp_file = temp_dir.join(format!("thumbnail_{}.jpg", uuid::Uuid::new_v4()));
-    let temp_path = temp_file.to_str().ok_or("Invalid temp path")?;
+    let temp_path = path_str(&temp_file)?;
     
-    let executor = FFmpegExecutor::new()?;
-    executor.generate_thumbnail(&file_path, timestamp, temp_path)?;
+    let executor = ffmpeg.executor()?;
+    executor.generate_thumbnail(&file_path, timestamp, temp_path, search_window)?;
     
     // Read the image file and convert to base64
     let mut file = fs::File::open(temp_path)
-        .map_err(|e| format!("Failed to read thumbnail: {}", e))?;
+        .map_err(Error::io("Failed to read thumbnail"))?;
     
     let mut buffer = Vec::new();
     file.read_to_end(&mut buffer)
-        .map_err(|e| format!("Failed to read file contents: {}", e))?;
+        .map_err(Error::io("Failed to read file contents"))?;
     
     // Clean up temp file
     let _ = fs::remove_file(temp_path);
@@ -64,39 +94,605 @@ pub async fn generate_thumbnail(
     Ok(base64)
 }
 
+/// Generate thumbnails for several timestamps of one video in as few FFmpeg runs as possible
+/// Returns base64-encoded images in the same order as `timestamps`
+#[tauri::command]
+pub async fn generate_thumbnails(
+    file_path: String,
+    timestamps: Vec<f64>,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<Vec<String>, Error> {
+    let temp_dir = std::env::temp_dir();
+    let batch_id = uuid::Uuid::new_v4();
+    let temp_paths: Vec<String> = (0..timestamps.len())
+        .map(|i| path_str(&temp_dir.join(format!("thumbnail_{}_{}.jpg", batch_id, i))).map(String::from))
+        .collect::<Result<_, _>>()?;
+    
+    let executor = ffmpeg.executor()?;
+    let result = executor
+        .generate_thumbnails(&file_path, &timestamps, &temp_paths)
+        .and_then(|_| {
+            use base64::{Engine as _, engine::general_purpose};
+            temp_paths
+                .iter()
+                .map(|path| {
+                    std::fs::read(path)
+                        .map(|buffer| general_purpose::STANDARD.encode(buffer))
+                        .map_err(Error::io("Failed to read thumbnail"))
+                })
+                .collect()
+        });
+    
+    // Clean up temp files whether or not extraction succeeded
+    for path in &temp_paths {
+        let _ = std::fs::remove_file(path);
+    }
+    
+    result
+}
+
+/// Filmstrip sprite sheet as sent to the frontend: base64-encoded JPEG data plus
+/// the cell layout needed to find each frame
+#[derive(Debug, Serialize)]
+pub struct FilmstripImage {
+    pub image: String,
+    #[serde(rename = "cellWidth")]
+    pub cell_width: u32,
+    #[serde(rename = "cellHeight")]
+    pub cell_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+/// Generate a sprite sheet of `count` evenly spaced frames for the timeline scrubber
+#[tauri::command]
+pub async fn generate_filmstrip(
+    file_path: String,
+    count: u32,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<FilmstripImage, Error> {
+    let executor = ffmpeg.executor()?;
+    let filmstrip = executor.generate_filmstrip(&file_path, count)?;
+    
+    // The sprite stays in the temp directory as a cache for the next request
+    let buffer = std::fs::read(&filmstrip.path)
+        .map_err(Error::io("Failed to read filmstrip"))?;
+    
+    use base64::{Engine as _, engine::general_purpose};
+    Ok(FilmstripImage {
+        image: general_purpose::STANDARD.encode(&buffer),
+        cell_width: filmstrip.cell_width,
+        cell_height: filmstrip.cell_height,
+        columns: filmstrip.columns,
+        rows: filmstrip.rows,
+    })
+}
+
+/// Find scene changes in a video, e.g. to place thumbnails at interesting moments
+/// `threshold` is FFmpeg's scene score (default 0.3); at most `max_results` (default 50) times are returned
+#[tauri::command]
+pub async fn detect_scene_changes(
+    file_path: String,
+    threshold: Option<f64>,
+    max_results: Option<usize>,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<Vec<f64>, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.detect_scene_changes(&file_path, threshold.unwrap_or(0.3), max_results.unwrap_or(50))
+}
+
+/// Draw a clip region's audio waveform for the timeline
+/// Returns a base64-encoded PNG (default 1000x100, light blue); `duration` defaults to the rest of the file
+#[tauri::command]
+pub async fn generate_waveform(
+    file_path: String,
+    trim_start: Option<f64>,
+    duration: Option<f64>,
+    width: Option<u32>,
+    height: Option<u32>,
+    color: Option<String>,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<String, Error> {
+    let executor = ffmpeg.executor()?;
+    let path = executor.generate_waveform(
+        &file_path,
+        trim_start.unwrap_or(0.0),
+        duration,
+        width.unwrap_or(1000),
+        height.unwrap_or(100),
+        color.as_deref().unwrap_or("#60a5fa"),
+    )?;
+    
+    // The image stays in the temp directory as a cache for the next request
+    let buffer = std::fs::read(&path)
+        .map_err(Error::io("Failed to read waveform"))?;
+    
+    use base64::{Engine as _, engine::general_purpose};
+    Ok(general_purpose::STANDARD.encode(&buffer))
+}
+
+/// Get the average color of a frame as a hex string (e.g. "#1a2b3c")
+/// Used to tint editor UI to match the current clip
+#[tauri::command]
+pub async fn get_frame_color(
+    file_path: String,
+    timestamp: f64,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<String, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.get_frame_color(&file_path, timestamp)
+}
+
+/// Render one frame of the composed timeline at a timeline timestamp
+/// Returns base64-encoded PNG data, e.g. for a poster image without a full export
+#[tauri::command]
+pub async fn export_timeline_frame(
+    clips: Vec<ClipInfo>,
+    timestamp: f64,
+    resolution: String,
+    fps: u32,
+    composition_length: f64,
+    options: Option<ExportOptions>,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<String, Error> {
+    use std::fs;
+    
+    let temp_file = std::env::temp_dir().join(format!("timeline_frame_{}.png", uuid::Uuid::new_v4()));
+    let temp_path = path_str(&temp_file)?;
+    
+    let executor = ffmpeg.executor()?;
+    let options = options.unwrap_or_default();
+    let result = executor.export_timeline_frame(
+        &clips,
+        timestamp,
+        temp_path,
+        &resolution,
+        fps,
+        composition_length,
+        &options,
+    );
+    
+    let buffer = result.and_then(|_| fs::read(temp_path).map_err(Error::io("Failed to read frame")));
+    let _ = fs::remove_file(temp_path);
+    
+    use base64::{Engine as _, engine::general_purpose};
+    Ok(general_purpose::STANDARD.encode(buffer?))
+}
+
 /// Export video from timeline clips with specified settings
+/// Returns warnings that didn't stop the export, e.g. clips far from the export frame rate
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn export_video(
     clips: Vec<ClipInfo>,
     output_path: String,
     resolution: String,
     fps: u32,
-    composition_length: f64
-) -> Result<(), String> {
-    let executor = FFmpegExecutor::new()?;
+    composition_length: f64,
+    options: Option<ExportOptions>,
+    window: tauri::Window,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<Vec<String>, Error> {
+    let options = options.unwrap_or_default();
+    let outputs = std::iter::once(&output_path).chain(options.extra_outputs.iter().map(|spec| &spec.path));
+    for path in outputs {
+        if crate::recording::is_recording_to(path) {
+            return Err(Error::InvalidInput(format!("Can't export to {} while it is being recorded", path)));
+        }
+    }
+    
+    let executor = ffmpeg.executor()?;
     
     // Convert Vec to slice for method call
-    executor.export_video(&clips, &output_path, &resolution, fps, composition_length)
+    let warnings = executor.export_video(
+        &clips,
+        &output_path,
+        &resolution,
+        fps,
+        composition_length,
+        &options,
+        &mut |percent| {
+            let _ = window.emit("export-progress", serde_json::json!({ "percent": percent }));
+        },
+    )?;
+    
+    // Only report completion once FFmpeg has exited successfully
+    window.emit("export-progress", serde_json::json!({ "percent": 100.0 }))
+        .map_err(emit_failed)?;
+    
+    Ok(warnings)
+}
+
+/// Progress events reach the window over IPC; failing to send one counts as an I/O error
+fn emit_failed(e: tauri::Error) -> Error {
+    Error::io("Failed to emit event")(std::io::Error::other(e))
+}
+
+/// Abort the running export; the pending export_video call returns an error
+/// and its partially written output is deleted
+#[tauri::command]
+pub async fn cancel_export() -> Result<(), Error> {
+    crate::ffmpeg::cancel_export()
+}
+
+/// Export a podcast MP3 with background music ducked under the voice,
+/// loudness normalized to -16 LUFS, and chapter markers from transcript pauses
+/// Returns the chapters that were embedded
+#[tauri::command]
+pub async fn export_podcast(
+    voice_path: String,
+    music_path: Option<String>,
+    music_volume: Option<f64>,
+    transcript: Option<Transcript>,
+    min_pause: Option<f64>,
+    output_path: String,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<Vec<Chapter>, Error> {
+    let chapters = transcript
+        .map(|t| chapters_from_pauses(&t, min_pause.unwrap_or(2.0)))
+        .unwrap_or_default();
+
+    let executor = ffmpeg.executor()?;
+    executor.export_podcast(
+        &voice_path,
+        music_path.as_deref(),
+        music_volume.unwrap_or(0.3),
+        &chapters,
+        &output_path,
+    )?;
+
+    Ok(chapters)
+}
+
+/// Rotate (clockwise, in degrees) and/or flip a clip into a new file for re-import
+/// Returns true if the transform was lossless (metadata-only rotation)
+#[tauri::command]
+pub async fn transform_clip(
+    input_path: String,
+    output_path: String,
+    rotate: u32,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<bool, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.transform_clip(&input_path, &output_path, rotate, flip_horizontal, flip_vertical)
+}
+
+/// Render a clip's trim to a standalone file
+/// Stream copy (reencode = false) is fast but snaps to the previous keyframe;
+/// the returned in/out points reflect what was actually written
+#[tauri::command]
+pub async fn render_trim(
+    file_path: String,
+    trim_start: f64,
+    trim_end: f64,
+    output_path: String,
+    reencode: bool,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<TrimResult, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.render_trim(&file_path, trim_start, trim_end, &output_path, reencode)
+}
+
+/// List the subtitle tracks embedded in a file (index, language tag, codec)
+#[tauri::command]
+pub async fn list_subtitle_streams(file_path: String, ffmpeg: State<'_, FFmpegState>) -> Result<Vec<SubtitleStream>, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.list_subtitle_streams(&file_path)
+}
+
+/// Save an embedded subtitle track as SRT, so files that already carry captions
+/// don't need transcribing
+#[tauri::command]
+pub async fn extract_subtitles(
+    file_path: String,
+    stream: u32,
+    output_path: String,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<(), Error> {
+    let executor = ffmpeg.executor()?;
+    executor.extract_subtitles(&file_path, stream, &output_path)
+}
+
+/// Split a clip into separate video and audio files (lossless stream copy)
+#[tauri::command]
+pub async fn demux(
+    file_path: String,
+    video_out: String,
+    audio_out: String,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<DemuxResult, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.demux(&file_path, &video_out, &audio_out)
+}
+
+/// Extract an image sequence for frame-by-frame analysis or contact sheets
+/// Pass either `every_n_frames` or `interval` (seconds between frames)
+#[tauri::command]
+pub async fn extract_frames(
+    file_path: String,
+    every_n_frames: Option<u32>,
+    interval: Option<f64>,
+    output_dir: String,
+    format: String,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<FrameExtraction, Error> {
+    let sampling = match (every_n_frames, interval) {
+        (Some(n), None) => FrameSampling::EveryNthFrame(n),
+        (None, Some(secs)) => FrameSampling::Interval(secs),
+        _ => return Err(Error::InvalidInput("Specify exactly one of every_n_frames or interval".to_string())),
+    };
+
+    let format = match format.as_str() {
+        "png" => ImageFormat::Png,
+        "jpg" | "jpeg" => ImageFormat::Jpg,
+        _ => return Err(Error::InvalidInput(format!("Unsupported image format: {}", format))),
+    };
+
+    let executor = ffmpeg.executor()?;
+    executor.extract_frames(&file_path, sampling, &output_dir, format)
+}
+
+/// Find how far `other_path` lags `reference_path` by cross-correlating their audio
+/// Used to line up separately recorded camera angles
+#[tauri::command]
+pub async fn find_audio_offset(
+    reference_path: String,
+    other_path: String,
+    max_offset: Option<f64>,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<AudioOffset, Error> {
+    let max_offset = max_offset.unwrap_or(30.0);
+    if !max_offset.is_finite() || max_offset <= 0.0 {
+        return Err(Error::InvalidInput(format!("Maximum offset must be positive, got {}", max_offset)));
+    }
+    // Analyse enough audio to cover the search range plus a minute of overlap
+    let analysis_secs = max_offset + 60.0;
+
+    let executor = ffmpeg.executor()?;
+    let reference = executor.decode_pcm_mono(&reference_path, SYNC_SAMPLE_RATE, analysis_secs)?;
+    let other = executor.decode_pcm_mono(&other_path, SYNC_SAMPLE_RATE, analysis_secs)?;
+
+    audio_sync::find_offset(&reference, &other, max_offset)
+}
+
+/// Measure the loudness of a clip region without modifying it
+/// Lets the UI decide whether loudness normalization is needed before export
+#[tauri::command]
+pub async fn measure_loudness(
+    file_path: String,
+    trim_start: f64,
+    duration: f64,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<LoudnessMeasurement, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.measure_loudness(&file_path, trim_start, duration)
+}
+
+/// Check whether a file's audio and video streams start at the same time
+/// The returned offset can be used to correct the audio when editing
+#[tauri::command]
+pub async fn check_av_sync(file_path: String, ffmpeg: State<'_, FFmpegState>) -> Result<AvSyncInfo, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.check_av_sync(&file_path)
 }
 
 /// List available cameras using FFmpeg
 #[tauri::command]
-pub async fn list_cameras() -> Result<Vec<CameraInfo>, String> {
-    let executor = FFmpegExecutor::new()?;
+pub async fn list_cameras(ffmpeg: State<'_, FFmpegState>) -> Result<Vec<CameraInfo>, Error> {
+    let executor = ffmpeg.executor()?;
     executor.list_cameras()
 }
 
-/// Transcribe a video clip using OpenAI Whisper
+/// Sizes and frame rates a camera supports, largest first, so the UI only offers modes
+/// the camera can record
 #[tauri::command]
+pub async fn get_camera_capabilities(index: u32, ffmpeg: State<'_, FFmpegState>) -> Result<Vec<CameraMode>, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.get_camera_capabilities(index)
+}
+
+/// List available audio input devices (microphones) using FFmpeg
+#[tauri::command]
+pub async fn list_audio_devices(ffmpeg: State<'_, FFmpegState>) -> Result<Vec<AudioDeviceInfo>, Error> {
+    let executor = ffmpeg.executor()?;
+    executor.list_audio_devices()
+}
+
+/// Check that an OpenAI API key is accepted before starting any transcription work
+#[tauri::command]
+pub async fn verify_openai_key(
+    api_key: String,
+    base_url: Option<String>,
+    auth_mode: Option<AuthMode>,
+    azure: Option<AzureDeployment>,
+    organization: Option<String>,
+    project: Option<String>,
+) -> Result<ApiKeyStatus, Error> {
+    openai_client(api_key, base_url, auth_mode, azure)
+        .with_organization(organization, project)
+        .verify_key()
+        .await
+}
+
+/// Client for api.openai.com, or for a compatible endpoint (self-hosted Whisper, Azure)
+/// With `azure`, `base_url` is the Azure resource endpoint
+fn openai_client(
+    api_key: String,
+    base_url: Option<String>,
+    auth_mode: Option<AuthMode>,
+    azure: Option<AzureDeployment>,
+) -> OpenAIClient {
+    let client = match base_url {
+        Some(url) => OpenAIClient::with_base_url(api_key, url),
+        None => OpenAIClient::new(api_key),
+    };
+    let client = client.with_auth_mode(auth_mode.unwrap_or_default());
+    match azure {
+        Some(azure) => client.with_azure_deployment(azure),
+        None => client,
+    }
+}
+
+/// Where clip audio is transcribed
+#[derive(Clone)]
+enum TranscriptionBackend {
+    Local(WhisperCpp),
+    Api(OpenAIClient),
+}
+
+impl TranscriptionBackend {
+    /// The OpenAI API when a key is given, otherwise whisper.cpp
+    /// Finds whisper.cpp up front so a missing install fails before any audio is extracted
+    fn new(
+        api_key: Option<String>,
+        base_url: Option<String>,
+        auth_mode: Option<AuthMode>,
+        azure: Option<AzureDeployment>,
+        config: &TranscriptionConfig,
+    ) -> Result<Self, Error> {
+        match api_key.filter(|key| !key.trim().is_empty()) {
+            Some(api_key) => Ok(Self::Api(
+                openai_client(api_key, base_url, auth_mode, azure)
+                    .with_organization(config.organization.clone(), config.project.clone()),
+            )),
+            None => Ok(Self::Local(WhisperCpp::new()?)),
+        }
+    }
+}
+
+/// Run blocking work (FFmpeg calls) on tokio's blocking pool so concurrent
+/// transcriptions don't stall the async runtime's worker threads
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, Error> + Send + 'static,
+) -> Result<T, Error> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| Error::process("Background task failed", e.to_string()))?
+}
+
+/// Default number of clips transcribe_clips works on at once
+const DEFAULT_CONCURRENT_TRANSCRIPTIONS: usize = 3;
+
+/// Transcribe a video clip using OpenAI Whisper, or locally with whisper.cpp when no
+/// API key is given
+/// `base_url` and `auth_mode` point it at a Whisper-compatible endpoint instead, and
+/// `azure` at an Azure OpenAI deployment
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn transcribe_clip(
     clip_id: String,
     file_path: String,
     trim_start: f64,
     duration: f64,
-    api_key: String,
+    api_key: Option<String>,
     config: TranscriptionConfig,
+    base_url: Option<String>,
+    auth_mode: Option<AuthMode>,
+    azure: Option<AzureDeployment>,
     window: tauri::Window,
-) -> Result<Transcript, String> {
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<Transcript, Error> {
+    let backend = TranscriptionBackend::new(api_key, base_url, auth_mode, azure, &config)?;
+    let clip = ClipSpec { clip_id, file_path, trim_start, duration };
+    transcribe_clip_with(ffmpeg.executor()?, &backend, &clip, &config, &window).await
+}
+
+/// Transcribe several clips, running up to `max_concurrent` (default 3) at a time
+/// Progress events are keyed by each clip's id. Returns one result per clip, in order;
+/// a clip that fails gets an "error" progress event and its error in the result rather
+/// than stopping the rest of the batch
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn transcribe_clips(
+    clips: Vec<ClipSpec>,
+    api_key: Option<String>,
+    config: TranscriptionConfig,
+    base_url: Option<String>,
+    auth_mode: Option<AuthMode>,
+    azure: Option<AzureDeployment>,
+    max_concurrent: Option<usize>,
+    window: tauri::Window,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<Vec<ClipTranscription>, Error> {
+    let backend = Arc::new(TranscriptionBackend::new(api_key, base_url, auth_mode, azure, &config)?);
+    let executor = Arc::new(ffmpeg.executor()?.clone());
+    let config = Arc::new(config);
+    let permits = Arc::new(Semaphore::new(
+        max_concurrent.unwrap_or(DEFAULT_CONCURRENT_TRANSCRIPTIONS).max(1),
+    ));
+
+    let clip_ids: Vec<String> = clips.iter().map(|clip| clip.clip_id.clone()).collect();
+    let mut task_indexes = HashMap::new();
+    let mut tasks = JoinSet::new();
+    for (index, clip) in clips.into_iter().enumerate() {
+        let backend = Arc::clone(&backend);
+        let executor = Arc::clone(&executor);
+        let config = Arc::clone(&config);
+        let permits = Arc::clone(&permits);
+        let window = window.clone();
+
+        let task = tasks.spawn(async move {
+            // The semaphore is never closed, so acquiring only waits for a free slot
+            let _permit = permits.acquire_owned().await;
+            let result = transcribe_clip_with(&executor, &backend, &clip, &config, &window).await;
+
+            if let Err(e) = &result {
+                emit_transcription_error(&window, &clip.clip_id, e);
+            }
+            (index, result)
+        });
+        task_indexes.insert(task.id(), index);
+    }
+
+    // Return results in the order the clips were given
+    let mut results: Vec<(usize, Result<Transcript, Error>)> = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                // The task panicked or was aborted, so it never reported its own error
+                let index = task_indexes[&e.id()];
+                let error = Error::process("Transcription task failed", e.to_string());
+                emit_transcription_error(&window, &clip_ids[index], &error);
+                results.push((index, Err(error)));
+            }
+        }
+    }
+    results.sort_by_key(|(index, _)| *index);
+
+    Ok(results
+        .into_iter()
+        .map(|(index, result)| {
+            let (transcript, error) = match result {
+                Ok(transcript) => (Some(transcript), None),
+                Err(error) => (None, Some(error)),
+            };
+            ClipTranscription { clip_id: clip_ids[index].clone(), transcript, error }
+        })
+        .collect())
+}
+
+/// Report a clip's failure to the frontend as an "error" progress event
+fn emit_transcription_error(window: &tauri::Window, clip_id: &str, error: &Error) {
+    let _ = window.emit("transcription-progress", serde_json::json!({
+        "clipId": clip_id,
+        "stage": "error",
+        "percent": 0.0,
+        "message": error.to_string()
+    }));
+}
+
+/// Extract one clip's audio and transcribe it, emitting progress keyed by the clip id
+async fn transcribe_clip_with(
+    executor: &FFmpegExecutor,
+    backend: &TranscriptionBackend,
+    clip: &ClipSpec,
+    config: &TranscriptionConfig,
+    window: &tauri::Window,
+) -> Result<Transcript, Error> {
+    let clip_id = clip.clip_id.clone();
 
     // Emit progress: Audio extraction
     window.emit("transcription-progress", serde_json::json!({
@@ -104,38 +700,74 @@ pub async fn transcribe_clip(
         "stage": "extracting",
         "percent": 0.0,
         "message": "Extracting audio from video..."
-    })).map_err(|e| format!("Failed to emit event: {}", e))?;
+    })).map_err(emit_failed)?;
 
-    // Extract audio
-    let executor = FFmpegExecutor::new()?;
-    let audio_path = executor
-        .extract_audio(&file_path, trim_start, duration, AudioFormat::Mp3)?;
+    // Extract 16 kHz mono audio (whisper.cpp reads WAV; the API takes smaller MP3 uploads)
+    let format = match backend {
+        TranscriptionBackend::Local(_) => AudioFormat::Wav,
+        TranscriptionBackend::Api(_) => AudioFormat::Mp3,
+    };
+    let audio_path = {
+        let executor = executor.clone();
+        let clip = clip.clone();
+        run_blocking(move || executor.extract_audio(&clip.file_path, clip.trim_start, clip.duration, format, true))
+            .await?
+    };
 
+    // Audio was extracted from trim_start, so times are shifted back to the source clip's
+    transcribe_extracted(executor, backend, &audio_path, clip_id, clip.trim_start, clip.duration, config, window).await
+}
+
+/// Transcribe extracted speech audio, then remove it, emitting progress from 30% on
+/// `offset` is added to every time; `duration` is the length of the audio
+#[allow(clippy::too_many_arguments)]
+async fn transcribe_extracted(
+    executor: &FFmpegExecutor,
+    backend: &TranscriptionBackend,
+    audio_path: &std::path::Path,
+    clip_id: String,
+    offset: f64,
+    duration: f64,
+    config: &TranscriptionConfig,
+    window: &tauri::Window,
+) -> Result<Transcript, Error> {
     // Emit progress: Transcribing
     window.emit("transcription-progress", serde_json::json!({
         "clipId": clip_id,
         "stage": "transcribing",
         "percent": 30.0,
-        "message": "Sending to OpenAI for transcription..."
-    })).map_err(|e| format!("Failed to emit event: {}", e))?;
+        "message": match backend {
+            TranscriptionBackend::Local(_) => "Transcribing locally with whisper.cpp...",
+            TranscriptionBackend::Api(_) => "Sending to OpenAI for transcription...",
+        }
+    })).map_err(emit_failed)?;
 
     // Transcribe
-    let client = OpenAIClient::new(api_key);
-    let whisper_response = client.transcribe(&audio_path, &config).await?;
+    let transcript = match backend {
+        TranscriptionBackend::Local(whisper_cpp) => {
+            let output = whisper_cpp.transcribe(audio_path, duration, config).await;
+            let _ = tokio::fs::remove_file(audio_path).await;
+            whisper_cpp_to_transcript(output?, clip_id, offset, duration)
+        }
+        TranscriptionBackend::Api(client) => {
+            let whisper_response =
+                transcribe_audio(executor, client, audio_path, config, window, &clip_id, (30.0, 90.0)).await;
 
-    // Clean up temporary audio file
-    let _ = tokio::fs::remove_file(&audio_path).await;
+            // Clean up temporary audio file
+            let _ = tokio::fs::remove_file(audio_path).await;
+            let (whisper_response, failed_ranges) = whisper_response?;
 
-    // Emit progress: Processing
-    window.emit("transcription-progress", serde_json::json!({
-        "clipId": clip_id,
-        "stage": "processing",
-        "percent": 90.0,
-        "message": "Processing transcription..."
-    })).map_err(|e| format!("Failed to emit event: {}", e))?;
+            // Emit progress: Processing
+            window.emit("transcription-progress", serde_json::json!({
+                "clipId": clip_id,
+                "stage": "processing",
+                "percent": 90.0,
+                "message": "Processing transcription..."
+            })).map_err(emit_failed)?;
 
-    // Convert to our format
-    let transcript = whisper_to_transcript(whisper_response, clip_id);
+            whisper_to_transcript(whisper_response, clip_id, offset, failed_ranges)
+        }
+    };
 
     // Emit completion
     window.emit("transcription-progress", serde_json::json!({
@@ -143,24 +775,31 @@ pub async fn transcribe_clip(
         "stage": "complete",
         "percent": 100.0,
         "message": "Transcription complete!"
-    })).map_err(|e| format!("Failed to emit event: {}", e))?;
+    })).map_err(emit_failed)?;
 
     Ok(transcript)
 }
 
-/// Transcribe entire timeline (all clips combined) using OpenAI Whisper
+/// Transcribe entire timeline (all clips combined) using OpenAI Whisper, or locally
+/// with whisper.cpp when no API key is given
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn transcribe_timeline(
     clips: Vec<ClipInfo>,
     composition_length: f64,
-    api_key: String,
+    api_key: Option<String>,
     config: TranscriptionConfig,
+    base_url: Option<String>,
+    auth_mode: Option<AuthMode>,
+    azure: Option<AzureDeployment>,
     window: tauri::Window,
-) -> Result<Transcript, String> {
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<Transcript, Error> {
     if clips.is_empty() {
-        return Err("No clips on timeline to transcribe".to_string());
+        return Err(Error::InvalidInput("No clips on timeline to transcribe".to_string()));
     }
 
+    let backend = TranscriptionBackend::new(api_key, base_url, auth_mode, azure, &config)?;
     let timeline_id = "timeline".to_string();
 
     // Emit progress: Audio extraction
@@ -169,63 +808,114 @@ pub async fn transcribe_timeline(
         "stage": "extracting",
         "percent": 0.0,
         "message": "Extracting audio from timeline clips..."
-    })).map_err(|e| format!("Failed to emit event: {}", e))?;
+    })).map_err(emit_failed)?;
 
-    // Extract and combine audio from all clips
-    let executor = FFmpegExecutor::new()?;
-    let audio_path = executor
-        .extract_and_combine_audio(&clips, composition_length)?;
+    // Extract and combine 16 kHz mono audio from all clips
+    let executor = ffmpeg.executor()?;
+    let format = match backend {
+        TranscriptionBackend::Local(_) => AudioFormat::Wav,
+        TranscriptionBackend::Api(_) => AudioFormat::Mp3,
+    };
+    let audio_path = {
+        let executor = executor.clone();
+        run_blocking(move || executor.extract_and_combine_audio(&clips, composition_length, format)).await?
+    };
 
-    // Emit progress: Transcribing
-    window.emit("transcription-progress", serde_json::json!({
-        "clipId": timeline_id,
-        "stage": "transcribing",
-        "percent": 50.0,
-        "message": "Sending combined timeline audio to OpenAI for transcription..."
-    })).map_err(|e| format!("Failed to emit event: {}", e))?;
-
-    // Transcribe
-    let client = OpenAIClient::new(api_key);
-    let whisper_response = client.transcribe(&audio_path, &config).await?;
+    // The combined audio starts at the start of the timeline
+    transcribe_extracted(executor, &backend, &audio_path, timeline_id, 0.0, composition_length, &config, &window).await
+}
 
-    // Clean up temporary audio file
-    let _ = tokio::fs::remove_file(&audio_path).await;
+/// Send extracted audio to Whisper, splitting it into chunks first if it's over the upload limit
+/// Progress events run across the `progress` percent range, one step per chunk
+/// A chunk the API fails on is returned as a failed range instead of discarding the
+/// chunks that did transcribe; only when every chunk fails is the error returned
+async fn transcribe_audio(
+    executor: &FFmpegExecutor,
+    client: &OpenAIClient,
+    audio_path: &std::path::Path,
+    config: &TranscriptionConfig,
+    window: &tauri::Window,
+    clip_id: &str,
+    progress: (f64, f64),
+) -> Result<(WhisperResponse, Vec<FailedRange>), Error> {
+    let size = tokio::fs::metadata(audio_path)
+        .await
+        .map_err(Error::io("Failed to read audio file"))?
+        .len();
+    if size <= WHISPER_MAX_UPLOAD_BYTES {
+        return Ok((client.transcribe(audio_path, config).await?, Vec::new()));
+    }
 
-    // Emit progress: Processing
-    window.emit("transcription-progress", serde_json::json!({
-        "clipId": timeline_id,
-        "stage": "processing",
-        "percent": 90.0,
-        "message": "Processing timeline transcription..."
-    })).map_err(|e| format!("Failed to emit event: {}", e))?;
+    // Size chunks by the file's average bitrate, with headroom for variable bitrate
+    let executor = executor.clone();
+    let audio_path = audio_path.to_string_lossy().into_owned();
+    let (duration, chunks) = run_blocking(move || {
+        let duration = executor.probe_duration(&audio_path)?;
+        let chunk_secs = duration * (WHISPER_MAX_UPLOAD_BYTES as f64 * 0.9) / size as f64;
+        Ok((duration, executor.split_audio(&audio_path, chunk_secs)?))
+    })
+    .await?;
 
-    // Convert to our format (use "timeline" as clip ID)
-    let transcript = whisper_to_transcript(whisper_response, timeline_id.clone());
+    let (from, to) = progress;
+    let mut responses = Vec::with_capacity(chunks.len());
+    let mut failed_ranges = Vec::new();
+    let mut last_error = None;
+    for (i, (path, start)) in chunks.iter().enumerate() {
+        let _ = window.emit("transcription-progress", serde_json::json!({
+            "clipId": clip_id,
+            "stage": "transcribing",
+            "percent": from + (to - from) * i as f64 / chunks.len() as f64,
+            "message": format!("Transcribing chunk {} of {}...", i + 1, chunks.len())
+        }));
+        match client.transcribe(path, config).await {
+            Ok(response) => responses.push((*start, response)),
+            Err(e) => {
+                let end = chunks.get(i + 1).map_or(duration, |(_, next)| *next);
+                eprintln!("⚠ Chunk {} of {} ({:.1}s-{:.1}s) failed to transcribe: {}", i + 1, chunks.len(), start, end, e);
+                failed_ranges.push(FailedRange { start: *start, end, message: e.to_string() });
+                last_error = Some(e);
+            }
+        }
+    }
 
-    // Emit completion
-    window.emit("transcription-progress", serde_json::json!({
-        "clipId": timeline_id,
-        "stage": "complete",
-        "percent": 100.0,
-        "message": "Timeline transcription complete!"
-    })).map_err(|e| format!("Failed to emit event: {}", e))?;
+    for (path, _) in &chunks {
+        let _ = tokio::fs::remove_file(path).await;
+    }
 
-    Ok(transcript)
+    match stitch_whisper_responses(responses) {
+        Some(stitched) => Ok((stitched, failed_ranges)),
+        None => Err(last_error
+            .unwrap_or_else(|| Error::process("Audio could not be split for transcription", ""))),
+    }
 }
 
 /// Export transcript to various formats
+/// For CSV, `include_words` also writes word timestamps to a `<name>.words.csv` file alongside
+/// For SRT/VTT, `granularity` chooses one cue per segment (default) or per word
 #[tauri::command]
 pub async fn export_transcript(
     transcript: Transcript,
     output_path: String,
     format: String,
-) -> Result<(), String> {
+    include_words: Option<bool>,
+    granularity: Option<CaptionGranularity>,
+) -> Result<(), Error> {
+    let granularity = granularity.unwrap_or_default();
+
     match format.as_str() {
+        "csv" => {
+            export_as_csv(&transcript, &output_path).await?;
+            if include_words.unwrap_or(false) {
+                let words_path = std::path::Path::new(&output_path).with_extension("words.csv");
+                export_words_as_csv(&transcript, &words_path.to_string_lossy()).await?;
+            }
+            Ok(())
+        }
         "txt" => export_as_txt(&transcript, &output_path).await,
-        "srt" => export_as_srt(&transcript, &output_path).await,
-        "vtt" => export_as_vtt(&transcript, &output_path).await,
+        "srt" => export_as_srt(&transcript, &output_path, granularity).await,
+        "vtt" => export_as_vtt(&transcript, &output_path, granularity).await,
         "json" => export_as_json(&transcript, &output_path).await,
-        _ => Err(format!("Unsupported format: {}", format)),
+        _ => Err(Error::InvalidInput(format!("Unsupported format: {}", format))),
     }
 }
 
@@ -0,0 +1,152 @@
+// Error Module
+//
+// Structured errors for media operations. Commands serialize these as
+// { kind, message } so the frontend can branch on the kind instead of
+// matching on message text.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// FFmpeg or FFprobe couldn't be located or launched
+    FfmpegNotFound(String),
+    /// FFmpeg or FFprobe ran but exited with an error
+    ProcessFailed { context: String, stderr: String },
+    /// The OS refused access to a file or capture device
+    PermissionDenied(String),
+    /// The request was rejected before anything ran
+    InvalidInput(String),
+    /// FFmpeg or FFprobe was killed for running past its deadline
+    Timeout(String),
+    /// The user stopped the operation before it finished
+    Cancelled(String),
+    /// The process an operation acts on (export, recording) isn't running
+    ProcessNotRunning(String),
+    /// whisper.cpp or its model couldn't be located
+    WhisperNotFound(String),
+    /// The transcription API couldn't be reached or rejected the request
+    Api(String),
+    /// Reading or writing a file, or spawning a process, failed
+    Io { context: String, source: std::io::Error },
+}
+
+impl Error {
+    /// Error for a process that exited unsuccessfully, classifying permission failures
+    pub fn process(context: impl Into<String>, stderr: impl AsRef<str>) -> Self {
+        let context = context.into();
+        let stderr = stderr.as_ref().trim().to_string();
+
+        if is_permission_error(&stderr) {
+            Error::PermissionDenied(format!("{}: {}", context, stderr))
+        } else {
+            Error::ProcessFailed { context, stderr }
+        }
+    }
+
+    /// Adapter for `map_err` on I/O results, e.g. `.map_err(Error::io("FFmpeg execution failed"))`
+    pub fn io(context: impl Into<String>) -> impl FnOnce(std::io::Error) -> Self {
+        let context = context.into();
+        move |source| match source.kind() {
+            std::io::ErrorKind::PermissionDenied => Error::PermissionDenied(format!("{}: {}", context, source)),
+            _ => Error::Io { context, source },
+        }
+    }
+
+    /// Stable identifier the frontend matches on
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::FfmpegNotFound(_) => "ffmpegNotFound",
+            Error::ProcessFailed { .. } => "processFailed",
+            Error::PermissionDenied(_) => "permissionDenied",
+            Error::InvalidInput(_) => "invalidInput",
+            Error::Timeout(_) => "timeout",
+            Error::Cancelled(_) => "cancelled",
+            Error::ProcessNotRunning(_) => "processNotRunning",
+            Error::WhisperNotFound(_) => "whisperNotFound",
+            Error::Api(_) => "api",
+            Error::Io { .. } => "io",
+        }
+    }
+}
+
+/// macOS reports missing screen/camera/microphone consent in several ways
+fn is_permission_error(stderr: &str) -> bool {
+    ["Permission denied", "No permission", "Operation not permitted", "not authorized"]
+        .iter()
+        .any(|needle| stderr.contains(needle))
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::FfmpegNotFound(message)
+            | Error::PermissionDenied(message)
+            | Error::InvalidInput(message)
+            | Error::Timeout(message)
+            | Error::Cancelled(message)
+            | Error::ProcessNotRunning(message)
+            | Error::WhisperNotFound(message)
+            | Error::Api(message) => write!(f, "{}", message),
+            Error::ProcessFailed { context, stderr } if stderr.is_empty() => write!(f, "{}", context),
+            Error::ProcessFailed { context, stderr } => write!(f, "{}: {}", context, stderr),
+            Error::Io { context, source } => write!(f, "{}: {}", context, source),
+        }
+    }
+}
+
+// std::io::Error isn't Clone, so an Io error is rebuilt from its kind and message
+impl Clone for Error {
+    fn clone(&self) -> Self {
+        match self {
+            Error::FfmpegNotFound(message) => Error::FfmpegNotFound(message.clone()),
+            Error::ProcessFailed { context, stderr } => Error::ProcessFailed {
+                context: context.clone(),
+                stderr: stderr.clone(),
+            },
+            Error::PermissionDenied(message) => Error::PermissionDenied(message.clone()),
+            Error::InvalidInput(message) => Error::InvalidInput(message.clone()),
+            Error::Timeout(message) => Error::Timeout(message.clone()),
+            Error::Cancelled(message) => Error::Cancelled(message.clone()),
+            Error::ProcessNotRunning(message) => Error::ProcessNotRunning(message.clone()),
+            Error::WhisperNotFound(message) => Error::WhisperNotFound(message.clone()),
+            Error::Api(message) => Error::Api(message.clone()),
+            Error::Io { context, source } => Error::Io {
+                context: context.clone(),
+                source: std::io::Error::new(source.kind(), source.to_string()),
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let stderr = match self {
+            Error::ProcessFailed { stderr, .. } => Some(stderr),
+            _ => None,
+        };
+
+        let mut state = serializer.serialize_struct("Error", 2 + stderr.is_some() as usize)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(stderr) = stderr {
+            state.serialize_field("stderr", stderr)?;
+        }
+        state.end()
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(source: std::io::Error) -> Self {
+        Error::io("I/O error")(source)
+    }
+}
@@ -3,16 +3,54 @@
 // Handles FFmpeg and FFprobe command execution for media operations.
 // Provides methods for metadata extraction, thumbnail generation, and video export.
 
-use std::process::Command;
-use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::path::{Path, PathBuf};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use crate::error::Error;
 
-// Audio format enum for transcription
-#[derive(Debug, Clone, Copy)]
+/// How long to wait for `-list_devices` before assuming a stuck device driver
+const DEVICE_LIST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a candidate binary gets to answer `-version` before it's treated as broken
+const BINARY_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default limit for an FFprobe metadata read; a corrupt or network-mounted file can hang it
+/// Override with FFMPEG_PROBE_TIMEOUT (seconds)
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default limit for extracting one thumbnail; override with FFMPEG_THUMBNAIL_TIMEOUT (seconds)
+const THUMBNAIL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Optional components compiled into the FFmpeg binary, probed once per run
+static CAPABILITIES: OnceLock<FfmpegCapabilities> = OnceLock::new();
+
+/// FFmpeg process of the export currently rendering
+/// The export takes it back when FFmpeg finishes; if cancel_export took it first,
+/// the export was cancelled. A process that already exited is left for the export
+static EXPORT_PROCESS: Mutex<Option<Child>> = Mutex::new(None);
+
+/// How long a cancelled export gets to quit on its own before being killed
+const EXPORT_CANCEL_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Audio format enum for transcription and audio-only exports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 pub enum AudioFormat {
+    /// Small lossy files; what the Whisper API is sent
+    #[default]
+    #[serde(rename = "mp3")]
     Mp3,
+    /// Lossless 16-bit PCM at the source sample rate
+    #[serde(rename = "wav")]
     Wav,
+    /// Lossless and compressed, for archival
+    #[serde(rename = "flac")]
+    Flac,
+    #[serde(rename = "m4a")]
     M4a,
 }
 
@@ -21,11 +59,83 @@ impl AudioFormat {
         match self {
             AudioFormat::Mp3 => "mp3",
             AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
             AudioFormat::M4a => "m4a",
         }
     }
 }
 
+// Image format enum for frame extraction
+#[derive(Debug, Clone, Copy)]
+pub enum ImageFormat {
+    Png,
+    Jpg,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpg => "jpg",
+        }
+    }
+}
+
+/// Which frames to keep when extracting an image sequence
+#[derive(Debug, Clone, Copy)]
+pub enum FrameSampling {
+    /// Keep every Nth decoded frame
+    EveryNthFrame(u32),
+    /// Keep one frame per interval, in seconds
+    Interval(f64),
+}
+
+/// Result of extracting an image sequence
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FrameExtraction {
+    pub count: u32,
+    #[serde(rename = "outputDir")]
+    pub output_dir: String,
+}
+
+/// Sprite sheet of evenly spaced frames for the timeline scrubber
+/// Cells are laid out left to right, top to bottom; frame `i` sits at column
+/// `i % columns`, row `i / columns`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Filmstrip {
+    pub path: String,
+    #[serde(rename = "cellWidth")]
+    pub cell_width: u32,
+    #[serde(rename = "cellHeight")]
+    pub cell_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+/// Thumbnails extracted per FFmpeg process by generate_thumbnails
+/// Each one opens the file as its own input, so batches stay modest
+const THUMBNAIL_BATCH_SIZE: usize = 32;
+
+/// Height of each filmstrip cell; widths follow the video's aspect ratio
+const FILMSTRIP_CELL_HEIGHT: u32 = 90;
+
+/// Cells per filmstrip row
+const FILMSTRIP_MAX_COLUMNS: u32 = 10;
+
+/// Most frames one filmstrip may hold
+const FILMSTRIP_MAX_FRAMES: u32 = 200;
+
+/// Size budget for each kind of generated image (filmstrips, waveforms) cached
+/// in the temp directory; the least recently used go first once it's exceeded
+const MEDIA_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Most scene changes detect_scene_changes returns
+const SCENE_CHANGES_MAX: usize = 500;
+
+/// Largest waveform image, in pixels
+const WAVEFORM_MAX_WIDTH: u32 = 8192;
+const WAVEFORM_MAX_HEIGHT: u32 = 1024;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MediaMetadata {
     pub duration: f64,
@@ -35,9 +145,17 @@ pub struct MediaMetadata {
     pub codec: String,
     pub bitrate: u64,
     pub file_size: u64,
+    /// Clockwise display rotation (0/90/180/270); width and height are as stored,
+    /// before rotation
+    pub rotation: u32,
+    /// Audio fields come from the first audio stream; None when the file has no audio
+    pub audio_codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub audio_bitrate: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipInfo {
     #[serde(rename = "filePath")]
     pub file_path: String,
@@ -48,748 +166,6109 @@ pub struct ClipInfo {
     pub trim_start: f64,
     #[serde(rename = "trimEnd")]
     pub trim_end: f64,
+    /// Seconds to hold the first frame before the clip starts playing
+    #[serde(rename = "holdStart", default)]
+    pub hold_start: f64,
+    /// Seconds to hold the last frame after the clip finishes playing
+    #[serde(rename = "holdEnd", default)]
+    pub hold_end: f64,
+    /// 0.0 (invisible) to 1.0 (opaque); only overlay-track clips can be partially
+    /// transparent, blending with the tracks beneath
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    /// Gain applied to the clip's audio, 0.0 (muted) to 2.0
+    /// Values above 1.0 boost the level and may clip loud passages
+    #[serde(default = "default_volume")]
+    pub volume: f64,
+    /// Playback speed: 2.0 plays twice as fast, 0.5 is half-speed slow motion
+    /// `duration` is still measured in source seconds; the clip occupies duration / speed
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+    /// Transition into this clip from the clip directly before it
+    /// Ignored when a gap separates the two clips
+    #[serde(default)]
+    pub transition: Option<Transition>,
+    /// Clockwise turn (0/90/180/270) on top of the file's own display rotation,
+    /// for footage shot sideways or upside down
+    #[serde(default)]
+    pub rotate: u32,
+    /// Mirror the clip, applied after `rotate`
+    #[serde(default)]
+    pub flip: Option<Flip>,
+    /// Region of the clip to keep, in pixels of the upright, rotated and flipped frame
+    #[serde(default)]
+    pub crop: Option<CropRect>,
+    /// Added to every pixel's brightness, -1.0 to 1.0 (0.0 leaves it unchanged)
+    #[serde(default)]
+    pub brightness: f64,
+    /// 0.0 (flat gray) to 2.0; 1.0 leaves the clip unchanged
+    #[serde(default = "default_contrast")]
+    pub contrast: f64,
+    /// 0.0 (grayscale) to 3.0; 1.0 leaves the clip unchanged
+    #[serde(default = "default_saturation")]
+    pub saturation: f64,
+    /// Play the clip backwards, sound included; limited to MAX_REVERSE_DURATION
+    #[serde(default)]
+    pub reverse: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CameraInfo {
-    pub index: u32,
-    pub name: String,
-}
+/// Longest clip (in source seconds) that can be reversed
+/// FFmpeg's reverse filter holds every decoded frame in memory: 20s of 1080p30 is about 2 GB
+pub const MAX_REVERSE_DURATION: f64 = 20.0;
 
-pub struct FFmpegExecutor {
-    ffmpeg_path: PathBuf,
-    ffprobe_path: PathBuf,
+/// A rectangle cut out of a clip's frame; the rest is discarded before scaling
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
 }
 
-impl FFmpegExecutor {
-    /// Creates a new FFmpegExecutor instance with bundled binary paths
-    /// Uses multi-strategy fallback: production bundle -> development -> system PATH
-    pub fn new() -> Result<Self, String> {
-        let mut attempted_paths = Vec::new();
-        
-        // Strategy 1: Production app bundle Resources directory
-        let exe_path = std::env::current_exe()
-            .map_err(|e| format!("Failed to get executable path: {}", e))?;
-        
-        let resources_binaries = exe_path
-            .parent()                          // Contents/MacOS/ -> Contents/
-            .and_then(|p| p.parent())          // Contents/ -> MyApp.app/
-            .and_then(|p| p.parent())          // MyApp.app/ -> parent dir
-            .map(|p| p.join("Contents").join("Resources").join("binaries"));
-        
-        if let Some(ref path) = resources_binaries {
-            attempted_paths.push(format!("Production Resources: {}", path.display()));
-            let ffmpeg = path.join("ffmpeg");
-            let ffprobe = path.join("ffprobe");
-            
-            if ffmpeg.exists() && ffprobe.exists() {
-                eprintln!("✓ Found FFmpeg binaries in production Resources:");
-                eprintln!("  ffmpeg:  {:?}", ffmpeg);
-                eprintln!("  ffprobe: {:?}", ffprobe);
-                return Ok(Self { 
-                    ffmpeg_path: ffmpeg, 
-                    ffprobe_path: ffprobe 
-                });
-            }
+impl CropRect {
+    /// Check the rectangle lies inside a `frame_width`x`frame_height` frame
+    fn validate_within(&self, frame_width: u32, frame_height: u32) -> Result<(), Error> {
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::InvalidInput(format!("Crop size must be at least 1x1, got {}x{}", self.width, self.height)));
         }
-        
-        // Strategy 2: Development manifest directory
-        if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
-            let binaries_dir = PathBuf::from(manifest_dir).join("binaries");
-            attempted_paths.push(format!("Development manifest: {}", binaries_dir.display()));
-            
-            let ffmpeg = binaries_dir.join("ffmpeg");
-            let ffprobe = binaries_dir.join("ffprobe");
-            
-            if ffmpeg.exists() && ffprobe.exists() {
-                eprintln!("✓ Found FFmpeg binaries in development:");
-                eprintln!("  ffmpeg:  {:?}", ffmpeg);
-                eprintln!("  ffprobe: {:?}", ffprobe);
-                return Ok(Self { 
-                    ffmpeg_path: ffmpeg, 
-                    ffprobe_path: ffprobe 
-                });
-            }
+        if u64::from(self.x) + u64::from(self.width) > u64::from(frame_width)
+            || u64::from(self.y) + u64::from(self.height) > u64::from(frame_height)
+        {
+            return Err(Error::InvalidInput(format!(
+                "Crop {}x{} at ({}, {}) extends past the {}x{} frame",
+                self.width, self.height, self.x, self.y, frame_width, frame_height
+            )));
         }
-        
-        // Strategy 3: System PATH (fallback)
-        attempted_paths.push("System PATH".to_string());
-        if let Ok(ffmpeg_path) = which::which("ffmpeg") {
-            if let Ok(ffprobe_path) = which::which("ffprobe") {
-                eprintln!("✓ Found FFmpeg binaries in system PATH:");
-                eprintln!("  ffmpeg:  {:?}", ffmpeg_path);
-                eprintln!("  ffprobe: {:?}", ffprobe_path);
-                return Ok(Self { ffmpeg_path, ffprobe_path });
-            }
+        Ok(())
+    }
+    
+    fn filter(&self) -> String {
+        format!("crop={}:{}:{}:{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+/// Direction a clip is mirrored in
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Flip {
+    /// Mirror left to right
+    #[serde(rename = "horizontal")]
+    Horizontal,
+    /// Mirror top to bottom
+    #[serde(rename = "vertical")]
+    Vertical,
+}
+
+/// How one clip hands over to the next
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Transition {
+    /// Blend the outgoing clip into the incoming one over `duration` seconds
+    /// The clips overlap by that long, so the export is shorter than the timeline by it
+    #[serde(rename = "crossfade")]
+    Crossfade { duration: f64 },
+}
+
+fn default_opacity() -> f64 {
+    1.0
+}
+
+fn default_volume() -> f64 {
+    1.0
+}
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+fn default_contrast() -> f64 {
+    1.0
+}
+
+fn default_saturation() -> f64 {
+    1.0
+}
+
+/// Largest tempo change a single atempo stage accepts (it takes 0.5–2.0)
+const ATEMPO_MAX: f64 = 2.0;
+
+impl ClipInfo {
+    /// Time the clip occupies on the timeline, including any held first or last frame
+    pub fn timeline_duration(&self) -> f64 {
+        self.duration / self.speed + self.hold_start + self.hold_end
+    }
+
+    /// Video filter retiming the clip to its speed, or nothing at normal speed
+    fn speed_filter(&self) -> String {
+        if self.speed == 1.0 {
+            String::new()
+        } else {
+            format!(",setpts=PTS/{}", self.speed)
         }
-        
-        // All strategies failed - provide detailed error
-        Err(format!(
-            "FFmpeg binaries not found. Attempted paths:\n{}",
-            attempted_paths.join("\n")
-        ))
+    }
+
+    /// Video filters turning the clip upright from the file's `rotation`, then applying
+    /// its own rotation, flip and crop, or nothing when none apply
+    fn orientation_filter(&self, rotation: u32) -> String {
+        orientation_filters(
+            (rotation + self.rotate) % 360,
+            self.flip == Some(Flip::Horizontal),
+            self.flip == Some(Flip::Vertical),
+        )
+        .into_iter()
+        .map(|filter| format!(",{}", filter))
+        .chain(self.crop.map(|crop| format!(",{}", crop.filter())))
+        .collect()
     }
     
-    /// Get metadata from a video file using FFprobe
-    pub fn get_metadata(&self, file_path: &str) -> Result<MediaMetadata, String> {
-        let output = Command::new(&self.ffprobe_path)
-            .args(&[
-                "-v", "quiet",
-                "-print_format", "json",
-                "-show_format",
-                "-show_streams",
-                file_path
-            ])
-            .output()
-            .map_err(|e| format!("FFprobe execution failed: {}", e))?;
-        
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let status_code = output.status.code().unwrap_or(-1);
-            return Err(format!(
-                "FFprobe failed (exit code: {}):\nStderr: {}\nStdout: {}\nCommand: ffprobe -v quiet -print_format json -show_format -show_streams \"{}\"",
-                status_code, stderr, stdout, file_path
-            ));
+    /// Video filter applying the clip's color adjustments, or nothing when they're neutral
+    fn color_filter(&self) -> String {
+        if self.brightness == 0.0 && self.contrast == 1.0 && self.saturation == 1.0 {
+            String::new()
+        } else {
+            format!(
+                ",eq=brightness={}:contrast={}:saturation={}",
+                self.brightness, self.contrast, self.saturation
+            )
         }
-        
-        let json: Value = serde_json::from_slice(&output.stdout)
-            .map_err(|e| format!("Failed to parse FFprobe output: {}", e))?;
-        
-        self.parse_metadata(json)
     }
     
-    /// Parse FFprobe JSON output into MediaMetadata
-    fn parse_metadata(&self, json: Value) -> Result<MediaMetadata, String> {
-        // Extract video stream info
-        let streams = json["streams"].as_array()
-            .ok_or("No streams found")?;
-        
-        let video_stream = streams.iter()
-            .find(|s| s["codec_type"].as_str() == Some("video"))
-            .ok_or("No video stream found")?;
-        
-        let duration = json["format"]["duration"]
-            .as_str()
-            .and_then(|s| s.parse::<f64>().ok())
-            .ok_or("Failed to parse duration")?;
-        
-        let width = video_stream["width"]
-            .as_u64()
-            .ok_or("Failed to parse width")? as u32;
-        
-        let height = video_stream["height"]
-            .as_u64()
-            .ok_or("Failed to parse height")? as u32;
-        
-        let fps_str = video_stream["r_frame_rate"].as_str()
-            .ok_or("Failed to get frame rate")?;
-        let fps = self.parse_fps(fps_str)?;
-        
-        let codec = video_stream["codec_name"]
-            .as_str()
-            .unwrap_or("unknown")
-            .to_string();
-        
-        let bitrate = json["format"]["bit_rate"]
-            .as_str()
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(0);
-        
-        let file_size = json["format"]["size"]
-            .as_str()
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(0);
-        
-        Ok(MediaMetadata {
-            duration,
-            width,
-            height,
-            fps,
-            codec,
-            bitrate,
-            file_size,
-        })
+    /// Video filter playing the clip backwards, or nothing
+    fn reverse_filter(&self) -> &'static str {
+        if self.reverse { ",reverse" } else { "" }
     }
     
-    /// Parse FPS string (handles fractional rates like "30000/1001")
-    fn parse_fps(&self, fps_str: &str) -> Result<f64, String> {
-        let parts: Vec<&str> = fps_str.split('/').collect();
-        if parts.len() == 2 {
-            let num = parts[0].parse::<f64>()
-                .map_err(|_| "Invalid FPS numerator")?;
-            let den = parts[1].parse::<f64>()
-                .map_err(|_| "Invalid FPS denominator")?;
-            if den == 0.0 {
-                return Err("FPS denominator cannot be zero".to_string());
-            }
-            Ok(num / den)
-        } else {
-            fps_str.parse::<f64>()
-                .map_err(|_| format!("Invalid FPS format: {}", fps_str))
+    /// Audio filter playing the clip's sound backwards, or nothing
+    fn areverse_filter(&self) -> &'static str {
+        if self.reverse { ",areverse" } else { "" }
+    }
+    
+    /// Audio filters retiming the clip to its speed without changing pitch
+    /// atempo only covers 0.5–2.0 per stage, so larger changes chain several stages
+    fn tempo_filters(&self) -> String {
+        if self.speed == 1.0 {
+            return String::new();
+        }
+        let mut filters = String::new();
+        let mut remaining = self.speed;
+        while remaining > ATEMPO_MAX {
+            filters.push_str(&format!(",atempo={}", ATEMPO_MAX));
+            remaining /= ATEMPO_MAX;
+        }
+        while remaining < 1.0 / ATEMPO_MAX {
+            filters.push_str(&format!(",atempo={}", 1.0 / ATEMPO_MAX));
+            remaining *= ATEMPO_MAX;
+        }
+        filters.push_str(&format!(",atempo={}", remaining));
+        filters
+    }
+}
+
+/// How a gap on the timeline is filled in the exported video
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum GapFill {
+    #[default]
+    #[serde(rename = "black")]
+    Black,
+    /// Solid color: an FFmpeg color name or "#rrggbb"
+    #[serde(rename = "color")]
+    Color { color: String },
+    /// Hold the last frame of the clip before the gap, or the first frame of
+    /// the next clip when nothing precedes it
+    #[serde(rename = "freeze")]
+    Freeze,
+    /// Show a still image for the length of the gap
+    #[serde(rename = "image")]
+    Image { path: String },
+}
+
+/// An explicit gap entry; timeline time covered by neither a clip nor a gap is filled black
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GapSpec {
+    #[serde(rename = "startTime")]
+    pub start_time: f64,
+    pub duration: f64,
+    #[serde(default)]
+    pub fill: GapFill,
+}
+
+/// One piece of the exported timeline, in playback order
+enum Segment<'a> {
+    /// `input` is the clip's FFmpeg input index; `crossfade` is the overlap with the
+    /// clip before it, set only when the two are adjacent
+    Clip { input: usize, clip: &'a ClipInfo, crossfade: Option<f64> },
+    Gap { start_time: f64, duration: f64, fill: GapFill },
+}
+
+impl Segment<'_> {
+    fn crossfade(&self) -> Option<f64> {
+        match self {
+            Segment::Clip { crossfade, .. } => *crossfade,
+            Segment::Gap { .. } => None,
         }
     }
     
-    /// Generate a thumbnail at a specific timestamp
-    pub fn generate_thumbnail(
-        &self,
-        file_path: &str,
-        timestamp: f64,
-        output_path: &str
-    ) -> Result<(), String> {
-        let output = Command::new(&self.ffmpeg_path)
-            .args(&[
-                "-ss", &timestamp.to_string(),
-                "-i", file_path,
-                "-vframes", "1",
-                "-q:v", "2",
-                "-f", "image2",
-                output_path
-            ])
-            .output()
-            .map_err(|e| format!("FFmpeg execution failed: {}", e))?;
-        
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("Thumbnail generation failed: {}", stderr));
+    fn image_path(&self) -> Option<&str> {
+        match self {
+            Segment::Gap { fill: GapFill::Image { path }, .. } => Some(path),
+            _ => None,
         }
-        
-        Ok(())
     }
+}
+
+/// Interleave clips with explicit and implicit gaps into a single ordered segment list
+fn timeline_segments<'a>(
+    clips: &'a [ClipInfo],
+    gaps: &[GapSpec],
+    composition_length: f64,
+) -> Result<Vec<Segment<'a>>, Error> {
+    // Explicit gaps come from UI arithmetic, so allow a millisecond of float drift at the edges
+    const OVERLAP_TOLERANCE: f64 = 0.001;
     
-    /// Export video with clips and settings
-    pub fn export_video(
-        &self,
-        clips: &[ClipInfo],
-        output_path: &str,
-        resolution: &str,
-        fps: u32,
-        composition_length: f64
-    ) -> Result<(), String> {
-        if clips.is_empty() {
-            return Err("No clips to export".to_string());
+    for gap in gaps {
+        if !gap.start_time.is_finite() || gap.start_time < 0.0 || !gap.duration.is_finite() || gap.duration <= 0.0 {
+            return Err(Error::InvalidInput(format!("Invalid gap at {}s lasting {}s", gap.start_time, gap.duration)));
+        }
+        if let GapFill::Color { color } = &gap.fill {
+            validate_color(color)?;
+        }
+    }
+    
+    let mut entries: Vec<(f64, f64, Segment<'a>)> = clips
+        .iter()
+        .enumerate()
+        .map(|(input, clip)| {
+            let segment = Segment::Clip { input, clip, crossfade: None };
+            (clip.start_time, clip.start_time + clip.timeline_duration(), segment)
+        })
+        .chain(gaps.iter().map(|gap| {
+            let segment = Segment::Gap { start_time: gap.start_time, duration: gap.duration, fill: gap.fill.clone() };
+            (gap.start_time, gap.start_time + gap.duration, segment)
+        }))
+        .collect();
+    entries.sort_by(|a, b| a.0.total_cmp(&b.0));
+    
+    let mut segments = Vec::new();
+    let mut current_time = 0.0;
+    let mut previous_is_gap = false;
+    
+    for (start, end, mut segment) in entries {
+        let is_gap = matches!(segment, Segment::Gap { .. });
+        if (is_gap || previous_is_gap) && start < current_time - OVERLAP_TOLERANCE {
+            return Err(Error::InvalidInput(format!("Gap overlapping {}s conflicts with another timeline item", start)));
         }
         
-        // Create FFmpeg filter complex for concatenation and trimming
-        let filter_complex = self.build_filter_complex(clips, resolution, fps, composition_length)?;
-        
-        let mut args = vec![
-            "-y".to_string(), // Overwrite output
-        ];
-        
-        // Add input files
-        for clip in clips {
-            args.push("-i".to_string());
-            args.push(clip.file_path.clone());
+        // Crossfades only join clips that touch; after a gap the transition is a plain cut
+        if let Segment::Clip { clip, crossfade, .. } = &mut segment {
+            let previous = match segments.last() {
+                Some(Segment::Clip { clip, crossfade, .. }) if start <= current_time + OVERLAP_TOLERANCE => {
+                    Some((*clip, crossfade.unwrap_or(0.0)))
+                }
+                _ => None,
+            };
+            if let (Some(Transition::Crossfade { duration }), Some((previous, previous_crossfade))) =
+                (&clip.transition, previous)
+            {
+                if *duration > clip.timeline_duration() {
+                    return Err(Error::InvalidInput(format!(
+                        "Crossfade at {}s ({}s) is longer than the incoming clip ({}s)",
+                        start, duration, clip.timeline_duration()
+                    )));
+                }
+                if *duration + previous_crossfade > previous.timeline_duration() {
+                    return Err(Error::InvalidInput(format!(
+                        "Crossfade at {}s ({}s) is longer than what remains of the outgoing clip ({}s)",
+                        start, duration, previous.timeline_duration() - previous_crossfade
+                    )));
+                }
+                *crossfade = Some(*duration);
+            }
         }
         
-        // Add filter complex
-        args.push("-filter_complex".to_string());
-        args.push(filter_complex);
+        if start > current_time {
+            segments.push(Segment::Gap { start_time: current_time, duration: start - current_time, fill: GapFill::Black });
+        }
+        segments.push(segment);
+        
+        current_time = end;
+        previous_is_gap = is_gap;
+    }
+    
+    // Fill to the composition length if needed
+    if current_time < composition_length {
+        segments.push(Segment::Gap {
+            start_time: current_time,
+            duration: composition_length - current_time,
+            fill: GapFill::Black,
+        });
+    }
+    
+    Ok(segments)
+}
+
+/// Stream facts about a timeline clip, probed once per export
+struct ClipProbe {
+    has_audio: bool,
+    /// Clockwise display rotation, applied in the filtergraph
+    rotation: u32,
+    /// Source frame rate; 0 for stills and streams that don't report one
+    fps: f64,
+}
+
+impl ClipProbe {
+    /// Whether the clip, played at `speed`, has fewer frames per second than the export
+    /// Only then does interpolation add frames; a faster clip just has frames dropped
+    fn below_frame_rate(&self, speed: f64, fps: u32) -> bool {
+        self.fps > 0.0 && self.fps * speed < fps as f64
+    }
+}
+
+/// File extensions treated as still images rather than video
+const STILL_IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "bmp", "webp", "tiff"];
+
+/// Whether a clip's file is a still image (e.g. a title card or logo screen)
+fn is_still_image(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| STILL_IMAGE_EXTENSIONS.iter().any(|still| ext.eq_ignore_ascii_case(still)))
+}
+
+/// Input arguments for a timeline render: every clip, then the still images
+/// for image-filled gaps in timeline order, then any background music, matching
+/// build_filter_complex's input indices
+/// Clips are decoded unrotated; build_filter_complex applies their rotation itself
+/// Still-image clips are looped at the output frame rate for the clip's duration
+fn timeline_input_args(clips: &[ClipInfo], segments: &[Segment], fps: u32, options: &ExportOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    for clip in clips {
+        args.extend(clip_input_args(clip, fps));
+    }
+    for path in segments.iter().filter_map(Segment::image_path) {
+        args.extend_from_slice(&[
+            "-loop".to_string(),
+            "1".to_string(),
+            "-framerate".to_string(),
+            fps.to_string(),
+            "-i".to_string(),
+            path.to_string(),
+        ]);
+    }
+    if let Some(path) = &options.music_path {
+        // Loop so short tracks keep playing; the filtergraph cuts it to the composition
+        args.extend_from_slice(&[
+            "-stream_loop".to_string(),
+            "-1".to_string(),
+            "-i".to_string(),
+            path.to_string(),
+        ]);
+    }
+    if let Some(watermark) = &options.watermark {
+        args.extend_from_slice(&["-i".to_string(), watermark.path.clone()]);
+    }
+    for overlay in options.overlay_tracks.iter().flatten() {
+        args.extend(clip_input_args(&overlay.clip, fps));
+    }
+    args
+}
+
+/// Input arguments for one timeline clip; stills are looped for the clip's duration
+fn clip_input_args(clip: &ClipInfo, fps: u32) -> Vec<String> {
+    let mut args = vec!["-noautorotate".to_string()];
+    if is_still_image(&clip.file_path) {
+        args.extend_from_slice(&[
+            "-loop".to_string(),
+            "1".to_string(),
+            "-framerate".to_string(),
+            fps.to_string(),
+            "-t".to_string(),
+            clip.duration.to_string(),
+        ]);
+    }
+    args.push("-i".to_string());
+    args.push(clip.file_path.clone());
+    args
+}
+
+/// Total time removed from the export by crossfade overlaps
+fn crossfade_overlap(segments: &[Segment]) -> f64 {
+    segments.iter().filter_map(Segment::crossfade).sum()
+}
+
+/// Where a timeline time lands in the render
+/// Crossfades that start before it pull later material earlier
+fn render_time(segments: &[Segment], timeline_time: f64) -> f64 {
+    let overlap_before: f64 = segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Clip { clip, crossfade: Some(duration), .. } if clip.start_time <= timeline_time => Some(*duration),
+            _ => None,
+        })
+        .sum();
+    (timeline_time - overlap_before).max(0.0)
+}
+
+/// minterpolate settings for smooth motion: motion-compensated, bidirectional estimation
+fn interpolation_filter(fps: u32) -> String {
+    format!("minterpolate=fps={}:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1", fps)
+}
+
+/// Reject clips with timing values FFmpeg filters can't use (NaN, infinite, negative)
+pub fn validate_clips(clips: &[ClipInfo]) -> Result<(), Error> {
+    for (i, clip) in clips.iter().enumerate() {
+        let fields = [
+            ("startTime", clip.start_time),
+            ("duration", clip.duration),
+            ("trimStart", clip.trim_start),
+            ("trimEnd", clip.trim_end),
+            ("holdStart", clip.hold_start),
+            ("holdEnd", clip.hold_end),
+        ];
+        for (name, value) in fields {
+            if !value.is_finite() || value < 0.0 {
+                return Err(Error::InvalidInput(format!("Invalid {} for clip {}: {}", name, i, value)));
+            }
+        }
+        if clip.duration == 0.0 {
+            return Err(Error::InvalidInput(format!("Clip {} has zero duration", i)));
+        }
+        if !(0.0..=1.0).contains(&clip.opacity) {
+            return Err(Error::InvalidInput(format!("Opacity for clip {} must be between 0.0 and 1.0, got {}", i, clip.opacity)));
+        }
+        if !clip.speed.is_finite() || clip.speed <= 0.0 {
+            return Err(Error::InvalidInput(format!("Speed for clip {} must be greater than 0, got {}", i, clip.speed)));
+        }
+        if !(0.0..=2.0).contains(&clip.volume) {
+            return Err(Error::InvalidInput(format!("Volume for clip {} must be between 0.0 and 2.0, got {}", i, clip.volume)));
+        }
+        if !(-1.0..=1.0).contains(&clip.brightness) {
+            return Err(Error::InvalidInput(format!("Brightness for clip {} must be between -1.0 and 1.0, got {}", i, clip.brightness)));
+        }
+        if !(0.0..=2.0).contains(&clip.contrast) {
+            return Err(Error::InvalidInput(format!("Contrast for clip {} must be between 0.0 and 2.0, got {}", i, clip.contrast)));
+        }
+        if !(0.0..=3.0).contains(&clip.saturation) {
+            return Err(Error::InvalidInput(format!("Saturation for clip {} must be between 0.0 and 3.0, got {}", i, clip.saturation)));
+        }
+        if clip.reverse && clip.duration > MAX_REVERSE_DURATION {
+            return Err(Error::InvalidInput(format!(
+                "Clip {} is {}s long; only clips up to {}s can be reversed",
+                i, clip.duration, MAX_REVERSE_DURATION
+            )));
+        }
+        if validate_rotation(clip.rotate).is_err() {
+            return Err(Error::InvalidInput(format!("Rotation for clip {} must be 0, 90, 180 or 270 degrees, got {}", i, clip.rotate)));
+        }
+        if let Some(Transition::Crossfade { duration }) = clip.transition {
+            if !duration.is_finite() || duration <= 0.0 {
+                return Err(Error::InvalidInput(format!("Invalid crossfade duration for clip {}: {}", i, duration)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// validate_clips for the main track, which has nothing beneath it to show through,
+/// so its clips must be opaque
+fn validate_main_track(clips: &[ClipInfo]) -> Result<(), Error> {
+    validate_clips(clips)?;
+    match clips.iter().position(|clip| clip.opacity < 1.0) {
+        Some(i) => Err(Error::InvalidInput(format!(
+            "Clip {} is on the main track, which can't be transparent; put it on an overlay track to change its opacity",
+            i
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Check a color grading LUT is an existing .cube file
+fn validate_lut_path(path: &str) -> Result<(), Error> {
+    let is_cube = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cube"));
+    if !is_cube {
+        return Err(Error::InvalidInput(format!("LUT must be a .cube file, got {}", path)));
+    }
+    if !Path::new(path).is_file() {
+        return Err(Error::InvalidInput(format!("LUT file not found: {}", path)));
+    }
+    Ok(())
+}
+
+/// Check FFmpeg will be able to write `output_path` before starting a long job
+/// The parent folder must already exist; writability is probed with a temporary file
+pub fn validate_output_path(output_path: &str) -> Result<(), Error> {
+    let path = Path::new(output_path);
+    if output_path.trim().is_empty() {
+        return Err(Error::InvalidInput("Output path is empty".to_string()));
+    }
+    if path.is_dir() {
+        return Err(Error::InvalidInput(format!("Output path is a folder, not a file: {}", output_path)));
+    }
+    
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if !parent.is_dir() {
+        return Err(Error::InvalidInput(format!("Output folder doesn't exist: {}", parent.display())));
+    }
+    
+    let probe = parent.join(format!(".write_test_{}", uuid::Uuid::new_v4()));
+    std::fs::File::create(&probe)
+        .map_err(Error::io(format!("Output folder isn't writable: {}", parent.display())))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Borrow a path as UTF-8 for an FFmpeg argument
+pub(crate) fn path_str(path: &Path) -> Result<&str, Error> {
+    path.to_str().ok_or_else(|| Error::Io {
+        context: format!("Path isn't valid UTF-8: {}", path.display()),
+        source: std::io::ErrorKind::InvalidData.into(),
+    })
+}
+
+/// Shortest composition that contains every clip, i.e. the end of the last clip
+pub fn compute_composition_length(clips: &[ClipInfo]) -> f64 {
+    clips
+        .iter()
+        .map(|clip| clip.start_time + clip.timeline_duration())
+        .fold(0.0, f64::max)
+}
+
+/// Color metadata tagged on exported video so players interpret colors consistently
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum ColorTagging {
+    /// Rec. 709 SDR in limited (TV) range - what browsers and most players expect
+    #[default]
+    #[serde(rename = "bt709")]
+    Bt709,
+    /// Rec. 2020 primaries with the PQ (SMPTE 2084) transfer curve for HDR exports
+    #[serde(rename = "bt2020-pq")]
+    Bt2020Pq,
+    /// Leave color metadata untagged
+    #[serde(rename = "none")]
+    Untagged,
+}
+
+impl ColorTagging {
+    /// PQ only holds up at 10 bits; 8-bit output bands visibly
+    pub fn is_hdr(&self) -> bool {
+        matches!(self, ColorTagging::Bt2020Pq)
+    }
+
+    /// FFmpeg output arguments that tag the stream's color properties
+    pub fn output_args(&self) -> Vec<String> {
+        let (colorspace, primaries, transfer) = match self {
+            ColorTagging::Bt709 => ("bt709", "bt709", "bt709"),
+            ColorTagging::Bt2020Pq => ("bt2020nc", "bt2020", "smpte2084"),
+            ColorTagging::Untagged => return Vec::new(),
+        };
+
+        vec![
+            "-colorspace".to_string(),
+            colorspace.to_string(),
+            "-color_primaries".to_string(),
+            primaries.to_string(),
+            "-color_trc".to_string(),
+            transfer.to_string(),
+            "-color_range".to_string(),
+            "tv".to_string(),
+        ]
+    }
+}
+
+/// Video codec for the main export output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum VideoCodec {
+    /// H.264 in MP4 - small files that play everywhere
+    #[default]
+    #[serde(rename = "h264")]
+    H264,
+    /// H.265/HEVC - roughly half the size of H.264 at the same quality, slower to encode
+    #[serde(rename = "hevc")]
+    Hevc,
+    /// VP9 in WebM with Opus audio, for the web
+    #[serde(rename = "vp9")]
+    Vp9,
+    /// Apple ProRes 422 HQ in MOV with PCM audio, for handing off to Final Cut/Premiere
+    /// An intermediate codec: expect roughly 10x the size of H.264
+    /// (about 220 Mbit/s at 1080p30)
+    #[serde(rename = "prores")]
+    ProRes,
+}
+
+impl VideoCodec {
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "libx264",
+            VideoCodec::Hevc => "libx265",
+            VideoCodec::Vp9 => "libvpx-vp9",
+            VideoCodec::ProRes => "prores_ks",
+        }
+    }
+
+    /// VideoToolbox (macOS hardware) encoder for this codec, if there is one
+    pub fn hardware_encoder(&self) -> Option<&'static str> {
+        match self {
+            VideoCodec::H264 => Some("h264_videotoolbox"),
+            VideoCodec::Hevc => Some("hevc_videotoolbox"),
+            VideoCodec::Vp9 | VideoCodec::ProRes => None,
+        }
+    }
+
+    /// Reject output containers the codec can't be written to reliably
+    pub fn validate_container(&self, output_path: &str) -> Result<(), Error> {
+        let extension = Path::new(output_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_default();
+
+        let allowed: &[&str] = match self {
+            VideoCodec::H264 | VideoCodec::Hevc => &["mp4", "mov", "mkv"],
+            VideoCodec::Vp9 => &["webm", "mkv"],
+            VideoCodec::ProRes => &["mov"],
+        };
+        if !allowed.contains(&extension.as_str()) {
+            return Err(Error::InvalidInput(format!(
+                "{} exports must be saved as {}, got {}",
+                self.encoder(),
+                allowed.iter().map(|ext| format!(".{}", ext)).collect::<Vec<_>>().join(" or "),
+                output_path
+            )));
+        }
+        Ok(())
+    }
+
+    /// Video and audio encoder arguments for the main output
+    /// ProRes has fixed-quality profiles, so `quality` doesn't apply to it;
+    /// VP9 takes the CRF and turns the x264-style preset into an encoder speed
+    pub fn output_args(&self, quality: &ExportQuality) -> Result<Vec<String>, Error> {
+        let crf = quality.crf.to_string();
+        let (vp9_deadline, vp9_cpu_used) = match self {
+            VideoCodec::Vp9 => quality.vp9_speed()?,
+            _ => ("", ""),
+        };
+        let args: &[&str] = match self {
+            VideoCodec::H264 => &[
+                "-c:v", "libx264",
+                "-preset", &quality.preset,
+                "-crf", &crf,
+            ],
+            VideoCodec::Hevc => &[
+                "-c:v", "libx265",
+                "-preset", &quality.preset,
+                "-crf", &crf,
+                "-tag:v", "hvc1", // QuickTime and Safari only play HEVC tagged hvc1
+            ],
+            VideoCodec::Vp9 => &[
+                "-c:v", "libvpx-vp9",
+                "-crf", &crf,
+                "-b:v", "0", // Constant quality mode
+                "-deadline", vp9_deadline,
+                "-cpu-used", vp9_cpu_used,
+                "-row-mt", "1",
+            ],
+            VideoCodec::ProRes => &[
+                "-c:v", "prores_ks",
+                "-profile:v", "3", // 422 HQ
+                "-vendor", "apl0", // Identify as Apple so NLEs trust the stream
+                "-pix_fmt", "yuv422p10le",
+            ],
+        };
+        let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        args.extend(self.audio_args());
+        Ok(args)
+    }
+
+    /// Arguments for a 10-bit encode, or None if the codec is only encoded at 8 bits here
+    /// (libx264 builds are usually 8-bit only)
+    pub fn ten_bit_args(&self) -> Option<Vec<String>> {
+        let args: &[&str] = match self {
+            VideoCodec::H264 => return None,
+            VideoCodec::Hevc => &["-pix_fmt", "yuv420p10le", "-profile:v", "main10"],
+            VideoCodec::Vp9 => &["-pix_fmt", "yuv420p10le", "-profile:v", "2"],
+            // output_args already writes yuv422p10le
+            VideoCodec::ProRes => &[],
+        };
+        Some(args.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    /// Audio encoder arguments that go with this codec's container
+    pub fn audio_args(&self) -> Vec<String> {
+        match self {
+            VideoCodec::H264 | VideoCodec::Hevc => vec![
+                "-c:a".to_string(),
+                "aac".to_string(),
+                "-b:a".to_string(),
+                AAC_AUDIO_BITRATE.to_string(),
+            ],
+            // WebM doesn't allow AAC
+            VideoCodec::Vp9 => vec!["-c:a".to_string(), "libopus".to_string(), "-b:a".to_string(), "160k".to_string()],
+            VideoCodec::ProRes => vec!["-c:a".to_string(), "pcm_s16le".to_string()],
+        }
+    }
+}
+
+/// Video bitrate for hardware exports when none is given (good for 1080p30)
+const DEFAULT_HARDWARE_BITRATE: u64 = 10_000_000;
+
+/// x264 speed/compression presets, fastest first
+const X264_PRESETS: &[&str] = &[
+    "ultrafast", "superfast", "veryfast", "faster", "fast",
+    "medium", "slow", "slower", "veryslow", "placebo",
+];
+
+/// libvpx-vp9 `-deadline` and `-cpu-used` standing in for each x264 preset,
+/// in X264_PRESETS order, since VP9 has no presets of its own
+const VP9_SPEEDS: [(&str, &str); 10] = [
+    ("good", "5"), ("good", "5"), ("good", "4"), ("good", "4"), ("good", "3"),
+    ("good", "2"), ("good", "1"), ("good", "1"), ("good", "0"), ("best", "0"),
+];
+
+/// Share of export progress, in percent, given to the loudness analysis pass,
+/// which only decodes and mixes the audio
+const LOUDNESS_ANALYSIS_PROGRESS: f64 = 10.0;
+
+/// Share of export progress, in percent, given to the stabilization analysis pass,
+/// which decodes every frame of the clip but encodes nothing
+const STABILIZATION_ANALYSIS_PROGRESS: f64 = 30.0;
+
+/// Audio bitrate of AAC (H.264 and HEVC) exports, also counted against a target file size
+const AAC_AUDIO_BITRATE: u64 = 192_000;
+
+/// Lowest video bitrate a target-size export will encode at before the picture falls apart
+const MIN_TARGET_VIDEO_BITRATE: u64 = 100_000;
+
+/// Share of a target file size left for the picture and sound after container overhead
+const TARGET_SIZE_PAYLOAD_SHARE: f64 = 0.98;
+
+/// Video bitrate that fits `composition_length` seconds into `target_size_mb` megabytes
+pub fn target_video_bitrate(target_size_mb: f64, composition_length: f64, with_audio: bool) -> Result<u64, Error> {
+    if !target_size_mb.is_finite() || target_size_mb <= 0.0 {
+        return Err(Error::InvalidInput(format!("Invalid target size: {} MB", target_size_mb)));
+    }
+    if composition_length <= 0.0 {
+        return Err(Error::InvalidInput("Can't target a file size for an empty composition".to_string()));
+    }
+    
+    let audio_bitrate = if with_audio { AAC_AUDIO_BITRATE } else { 0 } as f64;
+    let total_bitrate = target_size_mb * 8_000_000.0 * TARGET_SIZE_PAYLOAD_SHARE / composition_length;
+    let video_bitrate = total_bitrate - audio_bitrate;
+    if video_bitrate < MIN_TARGET_VIDEO_BITRATE as f64 {
+        let minimum_mb = (MIN_TARGET_VIDEO_BITRATE as f64 + audio_bitrate) * composition_length
+            / (8_000_000.0 * TARGET_SIZE_PAYLOAD_SHARE);
+        return Err(Error::InvalidInput(format!(
+            "A {} MB target is too small for {:.1}s of video; use at least {:.1} MB",
+            target_size_mb, composition_length, minimum_mb.ceil()
+        )));
+    }
+    Ok(video_bitrate as u64)
+}
+
+/// libx264 arguments for one pass of a two-pass encode at `bitrate` bits per second
+fn x264_pass_args(quality: &ExportQuality, bitrate: u64, passlog: &Path, pass: u8) -> Vec<String> {
+    vec![
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-preset".to_string(),
+        quality.preset.clone(),
+        "-b:v".to_string(),
+        bitrate.to_string(),
+        "-pass".to_string(),
+        pass.to_string(),
+        "-passlogfile".to_string(),
+        passlog.to_string_lossy().to_string(),
+    ]
+}
+
+/// Delete the stats files x264 writes next to a pass log prefix
+fn remove_pass_logs(passlog: &Path) {
+    for suffix in ["-0.log", "-0.log.mbtree"] {
+        let mut path = passlog.as_os_str().to_owned();
+        path.push(suffix);
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Kind of file an export writes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    /// Encoded video with audio, using `codec`
+    #[default]
+    #[serde(rename = "video")]
+    Video,
+    /// Silent animated GIF at a reduced frame rate and width, for short clips
+    #[serde(rename = "gif")]
+    Gif,
+    /// The timeline's mixed audio only, with silence for gaps
+    #[serde(rename = "audio")]
+    Audio,
+}
+
+/// Highest export frame rate accepted
+pub const MAX_EXPORT_FPS: u32 = 240;
+
+/// How far (as a ratio either way) a clip's frame rate can be from the export's before
+/// export_video warns about it
+const FPS_MISMATCH_RATIO: f64 = 2.0;
+
+/// Longest composition exported as a GIF; file sizes grow quickly beyond this
+pub const MAX_GIF_DURATION: f64 = 30.0;
+
+/// Frame rate and size for GIF exports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GifSettings {
+    pub fps: u32,
+    /// Output width in pixels; height follows the aspect ratio
+    pub width: u32,
+}
+
+impl Default for GifSettings {
+    fn default() -> Self {
+        Self { fps: 12, width: 480 }
+    }
+}
+
+impl GifSettings {
+    pub fn validate(&self) -> Result<(), Error> {
+        if !(1..=30).contains(&self.fps) {
+            return Err(Error::InvalidInput(format!("GIF frame rate must be between 1 and 30, got {}", self.fps)));
+        }
+        if !(16..=1920).contains(&self.width) {
+            return Err(Error::InvalidInput(format!("GIF width must be between 16 and 1920, got {}", self.width)));
+        }
+        Ok(())
+    }
+
+    /// Reduce the composed video and map it onto a palette generated from the
+    /// clip itself, which looks far better than GIF's default 256-color palette
+    fn filter(&self) -> String {
+        format!(
+            "fps={},scale={}:-1:flags=lanczos,split[gifsrc][gifpal];\
+             [gifpal]palettegen=stats_mode=diff[palette];\
+             [gifsrc][palette]paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle",
+            self.fps, self.width
+        )
+    }
+}
+
+/// Codec and quality for audio-only exports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioExportSettings {
+    /// MP3 (libmp3lame), WAV (16-bit PCM), FLAC or M4A (AAC)
+    pub format: AudioFormat,
+    /// Target bitrate, e.g. "192k" (default); ignored for lossless WAV and FLAC
+    pub bitrate: Option<String>,
+    #[serde(rename = "sampleRate")]
+    pub sample_rate: u32,
+}
+
+impl Default for AudioExportSettings {
+    fn default() -> Self {
+        Self {
+            format: AudioFormat::Mp3,
+            bitrate: None,
+            sample_rate: 48000,
+        }
+    }
+}
+
+impl AudioExportSettings {
+    pub fn validate(&self, output_path: &str) -> Result<(), Error> {
+        let extension = self.format.extension();
+        if !output_path.to_lowercase().ends_with(&format!(".{}", extension)) {
+            return Err(Error::InvalidInput(format!("{} audio exports must be saved as .{}, got {}", extension.to_uppercase(), extension, output_path)));
+        }
+        if !(8000..=192000).contains(&self.sample_rate) {
+            return Err(Error::InvalidInput(format!("Sample rate must be between 8000 and 192000 Hz, got {}", self.sample_rate)));
+        }
+        self.bitrate.as_deref().map(parse_bitrate).transpose()?;
+        Ok(())
+    }
+
+    /// Audio encoder arguments for the output
+    fn output_args(&self) -> Vec<String> {
+        let bitrate = self.bitrate.clone().unwrap_or_else(|| "192k".to_string());
+        let mut args = match self.format {
+            AudioFormat::Mp3 => vec!["-c:a".to_string(), "libmp3lame".to_string(), "-b:a".to_string(), bitrate],
+            AudioFormat::Wav => vec!["-c:a".to_string(), "pcm_s16le".to_string()],
+            AudioFormat::Flac => vec!["-c:a".to_string(), "flac".to_string()],
+            AudioFormat::M4a => vec!["-c:a".to_string(), "aac".to_string(), "-b:a".to_string(), bitrate],
+        };
+        args.extend(["-ar".to_string(), self.sample_rate.to_string()]);
+        args
+    }
+}
+
+/// H.264 encoding quality for exports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportQuality {
+    /// Constant rate factor: 0 (lossless) to 51 (worst) for H.264/HEVC, where 18-28
+    /// is the useful range; 0 to 63 for VP9, where 24-36 is
+    pub crf: u8,
+    /// x264 preset: slower presets give smaller files at the same quality
+    /// VP9 maps it to a similar encoder speed; unknown names fall back to "medium"
+    pub preset: String,
+}
+
+impl Default for ExportQuality {
+    fn default() -> Self {
+        Self {
+            crf: 23,
+            preset: "medium".to_string(),
+        }
+    }
+}
+
+impl ExportQuality {
+    /// Check the CRF and preset against what `codec` accepts
+    /// ProRes ignores both; VP9 maps the preset to an encoder speed (see VP9_SPEEDS)
+    pub fn validate(&self, codec: VideoCodec) -> Result<(), Error> {
+        let max_crf = match codec {
+            VideoCodec::H264 | VideoCodec::Hevc => 51,
+            VideoCodec::Vp9 => 63,
+            VideoCodec::ProRes => return Ok(()),
+        };
+        if self.crf > max_crf {
+            return Err(Error::InvalidInput(format!("{} CRF must be between 0 and {}, got {}", codec.encoder(), max_crf, self.crf)));
+        }
+        self.preset_index().map(|_| ())
+    }
+
+    /// Position of the preset in X264_PRESETS
+    fn preset_index(&self) -> Result<usize, Error> {
+        X264_PRESETS
+            .iter()
+            .position(|preset| *preset == self.preset)
+            .ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Invalid preset \"{}\". Expected one of: {}",
+                    self.preset,
+                    X264_PRESETS.join(", ")
+                ))
+            })
+    }
+    
+    /// VP9 `-deadline` and `-cpu-used` values matching the preset's speed
+    fn vp9_speed(&self) -> Result<(&'static str, &'static str), Error> {
+        self.preset_index().map(|i| VP9_SPEEDS[i])
+    }
+}
+
+/// Scheduling priority for long-running FFmpeg jobs such as exports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum JobPriority {
+    #[default]
+    #[serde(rename = "normal")]
+    Normal,
+    /// Yield the CPU to interactive apps; the job still uses idle cores fully
+    #[serde(rename = "low")]
+    Low,
+}
+
+/// Nice value for low-priority jobs (0 is normal, 19 is lowest)
+#[cfg(unix)]
+const LOW_PRIORITY_NICE: libc::c_int = 10;
+
+impl JobPriority {
+    /// Configure a command to run at this priority once spawned
+    /// Only Unix is supported; elsewhere jobs always run at normal priority
+    pub fn apply(&self, command: &mut Command) {
+        #[cfg(unix)]
+        if *self == JobPriority::Low {
+            use std::os::unix::process::CommandExt;
+            // SAFETY: setpriority is async-signal-safe and touches no parent state,
+            // so it's sound to call between fork and exec
+            unsafe {
+                command.pre_exec(|| {
+                    if libc::setpriority(libc::PRIO_PROCESS, 0, LOW_PRIORITY_NICE) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = command;
+    }
+}
+
+/// How clips are sized to the export resolution
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ScaleMode {
+    /// Scale straight to the output size, distorting clips with another aspect ratio
+    #[default]
+    #[serde(rename = "stretch")]
+    Stretch,
+    /// Scale to fit inside the output size and center on black (letterbox/pillarbox)
+    #[serde(rename = "fit")]
+    Fit,
+}
+
+impl ScaleMode {
+    /// Video filter sizing a clip to the export resolution
+    fn filter(&self, resolution: &str) -> Result<String, Error> {
+        match (self, resolution_size(resolution)?) {
+            (ScaleMode::Fit, Some((width, height))) => Ok(format!(
+                "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:color=black,setsar=1",
+                w = width,
+                h = height
+            )),
+            // "source" keeps each clip's own size, so there's nothing to fit
+            _ => Ok(format!("scale={}", resolution_scale(resolution)?)),
+        }
+    }
+}
+
+/// A cursor sample recorded alongside a screen capture
+/// Times are seconds on the composition timeline; positions are output-frame pixels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorEvent {
+    pub time: f64,
+    pub x: f64,
+    pub y: f64,
+    #[serde(default)]
+    pub click: bool,
+}
+
+/// Cursor overlays are one drawbox each, so cap them to keep the filtergraph manageable
+/// (clicks are always drawn and may exceed it on their own)
+const MAX_CURSOR_OVERLAYS: usize = 2000;
+/// Minimum spacing between rendered cursor positions, in seconds
+const CURSOR_SAMPLE_INTERVAL: f64 = 0.1;
+/// How long a click highlight stays on screen, in seconds
+const CLICK_HIGHLIGHT_DURATION: f64 = 0.4;
+
+/// Load cursor events from a JSON file (an array of CursorEvent)
+fn load_cursor_events(path: &str) -> Result<Vec<CursorEvent>, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(Error::io("Failed to read cursor events file"))?;
+    let mut events: Vec<CursorEvent> = serde_json::from_str(&contents)
+        .map_err(|e| Error::InvalidInput(format!("Failed to parse cursor events file: {}", e)))?;
+    events.retain(|e| e.time.is_finite() && e.x.is_finite() && e.y.is_finite());
+    events.sort_by(|a, b| a.time.total_cmp(&b.time));
+    Ok(events)
+}
+
+/// Check a "HH:MM:SS:FF" timecode is well formed for the given frame rate
+fn validate_timecode(timecode: &str, fps: u32) -> Result<(), Error> {
+    let invalid = || Error::InvalidInput(format!("Invalid timecode \"{}\": expected HH:MM:SS:FF", timecode));
+    
+    let parts: Vec<u32> = timecode
+        .split(':')
+        .map(|part| if part.len() == 2 { part.parse::<u32>().ok() } else { None })
+        .collect::<Option<Vec<u32>>>()
+        .ok_or_else(invalid)?;
+    
+    let [_hours, minutes, seconds, frames] = parts[..] else {
+        return Err(invalid());
+    };
+    if minutes >= 60 || seconds >= 60 {
+        return Err(invalid());
+    }
+    if frames >= fps {
+        return Err(Error::InvalidInput(format!(
+            "Invalid timecode \"{}\": frame {} is out of range at {} fps",
+            timecode, frames, fps
+        )));
+    }
+    Ok(())
+}
+
+/// Parse an aspect ratio written as "W:H" (e.g. "2.39:1", "16:9") or a single number
+fn parse_aspect_ratio(aspect: &str) -> Result<f64, Error> {
+    let ratio = match aspect.split_once(':') {
+        Some((width, height)) => {
+            let width = width.trim().parse::<f64>();
+            let height = height.trim().parse::<f64>();
+            match (width, height) {
+                (Ok(width), Ok(height)) => width / height,
+                _ => f64::NAN,
+            }
+        }
+        None => aspect.trim().parse::<f64>().unwrap_or(f64::NAN),
+    };
+
+    if !ratio.is_finite() || ratio <= 0.0 {
+        return Err(Error::InvalidInput(format!("Invalid aspect ratio: {}", aspect)));
+    }
+    Ok(ratio)
+}
+
+/// Accept FFmpeg color names ("black", "navy") and hex colors ("#1a1a1a")
+fn validate_color(color: &str) -> Result<(), Error> {
+    let is_name = !color.is_empty() && color.chars().all(|c| c.is_ascii_alphabetic());
+    let is_hex = color
+        .strip_prefix('#')
+        .map(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .unwrap_or(false);
+
+    if is_name || is_hex {
+        Ok(())
+    } else {
+        Err(Error::InvalidInput(format!("Invalid color: {}", color)))
+    }
+}
+
+/// Crop the picture to the target ratio and pad it back to the output frame with bars
+fn aspect_bars_filter(resolution: &str, ratio: f64, color: &str) -> Result<String, Error> {
+    validate_color(color)?;
+    let (width, height) = resolution_size(resolution)?
+        .ok_or_else(|| Error::InvalidInput(format!("Aspect-ratio bars need a fixed output resolution, got {}", resolution)))?;
+
+    // Keep picture dimensions even so yuv420p chroma stays aligned
+    let even = |value: f64| ((value / 2.0).round() as u32 * 2).max(2);
+    let (picture_width, picture_height) = if ratio > width as f64 / height as f64 {
+        (width, even(width as f64 / ratio)) // Wider: letterbox
+    } else {
+        (even(height as f64 * ratio), height) // Narrower: pillarbox
+    };
+
+    Ok(format!(
+        "crop={}:{},pad={}:{}:(ow-iw)/2:(oh-ih)/2:color={}",
+        picture_width, picture_height, width, height, color
+    ))
+}
+
+/// Build drawbox filters rendering a cursor marker and click highlights
+fn cursor_overlay_filters(events: &[CursorEvent], composition_length: f64) -> Vec<String> {
+    let mut filters = Vec::new();
+    let clicks: Vec<&CursorEvent> = events.iter().filter(|e| e.click).collect();
+
+    // Cursor marker: hold each sampled position until the next sample
+    let moves: Vec<&CursorEvent> = events.iter().filter(|e| !e.click).collect();
+    let mut last_time = f64::NEG_INFINITY;
+    let mut sampled: Vec<&CursorEvent> = moves
+        .into_iter()
+        .filter(|e| {
+            let keep = e.time - last_time >= CURSOR_SAMPLE_INTERVAL;
+            if keep {
+                last_time = e.time;
+            }
+            keep
+        })
+        .collect();
+
+    // Every click is kept; positions share what's left of the overlay cap, thinned
+    // evenly so the marker still follows the whole recording
+    let position_budget = MAX_CURSOR_OVERLAYS.saturating_sub(clicks.len());
+    if sampled.len() > position_budget {
+        eprintln!(
+            "Warning: {} cursor positions and {} clicks requested, thinning positions to fit {} overlays",
+            sampled.len(),
+            clicks.len(),
+            MAX_CURSOR_OVERLAYS
+        );
+        sampled = match position_budget {
+            0 => Vec::new(),
+            budget => {
+                let stride = sampled.len().div_ceil(budget);
+                sampled.into_iter().step_by(stride).collect()
+            }
+        };
+    }
+
+    for (i, event) in sampled.iter().enumerate() {
+        let until = sampled.get(i + 1).map_or(composition_length, |next| next.time);
+        filters.push(format!(
+            "drawbox=x={}:y={}:w=12:h=12:color=white@0.9:t=fill:enable='between(t,{},{})'",
+            (event.x - 6.0).round(),
+            (event.y - 6.0).round(),
+            event.time,
+            until
+        ));
+    }
+
+    // Click highlight: a translucent square around the click point
+    for event in clicks {
+        filters.push(format!(
+            "drawbox=x={}:y={}:w=48:h=48:color=yellow@0.5:t=fill:enable='between(t,{},{})'",
+            (event.x - 24.0).round(),
+            (event.y - 24.0).round(),
+            event.time,
+            event.time + CLICK_HIGHLIGHT_DURATION
+        ));
+    }
+
+    filters
+}
+
+/// An additional rendition encoded by the same FFmpeg run as the main export,
+/// e.g. a small review preview, so the timeline is only decoded once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputSpec {
+    pub path: String,
+    pub resolution: String,
+    #[serde(default = "default_extra_output_crf")]
+    pub crf: u8,
+}
+
+fn default_extra_output_crf() -> u8 {
+    28
+}
+
+/// Two-pass camera shake removal (libvidstab)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stabilization {
+    /// Frames on each side averaged when smoothing camera motion;
+    /// higher values give steadier output but follow intentional pans more slowly
+    #[serde(default = "default_stabilization_smoothing")]
+    pub smoothing: u32,
+}
+
+fn default_stabilization_smoothing() -> u32 {
+    10
+}
+
+/// Optional export settings
+/// Every field has a default so the frontend only needs to send what it changes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    #[serde(rename = "colorTagging")]
+    pub color_tagging: ColorTagging,
+    #[serde(rename = "extraOutputs")]
+    pub extra_outputs: Vec<OutputSpec>,
+    /// Remove all container metadata (GPS/location, device tags) from the output
+    #[serde(rename = "stripMetadata")]
+    pub strip_metadata: bool,
+    /// Carry container metadata over from the first clip
+    #[serde(rename = "copyMetadata")]
+    pub copy_metadata: bool,
+    /// Seconds to fade the final composition in from black (and silence) at the start
+    #[serde(rename = "fadeIn")]
+    pub fade_in: f64,
+    /// Seconds to fade the final composition out to black (and silence) at the very end
+    #[serde(rename = "endFade", alias = "fadeOut")]
+    pub end_fade: f64,
+    /// JSON file of CursorEvent samples to render as a cursor and click highlights,
+    /// for platforms where the capture device can't draw them at record time
+    #[serde(rename = "cursorEventsPath")]
+    pub cursor_events_path: Option<String>,
+    /// Stabilize shaky footage; only supported when exporting a single clip
+    pub stabilization: Option<Stabilization>,
+    /// Frame the picture to this aspect ratio (e.g. "2.39:1") with bars,
+    /// keeping the output resolution
+    #[serde(rename = "targetAspect")]
+    pub target_aspect: Option<String>,
+    /// Bar color for target_aspect: an FFmpeg color name or "#rrggbb" (default black)
+    #[serde(rename = "barColor")]
+    pub bar_color: Option<String>,
+    /// Starting timecode written to the output (e.g. "01:00:00:00" for broadcast)
+    #[serde(rename = "startTimecode")]
+    pub start_timecode: Option<String>,
+    /// Explicit gap entries with their own fill; other gaps stay black
+    pub gaps: Vec<GapSpec>,
+    /// Codec for the main output; extra outputs are always H.264 previews
+    pub codec: VideoCodec,
+    /// Run the export at lower CPU priority so the machine stays responsive
+    pub priority: JobPriority,
+    /// CRF and preset for H.264 exports (defaults to CRF 23, "medium")
+    pub quality: ExportQuality,
+    /// Encode with VideoToolbox when available, falling back to software otherwise
+    #[serde(rename = "hardwareEncoding")]
+    pub hardware_encoding: bool,
+    /// Target bitrate for hardware encoding, e.g. "8M" (hardware encoders don't use CRF)
+    #[serde(rename = "videoBitrate")]
+    pub video_bitrate: Option<String>,
+    /// Encode H.264 in two passes at the bitrate that lands near this size, in megabytes,
+    /// instead of using the CRF
+    #[serde(rename = "targetSizeMb")]
+    pub target_size_mb: Option<f64>,
+    /// Stretch clips to the output size or fit them with bars to keep their aspect ratio
+    #[serde(rename = "scaleMode")]
+    pub scale_mode: ScaleMode,
+    /// Leave the audio track out of every output entirely
+    #[serde(rename = "stripAudio")]
+    pub strip_audio: bool,
+    /// Audio file played under the whole composition, gaps included; looped if
+    /// shorter than the composition and cut off if longer
+    #[serde(rename = "musicPath")]
+    pub music_path: Option<String>,
+    /// Music level from 0.0 to 1.0 (default 0.3)
+    #[serde(rename = "musicVolume")]
+    pub music_volume: Option<f64>,
+    /// Duck the music under clip audio instead of mixing it at a constant level
+    #[serde(rename = "duckMusic")]
+    pub duck_music: bool,
+    /// Normalize the final audio to a loudness target (EBU R128)
+    pub loudness: Option<LoudnessNormalization>,
+    /// Write encoded video (default) or an animated GIF
+    pub format: ExportFormat,
+    /// Frame rate and width for GIF exports
+    pub gif: GifSettings,
+    /// Codec, bitrate and sample rate for audio-only exports
+    pub audio: AudioExportSettings,
+    /// 3D LUT (.cube) graded over the whole composed picture
+    #[serde(rename = "lutPath")]
+    pub lut_path: Option<String>,
+    /// Logo composited over the whole export, gaps included
+    pub watermark: Option<Watermark>,
+    /// Tracks shown over the main clips (e.g. a webcam), bottom track first
+    #[serde(rename = "overlayTracks")]
+    pub overlay_tracks: Vec<Vec<OverlayClip>>,
+    /// Synthesize in-between frames for clips slower than the export frame rate
+    /// (e.g. smooth slow motion) instead of repeating frames; much slower to render
+    #[serde(rename = "frameInterpolation")]
+    pub frame_interpolation: bool,
+}
+
+/// A clip on an overlay track, composited over the main track while it plays
+/// `start_time` is timeline time, like the main track's clips
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayClip {
+    #[serde(flatten)]
+    pub clip: ClipInfo,
+    #[serde(default)]
+    pub position: OverlayPosition,
+    /// Overlay width as a fraction of the frame width (0.05–1.0)
+    #[serde(default = "default_overlay_scale")]
+    pub scale: f64,
+}
+
+fn default_overlay_scale() -> f64 {
+    0.3
+}
+
+/// Where a watermark or overlay clip sits in the exported frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum OverlayPosition {
+    #[serde(rename = "topLeft")]
+    TopLeft,
+    #[serde(rename = "topRight")]
+    TopRight,
+    #[serde(rename = "bottomLeft")]
+    BottomLeft,
+    #[default]
+    #[serde(rename = "bottomRight")]
+    BottomRight,
+    #[serde(rename = "center")]
+    Center,
+}
+
+impl OverlayPosition {
+    /// overlay filter position for this spot, inset from the edges like the webcam overlay
+    fn overlay_position(&self) -> String {
+        let x = match self {
+            OverlayPosition::TopLeft | OverlayPosition::BottomLeft => PIP_MARGIN.to_string(),
+            OverlayPosition::TopRight | OverlayPosition::BottomRight => format!("main_w-overlay_w-{}", PIP_MARGIN),
+            OverlayPosition::Center => "(main_w-overlay_w)/2".to_string(),
+        };
+        let y = match self {
+            OverlayPosition::TopLeft | OverlayPosition::TopRight => PIP_MARGIN.to_string(),
+            OverlayPosition::BottomLeft | OverlayPosition::BottomRight => format!("main_h-overlay_h-{}", PIP_MARGIN),
+            OverlayPosition::Center => "(main_h-overlay_h)/2".to_string(),
+        };
+        format!("x={}:y={}", x, y)
+    }
+}
+
+/// A logo image (PNG transparency is kept) laid over the exported video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watermark {
+    pub path: String,
+    #[serde(default)]
+    pub position: OverlayPosition,
+    /// 0.0 (invisible) to 1.0, multiplied with the image's own transparency
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    /// Watermark width as a fraction of the frame width (0.01–1.0)
+    #[serde(default = "default_watermark_scale")]
+    pub scale: f64,
+}
+
+fn default_watermark_scale() -> f64 {
+    0.15
+}
+
+impl Watermark {
+    fn validate(&self) -> Result<(), Error> {
+        if !Path::new(&self.path).is_file() {
+            return Err(Error::InvalidInput(format!("Watermark image not found: {}", self.path)));
+        }
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(Error::InvalidInput(format!("Watermark opacity must be between 0.0 and 1.0, got {}", self.opacity)));
+        }
+        if !(0.01..=1.0).contains(&self.scale) {
+            return Err(Error::InvalidInput(format!("Watermark scale must be between 0.01 and 1.0, got {}", self.scale)));
+        }
+        Ok(())
+    }
+    
+    /// Filters compositing input `input` over `[base]` into `[output]` on a `frame_width`-wide frame
+    fn overlay_filters(&self, input: usize, frame_width: u32, base: &str, output: &str) -> Vec<String> {
+        // Even width keeps chroma-subsampled output happy; height follows the aspect ratio
+        let width = ((frame_width as f64 * self.scale / 2.0).round() as u32 * 2).max(2);
+        vec![
+            format!(
+                "[{}:v]format=rgba,scale={}:-2,colorchannelmixer=aa={}[watermark]",
+                input, width, self.opacity
+            ),
+            // A single image repeats its last frame, so it stays up for the whole video
+            format!("[{}][watermark]overlay={}:format=auto[{}]", base, self.position.overlay_position(), output),
+        ]
+    }
+}
+
+impl ExportOptions {
+    /// Every overlay track clip, in FFmpeg input order
+    fn overlay_clips(&self) -> Vec<ClipInfo> {
+        self.overlay_tracks.iter().flatten().map(|overlay| overlay.clip.clone()).collect()
+    }
+    
+    /// Check overlay clips are valid and don't overlap others on their own track
+    fn validate_overlay_tracks(&self) -> Result<(), Error> {
+        for (t, track) in self.overlay_tracks.iter().enumerate() {
+            let clips: Vec<ClipInfo> = track.iter().map(|overlay| overlay.clip.clone()).collect();
+            validate_clips(&clips).map_err(|e| Error::InvalidInput(format!("Overlay track {}: {}", t, e)))?;
+            
+            for overlay in track {
+                if !(0.05..=1.0).contains(&overlay.scale) {
+                    return Err(Error::InvalidInput(format!("Overlay scale must be between 0.05 and 1.0, got {}", overlay.scale)));
+                }
+            }
+            
+            let mut spans: Vec<(f64, f64)> = clips
+                .iter()
+                .map(|clip| (clip.start_time, clip.start_time + clip.timeline_duration()))
+                .collect();
+            spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for pair in spans.windows(2) {
+                // Same float slack as gaps on the main track
+                if pair[1].0 < pair[0].1 - 0.001 {
+                    return Err(Error::InvalidInput(format!("Overlay track {} has overlapping clips at {}s", t, pair[1].0)));
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Filters applied to the whole composed video after clips and gaps are joined
+    fn finishing_filters(&self, resolution: &str, composition_length: f64) -> Result<Vec<String>, Error> {
+        let mut filters = Vec::new();
+        
+        // Grade first so bars and overlays keep their own colors
+        if let Some(path) = &self.lut_path {
+            filters.push(format!("lut3d=file={}", escape_filter_path(Path::new(path))));
+        }
+        
+        if let Some(aspect) = &self.target_aspect {
+            let color = self.bar_color.as_deref().unwrap_or("black");
+            filters.push(aspect_bars_filter(resolution, parse_aspect_ratio(aspect)?, color)?);
+        }
+
+        for (name, fade) in [("Fade in", self.fade_in), ("End fade", self.end_fade)] {
+            if !fade.is_finite() || fade < 0.0 {
+                return Err(Error::InvalidInput(format!("Invalid {} duration: {}", name.to_lowercase(), fade)));
+            }
+            if fade > composition_length {
+                return Err(Error::InvalidInput(format!(
+                    "{} ({}s) is longer than the composition ({}s)",
+                    name, fade, composition_length
+                )));
+            }
+        }
+        if let Some(path) = &self.cursor_events_path {
+            let events = load_cursor_events(path)?;
+            filters.extend(cursor_overlay_filters(&events, composition_length));
+        }
+
+        // Fade last so overlays fade along with the picture
+        if self.fade_in > 0.0 {
+            filters.push(format!("fade=t=in:st=0:d={}", self.fade_in));
+        }
+        if self.end_fade > 0.0 {
+            filters.push(format!(
+                "fade=t=out:st={}:d={}",
+                composition_length - self.end_fade,
+                self.end_fade
+            ));
+        }
+
+        Ok(filters)
+    }
+
+    /// Filters applied to the whole composed audio: loudness normalization, then fades
+    /// matching the video's. `measured` is the analysis pass of a two-pass normalization
+    /// Durations are checked by finishing_filters
+    fn audio_finishing_filters(&self, composition_length: f64, measured: Option<&LoudnessMeasurement>) -> Vec<String> {
+        let mut filters = Vec::new();
+        if let Some(loudness) = &self.loudness {
+            filters.push(match measured {
+                Some(measured) => measured_loudnorm_filter(loudness.target_lufs, measured),
+                None => loudnorm_filter(loudness.target_lufs),
+            });
+            // loudnorm works (and outputs) at 192 kHz
+            filters.push("aresample=48000".to_string());
+        }
+        if self.fade_in > 0.0 {
+            filters.push(format!("afade=t=in:st=0:d={}", self.fade_in));
+        }
+        if self.end_fade > 0.0 {
+            filters.push(format!(
+                "afade=t=out:st={}:d={}",
+                composition_length - self.end_fade,
+                self.end_fade
+            ));
+        }
+        filters
+    }
+
+    /// Per-output arguments controlling which container metadata is written
+    fn metadata_args(&self) -> Vec<String> {
+        let mut args = if self.strip_metadata {
+            vec!["-map_metadata".to_string(), "-1".to_string()]
+        } else if self.copy_metadata {
+            vec!["-map_metadata".to_string(), "0".to_string()]
+        } else {
+            Vec::new()
+        };
+        
+        if let Some(timecode) = &self.start_timecode {
+            args.push("-timecode".to_string());
+            args.push(timecode.clone());
+        }
+        
+        args
+    }
+}
+
+/// Optional screen recording settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenRecordingOptions {
+    /// Target video bitrate such as "5M" or "2500k"; CRF is used when unset
+    #[serde(rename = "videoBitrate")]
+    pub video_bitrate: Option<String>,
+    /// Audio bitrate such as "128k"; only applies when an audio device is recorded
+    #[serde(rename = "audioBitrate")]
+    pub audio_bitrate: Option<String>,
+    /// Which display to record, as numbered in "Capture screen N"; defaults to the first
+    /// (macOS only; on Windows the whole desktop or `region` is recorded)
+    #[serde(rename = "screenIndex")]
+    pub screen_index: Option<u32>,
+    /// Record only this part of the screen, e.g. one app window
+    pub region: Option<CaptureRegion>,
+    /// Second audio device carrying system audio, mixed with the microphone into one track
+    /// macOS has no system-audio input of its own: install a loopback driver such as
+    /// BlackHole, route output through a Multi-Output Device that includes it, and pass
+    /// BlackHole's index from list_audio_devices here
+    #[serde(rename = "systemAudioDevice")]
+    pub system_audio_device: Option<String>,
+}
+
+/// Microphone stream in screen recordings: avfoundation captures it alongside the
+/// screen, while gdigrab is video-only and takes it from a second input
+#[cfg(not(target_os = "windows"))]
+const SCREEN_MIC_STREAM: &str = "0:a";
+#[cfg(target_os = "windows")]
+const SCREEN_MIC_STREAM: &str = "1:a";
+
+/// How a screen recording's audio is produced from its inputs
+struct RecordingAudio {
+    /// Filter chain mixing several audio inputs into one track
+    filter: Option<String>,
+    /// Stream or filter label to map as the output's audio; None records no audio
+    stream: Option<String>,
+}
+
+/// Corner of the screen the webcam sits in during picture-in-picture recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum PipCorner {
+    #[serde(rename = "topLeft")]
+    TopLeft,
+    #[serde(rename = "topRight")]
+    TopRight,
+    #[serde(rename = "bottomLeft")]
+    BottomLeft,
+    #[default]
+    #[serde(rename = "bottomRight")]
+    BottomRight,
+}
+
+/// Gap between the webcam and the screen edges, in screen pixels
+const PIP_MARGIN: u32 = 24;
+
+impl PipCorner {
+    /// overlay filter position for this corner
+    fn overlay_position(&self) -> String {
+        let x = match self {
+            PipCorner::TopLeft | PipCorner::BottomLeft => PIP_MARGIN.to_string(),
+            PipCorner::TopRight | PipCorner::BottomRight => format!("main_w-overlay_w-{}", PIP_MARGIN),
+        };
+        let y = match self {
+            PipCorner::TopLeft | PipCorner::TopRight => PIP_MARGIN.to_string(),
+            PipCorner::BottomLeft | PipCorner::BottomRight => format!("main_h-overlay_h-{}", PIP_MARGIN),
+        };
+        format!("x={}:y={}", x, y)
+    }
+}
+
+/// Layout of the webcam in picture-in-picture recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PipOptions {
+    pub corner: PipCorner,
+    /// Webcam height as a fraction of the screen height (0.1–0.5)
+    pub size: f64,
+}
+
+impl Default for PipOptions {
+    fn default() -> Self {
+        Self {
+            corner: PipCorner::default(),
+            size: 0.25,
+        }
+    }
+}
+
+/// A rectangle of the captured screen in pixels, from its top-left corner
+/// macOS captures the full screen and crops; Windows grabs only the region.
+/// On Windows the offsets are virtual-desktop coordinates, which are negative on
+/// displays left of or above the primary one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Area a screen recording can capture, in the same coordinates as CaptureRegion
+/// On Windows this is the virtual desktop spanning every display
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ScreenBounds {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+impl CaptureRegion {
+    /// Check the region is encodable and lies within the screen
+    fn validate(&self, screen: &ScreenBounds) -> Result<(), Error> {
+        if self.width == 0 || self.height == 0 || !self.width.is_multiple_of(2) || !self.height.is_multiple_of(2) {
+            return Err(Error::InvalidInput(format!(
+                "Capture region must have positive even width and height, got {}x{}",
+                self.width, self.height
+            )));
+        }
+        let (left, top) = (i64::from(self.x), i64::from(self.y));
+        let (screen_left, screen_top) = (i64::from(screen.x), i64::from(screen.y));
+        let fits = left >= screen_left
+            && top >= screen_top
+            && left + i64::from(self.width) <= screen_left + i64::from(screen.width)
+            && top + i64::from(self.height) <= screen_top + i64::from(screen.height);
+        if !fits {
+            return Err(Error::InvalidInput(format!(
+                "Capture region {}x{} at ({}, {}) doesn't fit on the {}x{} screen at ({}, {})",
+                self.width, self.height, self.x, self.y, screen.width, screen.height, screen.x, screen.y
+            )));
+        }
+        Ok(())
+    }
+
+    /// Filter cropping a full-screen capture down to the region
+    fn crop_filter(&self) -> String {
+        format!("crop={}:{}:{}:{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CameraInfo {
+    pub index: u32,
+    pub name: String,
+}
+
+/// A capture size and frame rate a camera supports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraMode {
+    pub width: u32,
+    pub height: u32,
+    /// Highest frame rate the camera delivers at this size
+    pub fps: f64,
+}
+
+/// A microphone or other audio input, by its avfoundation device index
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AudioDeviceInfo {
+    pub index: u32,
+    pub name: String,
+}
+
+/// Parse the (index, name) entries under one header of an avfoundation device dump
+/// Each section runs until the next "AVFoundation ... devices" header
+#[cfg(not(target_os = "windows"))]
+fn parse_device_section(stderr: &str, header: &str) -> Vec<(u32, String)> {
+    let mut devices = Vec::new();
+    let mut in_section = false;
+    
+    for line in stderr.lines() {
+        if line.contains("AVFoundation") && line.contains("devices:") {
+            in_section = line.contains(header);
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        
+        // Device line format: [AVFoundation indev @ 0x...] [<index>] <name>
+        // Example: "[AVFoundation indev @ 0x156630da0] [0] FaceTime HD Camera"
+        // The last "] [" marks the start of the device index
+        let trimmed = line.trim();
+        if let Some(device_start) = trimmed.rfind("] [") {
+            let device_part = &trimmed[device_start + 3..];
+            if let Some(bracket_end) = device_part.find(']') {
+                if let Ok(index) = device_part[..bracket_end].parse::<u32>() {
+                    let name = device_part[bracket_end + 1..].trim();
+                    if !name.is_empty() {
+                        devices.push((index, name.to_string()));
+                    }
+                }
+            }
+        }
+    }
+    
+    devices
+}
+
+/// Parse the modes avfoundation lists when a camera rejects the requested format
+/// Example: "[avfoundation @ 0x7f8c] 1280x720@[1.000000 30.000000]fps"
+#[cfg(not(target_os = "windows"))]
+fn parse_avfoundation_modes(stderr: &str) -> Vec<CameraMode> {
+    let mut modes = Vec::new();
+    for line in stderr.lines() {
+        let Some(at) = line.find("@[") else { continue };
+        let size = line[..at].rsplit(' ').next().unwrap_or_default();
+        let Some((width, height)) = size.split_once('x') else { continue };
+        let (Ok(width), Ok(height)) = (width.parse(), height.parse()) else { continue };
+        // The brackets hold the frame rate range the camera supports at this size
+        let rates = line[at + 2..].split(']').next().unwrap_or_default();
+        let fps = rates
+            .split_whitespace()
+            .filter_map(|rate| rate.parse::<f64>().ok())
+            .fold(0.0, f64::max);
+        if fps > 0.0 {
+            modes.push(CameraMode { width, height, fps });
+        }
+    }
+    modes
+}
+
+/// Parse the modes DirectShow lists for a camera with -list_options
+/// Example: "[dshow @ 0000021e]   vcodec=mjpeg  min s=1280x720 fps=5 max s=1280x720 fps=30"
+#[cfg(target_os = "windows")]
+fn parse_dshow_modes(stderr: &str) -> Vec<CameraMode> {
+    let mut modes = Vec::new();
+    for line in stderr.lines() {
+        let Some(max) = line.find("max s=") else { continue };
+        let mut fields = line[max + "max s=".len()..].split_whitespace();
+        let Some((width, height)) = fields.next().and_then(|size| size.split_once('x')) else { continue };
+        let (Ok(width), Ok(height)) = (width.parse(), height.parse()) else { continue };
+        let Some(Ok(fps)) = fields.next().and_then(|f| f.strip_prefix("fps=")).map(str::parse::<f64>) else { continue };
+        modes.push(CameraMode { width, height, fps });
+    }
+    modes
+}
+
+/// Sort camera modes largest first and drop duplicates (one per pixel format in DirectShow)
+fn sort_camera_modes(mut modes: Vec<CameraMode>) -> Vec<CameraMode> {
+    modes.sort_by(|a, b| {
+        (b.width * b.height)
+            .cmp(&(a.width * a.height))
+            .then(b.fps.total_cmp(&a.fps))
+    });
+    modes.dedup();
+    modes
+}
+
+/// Parse device names of one kind ("video" or "audio") from a DirectShow device dump
+/// Newer FFmpeg tags each device line with "(video)"/"(audio)"; older builds group
+/// devices under "DirectShow video devices" / "DirectShow audio devices" headers
+#[cfg(target_os = "windows")]
+fn parse_dshow_devices(stderr: &str, kind: &str) -> Vec<String> {
+    let mut devices = Vec::new();
+    let mut section = "";
+    
+    for line in stderr.lines() {
+        if line.contains("DirectShow video devices") {
+            section = "video";
+            continue;
+        }
+        if line.contains("DirectShow audio devices") {
+            section = "audio";
+            continue;
+        }
+        // Each device is followed by its "@device_..." moniker; skip those
+        if line.contains("Alternative name") {
+            continue;
+        }
+        
+        // Example: "[dshow @ 000001c8] "Integrated Camera" (video)"
+        let mut quoted = line.splitn(3, '"');
+        let (name, tag) = match (quoted.next(), quoted.next(), quoted.next()) {
+            (Some(_), Some(name), Some(rest)) if !name.is_empty() => (name, rest.trim()),
+            _ => continue,
+        };
+        let device_kind = match tag {
+            "(video)" => "video",
+            "(audio)" => "audio",
+            _ => section,
+        };
+        if device_kind == kind {
+            devices.push(name.to_string());
+        }
+    }
+    
+    devices
+}
+
+/// Result of rendering a trim to a standalone file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrimResult {
+    #[serde(rename = "outputPath")]
+    pub output_path: String,
+    #[serde(rename = "actualStart")]
+    pub actual_start: f64,
+    #[serde(rename = "actualEnd")]
+    pub actual_end: f64,
+    pub duration: f64,
+    /// True when stream copy had to start at a keyframe before the requested in point
+    #[serde(rename = "snappedToKeyframe")]
+    pub snapped_to_keyframe: bool,
+}
+
+/// Files written when splitting a clip into separate video and audio files
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DemuxResult {
+    #[serde(rename = "videoPath")]
+    pub video_path: String,
+    /// None when the source has no audio stream
+    #[serde(rename = "audioPath")]
+    pub audio_path: Option<String>,
+}
+
+/// A subtitle track embedded in a media file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubtitleStream {
+    /// Position among the file's subtitle streams, as passed to extract_subtitles
+    pub index: u32,
+    /// ISO 639 language tag, when the file has one
+    pub language: Option<String>,
+    pub codec: String,
+    pub title: Option<String>,
+}
+
+/// Subtitle codecs that are images rather than text and can't be written as SRT
+const BITMAP_SUBTITLE_CODECS: [&str; 4] = ["hdmv_pgs_subtitle", "dvd_subtitle", "dvb_subtitle", "xsub"];
+
+/// Chapter marker written into exported audio (ID3 CHAP frames for MP3)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Loudness statistics from FFmpeg's loudnorm analysis pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoudnessMeasurement {
+    #[serde(rename = "integratedLufs")]
+    pub integrated_lufs: f64,
+    #[serde(rename = "truePeak")]
+    pub true_peak: f64,
+    #[serde(rename = "loudnessRange")]
+    pub loudness_range: f64,
+    pub threshold: f64,
+    #[serde(rename = "targetOffset")]
+    pub target_offset: f64,
+}
+
+/// Start times of a file's first video and audio streams
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AvSyncInfo {
+    #[serde(rename = "videoStart")]
+    pub video_start: f64,
+    #[serde(rename = "audioStart")]
+    pub audio_start: f64,
+    /// audio_start - video_start: positive means audio begins after the picture
+    pub offset: f64,
+}
+
+/// Integrated loudness target for podcast exports (spoken-word platforms use -16 LUFS)
+pub const PODCAST_TARGET_LUFS: f64 = -16.0;
+
+/// Loudness normalization applied to an export's final audio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoudnessNormalization {
+    /// Integrated loudness target (default -16 LUFS, as social platforms expect)
+    #[serde(rename = "targetLufs")]
+    pub target_lufs: f64,
+    /// Measure the composed audio in a separate pass first for accurate linear
+    /// normalization; the default single pass adjusts dynamically and is faster
+    #[serde(rename = "twoPass")]
+    pub two_pass: bool,
+}
+
+impl Default for LoudnessNormalization {
+    fn default() -> Self {
+        Self {
+            target_lufs: PODCAST_TARGET_LUFS,
+            two_pass: false,
+        }
+    }
+}
+
+/// Build a single-pass loudnorm (EBU R128) filter for the given integrated loudness target
+fn loudnorm_filter(target_lufs: f64) -> String {
+    format!("loudnorm=I={}:TP=-1.5:LRA=11", target_lufs)
+}
+
+/// Build the second-pass loudnorm filter from an analysis pass's statistics
+fn measured_loudnorm_filter(target_lufs: f64, measured: &LoudnessMeasurement) -> String {
+    format!(
+        "{}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true",
+        loudnorm_filter(target_lufs),
+        measured.integrated_lufs,
+        measured.true_peak,
+        measured.loudness_range,
+        measured.threshold,
+        measured.target_offset
+    )
+}
+
+/// Parse the JSON block loudnorm prints to stderr when `print_format=json` is set
+fn parse_loudnorm_stats(stderr: &str) -> Result<LoudnessMeasurement, Error> {
+    let failed = |message: String| Error::process("Loudness analysis failed", message);
+    let start = stderr
+        .rfind('{')
+        .ok_or_else(|| failed("no statistics were printed".to_string()))?;
+    let end = stderr[start..]
+        .find('}')
+        .ok_or_else(|| failed("statistics were incomplete".to_string()))?
+        + start;
+
+    let json: Value = serde_json::from_str(&stderr[start..=end])
+        .map_err(|e| failed(format!("failed to parse statistics: {}", e)))?;
+
+    let field = |name: &str| -> Result<f64, Error> {
+        let value = json[name]
+            .as_str()
+            .and_then(|s| s.parse::<f64>().ok())
+            .ok_or_else(|| failed(format!("missing statistic {}", name)))?;
+        if !value.is_finite() {
+            return Err(Error::InvalidInput("Audio is silent; loudness cannot be measured".to_string()));
+        }
+        Ok(value)
+    };
+
+    Ok(LoudnessMeasurement {
+        integrated_lufs: field("input_i")?,
+        true_peak: field("input_tp")?,
+        loudness_range: field("input_lra")?,
+        threshold: field("input_thresh")?,
+        target_offset: field("target_offset")?,
+    })
+}
+
+/// Build filters that duck the `music` stream under the `voice` stream using
+/// sidechain compression, then mix both into `output`
+fn ducked_mix_filters(voice: &str, music: &str, output: &str) -> Vec<String> {
+    vec![
+        format!("[{}]asplit=2[duckvoice][duckkey]", voice),
+        format!(
+            "[{}][duckkey]sidechaincompress=threshold=0.02:ratio=8:attack=20:release=400[ducked]",
+            music
+        ),
+        format!(
+            "[duckvoice][ducked]amix=inputs=2:duration=first:dropout_transition=0:normalize=0[{}]",
+            output
+        ),
+    ]
+}
+
+/// Output of a process that was run under a deadline
+struct TimedOutput {
+    /// None when the process was killed because it hit the deadline
+    status: Option<ExitStatus>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl TimedOutput {
+    fn timed_out(&self) -> bool {
+        self.status.is_none()
+    }
+}
+
+/// Stop the running export, asking FFmpeg to quit before killing it
+/// The export itself removes the partial output and reports the cancellation
+pub fn cancel_export() -> Result<(), Error> {
+    let mut child = {
+        let mut slot = EXPORT_PROCESS.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut child = slot
+            .take()
+            .ok_or_else(|| Error::ProcessNotRunning("No export in progress".to_string()))?;
+        if !matches!(child.try_wait(), Ok(None)) {
+            // FFmpeg exited before the export reclaimed it; let the export
+            // report its result instead of deleting a finished file
+            *slot = Some(child);
+            return Err(Error::ProcessNotRunning("Export already finished".to_string()));
+        }
+        child
+    };
+    
+    // Step 1: Send 'q' to stdin for graceful quit
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"q");
+        let _ = stdin.flush();
+    }
+    
+    // Step 2: Give FFmpeg a moment to stop on its own
+    let deadline = Instant::now() + EXPORT_CANCEL_TIMEOUT;
+    while matches!(child.try_wait(), Ok(None)) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    
+    // Step 3: If still running, kill it
+    if let Ok(None) = child.try_wait() {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+    
+    Ok(())
+}
+
+/// Top-left corner of the virtual desktop from gdigrab's log, e.g.
+/// "Capturing whole desktop as 3840x1080x32 at (-1920,0)"
+fn parse_gdigrab_origin(log: &str) -> Option<(i32, i32)> {
+    let line = log.lines().find(|line| line.contains("Capturing whole desktop"))?;
+    let (_, origin) = line.rsplit_once(" at (")?;
+    let (x, y) = origin.trim_end().trim_end_matches(')').split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+/// Seconds of output written so far, from a `-progress` line such as "out_time_us=1500000"
+/// (`out_time_ms` is also in microseconds, despite its name)
+fn parse_progress_time(line: &str) -> Option<f64> {
+    let (key, value) = line.trim().split_once('=')?;
+    match key {
+        "out_time_us" | "out_time_ms" => value.parse::<i64>().ok().map(|us| us as f64 / 1_000_000.0),
+        _ => None,
+    }
+}
+
+/// Run a command, killing it if it hasn't exited within `timeout`
+/// Whatever the process wrote before being killed is still returned
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> std::io::Result<TimedOutput> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain the pipes on separate threads so a chatty process can't block on a full pipe
+    let stdout_reader = spawn_pipe_reader(child.stdout.take());
+    let stderr_reader = spawn_pipe_reader(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    Ok(TimedOutput {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+/// Like `Command::output`, but the process is killed and Error::Timeout returned
+/// if it runs longer than `timeout`
+fn output_with_timeout(command: &mut Command, timeout: Duration, context: &str) -> Result<Output, Error> {
+    let output = run_with_timeout(command, timeout)
+        .map_err(Error::io(format!("{} failed to start", context)))?;
+    
+    match output.status {
+        Some(status) => Ok(Output { status, stdout: output.stdout, stderr: output.stderr }),
+        None => Err(Error::Timeout(format!(
+            "{} didn't finish within {}s",
+            context,
+            timeout.as_secs_f64()
+        ))),
+    }
+}
+
+/// Seconds from an environment variable, or `default` when unset or not a positive number
+fn timeout_from_env(name: &str, default: Duration) -> Duration {
+    match std::env::var(name) {
+        Ok(value) => match value.trim().parse::<f64>() {
+            Ok(secs) if secs.is_finite() && secs > 0.0 => Duration::from_secs_f64(secs),
+            _ => {
+                eprintln!("Warning: ignoring {}={:?}; expected a positive number of seconds", name, value);
+                default
+            }
+        },
+        Err(_) => default,
+    }
+}
+
+/// Read a child pipe to completion on a background thread
+fn spawn_pipe_reader<R: Read + Send + 'static>(pipe: Option<R>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+/// 64-bit FNV-1a hash; unlike DefaultHasher it gives the same value on every run
+/// and Rust release, so cache file names survive restarts
+fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Cache file in the temp directory for a `kind` image identified by `file_path`'s
+/// current modification time plus whatever else shapes the image
+fn media_cache_path(kind: &str, file_path: &str, key: &str, extension: &str) -> Result<PathBuf, Error> {
+    let modified = std::fs::metadata(file_path)
+        .and_then(|m| m.modified())
+        .map_err(Error::io(format!("Failed to read {}", file_path)))?;
+    let modified = modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos());
+    let hash = stable_hash(format!("{}\0{}\0{}", file_path, modified, key).as_bytes());
+    Ok(std::env::temp_dir().join(format!("{}_{:016x}.{}", kind, hash, extension)))
+}
+
+/// Whether a cache entry exists; marks it recently used so pruning keeps it
+fn media_cache_hit(path: &Path) -> bool {
+    let Ok(file) = std::fs::File::options().append(true).open(path) else {
+        return false;
+    };
+    let _ = file.set_modified(std::time::SystemTime::now());
+    true
+}
+
+/// Scratch name FFmpeg writes a cache entry to before it's renamed into place,
+/// so a concurrent request or a crash never leaves a half-written image behind
+/// `path`'s own extension is kept because FFmpeg picks the image format from it
+fn media_cache_partial_path(path: &Path) -> PathBuf {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    path.with_extension(format!("partial-{}.{}", uuid::Uuid::new_v4(), extension))
+}
+
+/// Move a finished cache entry into place, then trim the `kind` cache to its budget
+fn commit_media_cache(partial: &Path, path: &Path, kind: &str) -> Result<(), Error> {
+    std::fs::rename(partial, path).map_err(|e| {
+        let _ = std::fs::remove_file(partial);
+        Error::io("Failed to store cached image")(e)
+    })?;
+    prune_media_cache(kind, MEDIA_CACHE_MAX_BYTES);
+    Ok(())
+}
+
+/// Delete the least recently used `kind` cache entries until the rest fit in `max_bytes`
+/// Entries another request is still writing are left alone
+fn prune_media_cache(kind: &str, max_bytes: u64) {
+    let Ok(dir) = std::fs::read_dir(std::env::temp_dir()) else {
+        return;
+    };
+    let prefix = format!("{}_", kind);
+    let mut entries: Vec<(std::time::SystemTime, u64, PathBuf)> = dir
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with(&prefix) && !name.contains(".partial-")
+        })
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+        })
+        .collect();
+    
+    let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+    entries.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in entries {
+        if total <= max_bytes {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+}
+
+/// FFmpeg arguments extracting `duration` seconds of audio from `trim_start` into `output_path`
+/// `speech` downmixes to 16 kHz mono, all speech recognition needs
+fn extract_audio_args(
+    video_path: &str,
+    trim_start: f64,
+    duration: f64,
+    output_format: AudioFormat,
+    speech: bool,
+    output_path: &str,
+) -> Vec<String> {
+    let mut args = vec![
+        "-ss".to_string(),
+        trim_start.to_string(),
+        "-i".to_string(),
+        video_path.to_string(),
+        "-t".to_string(),
+        duration.to_string(),
+        "-vn".to_string(), // No video
+        "-acodec".to_string(),
+    ];
+
+    match output_format {
+        AudioFormat::Mp3 => {
+            args.push("libmp3lame".to_string());
+            args.push("-q:a".to_string());
+            args.push("2".to_string()); // High quality
+        }
+        AudioFormat::Wav => {
+            args.push("pcm_s16le".to_string());
+        }
+        AudioFormat::Flac => {
+            args.push("flac".to_string());
+        }
+        AudioFormat::M4a => {
+            args.push("aac".to_string());
+            args.push("-b:a".to_string());
+            args.push("192k".to_string());
+        }
+    }
+
+    if speech {
+        args.extend(["-ar", "16000", "-ac", "1"].map(String::from));
+    }
+
+    args.push("-y".to_string()); // Overwrite
+    args.push(output_path.to_string());
+    args
+}
+
+/// Encoder and output arguments shared by every recording: low-latency x264 at CRF 23
+/// or within a bitrate budget, then the frame rate, output size and path
+fn recording_output_args(
+    output_path: &str,
+    resolution: &str,
+    fps: u32,
+    video_bitrate: Option<u64>,
+    audio_bitrate: Option<u64>,
+) -> Vec<String> {
+    // Low latency for real-time recording
+    let mut args = vec!["-c:v".to_string(), "libx264".to_string(), "-preset".to_string(), "ultrafast".to_string()];
+    match video_bitrate {
+        // A bitrate budget gives predictable file sizes on long recordings
+        Some(bitrate) => args.extend([
+            "-b:v".to_string(),
+            bitrate.to_string(),
+            "-maxrate".to_string(),
+            bitrate.to_string(),
+            "-bufsize".to_string(),
+            (bitrate * 2).to_string(),
+        ]),
+        None => args.extend(["-crf".to_string(), "23".to_string()]),
+    }
+    args.extend(["-r".to_string(), fps.to_string()]);
+    if let Some(bitrate) = audio_bitrate {
+        args.extend(["-b:a".to_string(), bitrate.to_string()]);
+    }
+    if resolution != "source" {
+        args.extend(["-s".to_string(), resolution.to_string()]);
+    }
+    
+    args.extend(["-y".to_string(), output_path.to_string()]); // Overwrite output
+    args
+}
+
+/// Parse a bitrate such as "5M", "2500k" or "800000" into bits per second
+pub fn parse_bitrate(value: &str) -> Result<u64, Error> {
+    let trimmed = value.trim();
+    let (number, multiplier) = match trimmed.chars().last() {
+        Some('k') | Some('K') => (&trimmed[..trimmed.len() - 1], 1_000.0),
+        Some('m') | Some('M') => (&trimmed[..trimmed.len() - 1], 1_000_000.0),
+        Some('g') | Some('G') => (&trimmed[..trimmed.len() - 1], 1_000_000_000.0),
+        _ => (trimmed, 1.0),
+    };
+
+    let parsed: f64 = number
+        .parse()
+        .map_err(|_| Error::InvalidInput(format!("Invalid bitrate: {}", value)))?;
+    if !parsed.is_finite() || parsed <= 0.0 {
+        return Err(Error::InvalidInput(format!("Bitrate must be positive: {}", value)));
+    }
+
+    let bits = (parsed * multiplier).round() as u64;
+    if bits < 1_000 {
+        return Err(Error::InvalidInput(format!("Bitrate is too low to be usable: {}", value)));
+    }
+    Ok(bits)
+}
+
+/// Output frame size for an export resolution: a named preset or "WIDTHxHEIGHT"
+/// (e.g. "1080x1920" for vertical video). None for "source", which keeps clip sizes
+fn resolution_size(resolution: &str) -> Result<Option<(u32, u32)>, Error> {
+    let size = match resolution {
+        "source" => return Ok(None),
+        "480p" => (854, 480),
+        "720p" => (1280, 720),
+        "1080p" => (1920, 1080),
+        "4k" | "2160p" => (3840, 2160),
+        custom => {
+            let parsed = custom
+                .split_once('x')
+                .and_then(|(width, height)| Some((width.parse::<u32>().ok()?, height.parse::<u32>().ok()?)));
+            match parsed {
+                // yuv420p needs even dimensions
+                Some((width, height)) if width > 0 && height > 0 && width % 2 == 0 && height % 2 == 0 => {
+                    (width, height)
+                }
+                _ => {
+                    return Err(Error::InvalidInput(format!(
+                        "Invalid resolution: {} (expected 480p, 720p, 1080p, 4k, source, \
+                         or WIDTHxHEIGHT with positive even sizes)",
+                        resolution
+                    )))
+                }
+            }
+        }
+    };
+    Ok(Some(size))
+}
+
+/// Map an export resolution to an FFmpeg scale size
+fn resolution_scale(resolution: &str) -> Result<String, Error> {
+    Ok(match resolution_size(resolution)? {
+        Some((width, height)) => format!("{}:{}", width, height),
+        None => "-1:-1".to_string(),
+    })
+}
+
+/// Validate a clockwise rotation in degrees (multiples of 90 only)
+fn validate_rotation(rotate: u32) -> Result<(), Error> {
+    match rotate {
+        0 | 90 | 180 | 270 => Ok(()),
+        _ => Err(Error::InvalidInput(format!("Rotation must be 0, 90, 180 or 270 degrees, got {}", rotate))),
+    }
+}
+
+/// Video filters that rotate a frame clockwise by `rotate` degrees, then mirror it
+fn orientation_filters(rotate: u32, flip_horizontal: bool, flip_vertical: bool) -> Vec<&'static str> {
+    let mut filters = match rotate {
+        90 => vec!["transpose=clock"],
+        180 => vec!["hflip", "vflip"],
+        270 => vec!["transpose=cclock"],
+        _ => Vec::new(),
+    };
+    if flip_horizontal {
+        filters.push("hflip");
+    }
+    if flip_vertical {
+        filters.push("vflip");
+    }
+    filters
+}
+
+/// Read the display rotation of an FFprobe video stream as clockwise degrees (0/90/180/270)
+/// Newer FFmpeg reports a display matrix in side_data_list (counter-clockwise),
+/// older files carry a `rotate` tag (clockwise)
+fn parse_rotation(stream: &Value) -> u32 {
+    let side_data_rotation = stream["side_data_list"]
+        .as_array()
+        .and_then(|list| list.iter().find_map(|d| d["rotation"].as_f64()))
+        .map(|ccw| -ccw);
+
+    let tag_rotation = stream["tags"]["rotate"]
+        .as_str()
+        .and_then(|s| s.parse::<f64>().ok());
+
+    let degrees = side_data_rotation.or(tag_rotation).unwrap_or(0.0);
+    let quarter_turns = (degrees / 90.0).round() as i64;
+    (quarter_turns.rem_euclid(4) * 90) as u32
+}
+
+/// Escape a value for use in an FFmetadata file
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escape a file path for use as a filter option value inside a filtergraph
+/// Filter options and the filtergraph each have their own escaping level
+fn escape_filter_path(path: &Path) -> String {
+    let option_value = escape_chars(&path.to_string_lossy(), &['\\', '\'', ':']);
+    escape_chars(&option_value, &['\\', '\'', '[', ']', ',', ';'])
+}
+
+fn escape_chars(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Component names from `ffmpeg -filters` / `ffmpeg -encoders` output
+/// Entries look like " TSC acompressor  A->A  ..." — flags, then the name;
+/// legend lines ("V..... = Video") are skipped
+fn parse_component_list(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.len() >= 3 && tokens[1] != "=" {
+                Some(tokens[1].to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Write chapters to a temporary FFmetadata file that FFmpeg can map chapters from
+fn write_chapters_metadata(chapters: &[Chapter]) -> Result<PathBuf, Error> {
+    let mut metadata = String::from(";FFMETADATA1\n");
+    for chapter in chapters {
+        metadata.push_str("[CHAPTER]\nTIMEBASE=1/1000\n");
+        metadata.push_str(&format!("START={}\n", (chapter.start * 1000.0).round() as u64));
+        metadata.push_str(&format!("END={}\n", (chapter.end * 1000.0).round() as u64));
+        metadata.push_str(&format!("title={}\n", escape_ffmetadata(&chapter.title)));
+    }
+
+    let path = std::env::temp_dir().join(format!("chapters_{}.txt", uuid::Uuid::new_v4()));
+    std::fs::write(&path, metadata)
+        .map_err(Error::io("Failed to write chapter metadata"))?;
+    Ok(path)
+}
+
+/// Optional FFmpeg components available in the binary we ship or found on PATH
+#[derive(Debug, Clone)]
+pub struct FfmpegCapabilities {
+    filters: HashSet<String>,
+    encoders: HashSet<String>,
+    /// The -display_rotation input option (FFmpeg 7+)
+    display_rotation: bool,
+}
+
+impl FfmpegCapabilities {
+    pub fn has_filter(&self, name: &str) -> bool {
+        self.filters.contains(name)
+    }
+
+    pub fn has_encoder(&self, name: &str) -> bool {
+        self.encoders.contains(name)
+    }
+
+    pub fn has_display_rotation(&self) -> bool {
+        self.display_rotation
+    }
+}
+
+#[derive(Clone)]
+pub struct FFmpegExecutor {
+    ffmpeg_path: PathBuf,
+    ffprobe_path: PathBuf,
+    probe_timeout: Duration,
+    thumbnail_timeout: Duration,
+}
+
+/// The FFmpegExecutor resolved once at startup, shared by commands through Tauri state
+/// Keeps the lookup error instead when no usable binaries were found, so the app still
+/// launches and each command reports that error with its original kind
+pub struct FFmpegState(Result<FFmpegExecutor, Error>);
+
+impl FFmpegState {
+    pub fn resolve() -> Self {
+        FFmpegState(FFmpegExecutor::new())
+    }
+    
+    pub fn executor(&self) -> Result<&FFmpegExecutor, Error> {
+        self.0.as_ref().map_err(Error::clone)
+    }
+}
+
+impl FFmpegExecutor {
+    /// Creates a new FFmpegExecutor instance with bundled binary paths
+    /// Uses multi-strategy fallback: production bundle -> development -> system PATH,
+    /// unless FFMPEG_PATH/FFPROBE_PATH point at specific binaries
+    pub fn new() -> Result<Self, Error> {
+        if let Some(executor) = Self::from_env() {
+            return executor;
+        }
+        
+        let mut attempted_paths = Vec::new();
+        
+        // Strategy 1: Production app bundle Resources directory
+        let exe_path = std::env::current_exe()
+            .map_err(Error::io("Failed to get executable path"))?;
+        
+        let resources_binaries = exe_path
+            .parent()                          // Contents/MacOS/ -> Contents/
+            .and_then(|p| p.parent())          // Contents/ -> MyApp.app/
+            .and_then(|p| p.parent())          // MyApp.app/ -> parent dir
+            .map(|p| p.join("Contents").join("Resources").join("binaries"));
+        
+        if let Some(ref path) = resources_binaries {
+            attempted_paths.push(format!("Production Resources: {}", path.display()));
+            let ffmpeg = path.join("ffmpeg");
+            let ffprobe = path.join("ffprobe");
+            
+            if ffmpeg.exists() && ffprobe.exists() {
+                let executor = Self::with_paths(ffmpeg, ffprobe);
+                match executor.validate() {
+                    Ok(()) => {
+                        eprintln!("✓ Found FFmpeg binaries in production Resources:");
+                        eprintln!("  ffmpeg:  {:?}", executor.ffmpeg_path);
+                        eprintln!("  ffprobe: {:?}", executor.ffprobe_path);
+                        return Ok(executor);
+                    }
+                    Err(e) => attempted_paths.push(format!("  rejected: {}", e)),
+                }
+            }
+        }
+        
+        // Strategy 2: Development manifest directory
+        if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
+            let binaries_dir = PathBuf::from(manifest_dir).join("binaries");
+            attempted_paths.push(format!("Development manifest: {}", binaries_dir.display()));
+            
+            let ffmpeg = binaries_dir.join("ffmpeg");
+            let ffprobe = binaries_dir.join("ffprobe");
+            
+            if ffmpeg.exists() && ffprobe.exists() {
+                let executor = Self::with_paths(ffmpeg, ffprobe);
+                match executor.validate() {
+                    Ok(()) => {
+                        eprintln!("✓ Found FFmpeg binaries in development:");
+                        eprintln!("  ffmpeg:  {:?}", executor.ffmpeg_path);
+                        eprintln!("  ffprobe: {:?}", executor.ffprobe_path);
+                        return Ok(executor);
+                    }
+                    Err(e) => attempted_paths.push(format!("  rejected: {}", e)),
+                }
+            }
+        }
+        
+        // Strategy 3: System PATH (fallback)
+        attempted_paths.push("System PATH".to_string());
+        if let Ok(ffmpeg_path) = which::which("ffmpeg") {
+            if let Ok(ffprobe_path) = which::which("ffprobe") {
+                let executor = Self::with_paths(ffmpeg_path, ffprobe_path);
+                match executor.validate() {
+                    Ok(()) => {
+                        eprintln!("✓ Found FFmpeg binaries in system PATH:");
+                        eprintln!("  ffmpeg:  {:?}", executor.ffmpeg_path);
+                        eprintln!("  ffprobe: {:?}", executor.ffprobe_path);
+                        return Ok(executor);
+                    }
+                    Err(e) => attempted_paths.push(format!("  rejected: {}", e)),
+                }
+            }
+        }
+        
+        // All strategies failed - provide detailed error
+        Err(Error::FfmpegNotFound(format!(
+            "FFmpeg binaries not found. Attempted paths:\n{}",
+            attempted_paths.join("\n")
+        )))
+    }
+    
+    /// Executor for a pair of binaries, with timeouts from the environment or the defaults
+    pub(crate) fn with_paths(ffmpeg_path: PathBuf, ffprobe_path: PathBuf) -> Self {
+        Self {
+            ffmpeg_path,
+            ffprobe_path,
+            probe_timeout: timeout_from_env("FFMPEG_PROBE_TIMEOUT", PROBE_TIMEOUT),
+            thumbnail_timeout: timeout_from_env("FFMPEG_THUMBNAIL_TIMEOUT", THUMBNAIL_TIMEOUT),
+        }
+    }
+    
+    /// Use the binaries named by FFMPEG_PATH/FFPROBE_PATH (e.g. a build with NVENC)
+    /// If only one is set, the other is looked for next to it
+    /// Returns None when neither is set; an override that doesn't work is an error
+    /// rather than a silent fallback to the bundled binaries
+    fn from_env() -> Option<Result<Self, Error>> {
+        let ffmpeg_env = std::env::var_os("FFMPEG_PATH").map(PathBuf::from);
+        let ffprobe_env = std::env::var_os("FFPROBE_PATH").map(PathBuf::from);
+        
+        let sibling = |path: &PathBuf, name: &str| {
+            path.with_file_name(format!("{}{}", name, std::env::consts::EXE_SUFFIX))
+        };
+        let (ffmpeg_path, ffprobe_path) = match (ffmpeg_env, ffprobe_env) {
+            (None, None) => return None,
+            (Some(ffmpeg), Some(ffprobe)) => (ffmpeg, ffprobe),
+            (Some(ffmpeg), None) => {
+                let ffprobe = sibling(&ffmpeg, "ffprobe");
+                (ffmpeg, ffprobe)
+            }
+            (None, Some(ffprobe)) => (sibling(&ffprobe, "ffmpeg"), ffprobe),
+        };
+        
+        for path in [&ffmpeg_path, &ffprobe_path] {
+            if !path.is_file() {
+                return Some(Err(Error::FfmpegNotFound(format!(
+                    "FFMPEG_PATH/FFPROBE_PATH override points at a missing file: {}",
+                    path.display()
+                ))));
+            }
+        }
+        
+        let executor = Self::with_paths(ffmpeg_path, ffprobe_path);
+        Some(match executor.validate() {
+            Ok(()) => {
+                eprintln!("✓ Using FFmpeg binaries from FFMPEG_PATH/FFPROBE_PATH:");
+                eprintln!("  ffmpeg:  {:?}", executor.ffmpeg_path);
+                eprintln!("  ffprobe: {:?}", executor.ffprobe_path);
+                Ok(executor)
+            }
+            Err(e) => Err(Error::FfmpegNotFound(format!(
+                "FFMPEG_PATH/FFPROBE_PATH override can't be used: {}",
+                e
+            ))),
+        })
+    }
+    
+    /// Check both binaries actually run on this machine
+    /// Catches binaries that exist but can't execute, e.g. an x86 build on Apple Silicon
+    /// without Rosetta ("Bad CPU type in executable") or one blocked by quarantine
+    pub fn validate(&self) -> Result<(), Error> {
+        for binary in [&self.ffmpeg_path, &self.ffprobe_path] {
+            let output = run_with_timeout(Command::new(binary).arg("-version"), BINARY_CHECK_TIMEOUT)
+                .map_err(Error::io(format!("{} could not be run", binary.display())))?;
+            
+            if output.timed_out() {
+                return Err(Error::FfmpegNotFound(format!("{} did not respond to -version", binary.display())));
+            }
+            if !output.status.map(|status| status.success()).unwrap_or(false) {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(Error::process(format!("{} failed to run", binary.display()), stderr));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Get metadata from a video file using FFprobe
+    pub fn get_metadata(&self, file_path: &str) -> Result<MediaMetadata, Error> {
+        let output = output_with_timeout(
+            Command::new(&self.ffprobe_path).args([
+                "-v", "quiet",
+                "-print_format", "json",
+                "-show_format",
+                "-show_streams",
+                file_path
+            ]),
+            self.probe_timeout,
+            &format!("FFprobe reading {}", file_path),
+        )?;
+        
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let status_code = output.status.code().unwrap_or(-1);
+            return Err(Error::process(
+                format!("FFprobe failed on \"{}\" (exit code: {})", file_path, status_code),
+                format!("{}\n{}", stderr, stdout),
+            ));
+        }
+        
+        let json: Value = serde_json::from_slice(&output.stdout)
+            .map_err(|e| Error::process("Failed to parse FFprobe output", e.to_string()))?;
+        
+        self.parse_metadata(json)
+    }
+    
+    /// Get the display rotation (clockwise degrees) of a file's first video stream
+    pub fn get_rotation(&self, file_path: &str) -> Result<u32, Error> {
+        let output = output_with_timeout(
+            Command::new(&self.ffprobe_path).args([
+                "-v", "quiet",
+                "-print_format", "json",
+                "-show_streams",
+                "-select_streams", "v:0",
+                file_path
+            ]),
+            self.probe_timeout,
+            &format!("FFprobe reading {}", file_path),
+        )?;
+
+        if !output.status.success() {
+            return Err(Error::process("FFprobe failed to read rotation", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        let json: Value = serde_json::from_slice(&output.stdout)
+            .map_err(|e| Error::process("Failed to parse FFprobe output", e.to_string()))?;
+
+        let stream = json["streams"]
+            .as_array()
+            .and_then(|streams| streams.first())
+            .ok_or_else(|| Error::InvalidInput(format!("No video stream found in {}", file_path)))?;
+
+        Ok(parse_rotation(stream))
+    }
+    
+    /// Parse FFprobe JSON output into MediaMetadata
+    fn parse_metadata(&self, json: Value) -> Result<MediaMetadata, Error> {
+        // Extract video stream info
+        let streams = json["streams"].as_array()
+            .ok_or_else(|| Error::process("Failed to read media metadata", "FFprobe listed no streams"))?;
+        
+        let video_stream = streams.iter()
+            .find(|s| s["codec_type"].as_str() == Some("video"))
+            .ok_or_else(|| Error::InvalidInput("No video stream found".to_string()))?;
+        
+        let width = video_stream["width"]
+            .as_u64()
+            .ok_or_else(|| Error::process("Failed to read media metadata", "missing width"))? as u32;
+        
+        let height = video_stream["height"]
+            .as_u64()
+            .ok_or_else(|| Error::process("Failed to read media metadata", "missing height"))? as u32;
+        
+        let fps_str = video_stream["r_frame_rate"].as_str()
+            .ok_or_else(|| Error::process("Failed to read media metadata", "missing frame rate"))?;
+        let fps = self.parse_fps(fps_str)?;
+        
+        // Some webm and fragmented MP4 files have no container duration, so fall back
+        // to the video stream's duration, then to its frame count
+        let parse_seconds = |value: &Value| value.as_str().and_then(|s| s.parse::<f64>().ok());
+        let duration = parse_seconds(&json["format"]["duration"])
+            .or_else(|| parse_seconds(&video_stream["duration"]))
+            .or_else(|| {
+                video_stream["nb_frames"]
+                    .as_str()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .filter(|_| fps > 0.0)
+                    .map(|frames| frames as f64 / fps)
+            })
+            .ok_or_else(|| {
+                Error::process(
+                    "Failed to read media metadata",
+                    "no container duration, stream duration or frame count",
+                )
+            })?;
+        
+        let codec = video_stream["codec_name"]
+            .as_str()
+            .unwrap_or("unknown")
+            .to_string();
+        
+        let bitrate = json["format"]["bit_rate"]
+            .as_str()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0);
+        
+        let file_size = json["format"]["size"]
+            .as_str()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0);
+        
+        // Audio is optional: video-only files simply leave these fields empty
+        let audio_stream = streams.iter()
+            .find(|s| s["codec_type"].as_str() == Some("audio"));
+        let audio_codec = audio_stream
+            .and_then(|s| s["codec_name"].as_str())
+            .map(String::from);
+        // FFprobe reports sample_rate and bit_rate as strings
+        let sample_rate = audio_stream
+            .and_then(|s| s["sample_rate"].as_str())
+            .and_then(|s| s.parse::<u32>().ok());
+        let channels = audio_stream
+            .and_then(|s| s["channels"].as_u64())
+            .map(|c| c as u32);
+        let audio_bitrate = audio_stream
+            .and_then(|s| s["bit_rate"].as_str())
+            .and_then(|s| s.parse::<u64>().ok());
+        
+        Ok(MediaMetadata {
+            duration,
+            width,
+            height,
+            fps,
+            codec,
+            bitrate,
+            file_size,
+            rotation: parse_rotation(video_stream),
+            audio_codec,
+            sample_rate,
+            channels,
+            audio_bitrate,
+        })
+    }
+    
+    /// Parse FPS string (handles fractional rates like "30000/1001")
+    fn parse_fps(&self, fps_str: &str) -> Result<f64, Error> {
+        let parts: Vec<&str> = fps_str.split('/').collect();
+        if parts.len() == 2 {
+            let num = parts[0].parse::<f64>()
+                .map_err(|_| Error::process("Failed to read media metadata", format!("invalid frame rate {}", fps_str)))?;
+            let den = parts[1].parse::<f64>()
+                .map_err(|_| Error::process("Failed to read media metadata", format!("invalid frame rate {}", fps_str)))?;
+            if den == 0.0 {
+                return Err(Error::InvalidInput("FPS denominator cannot be zero".to_string()));
+            }
+            Ok(num / den)
+        } else {
+            fps_str.parse::<f64>()
+                .map_err(|_| Error::InvalidInput(format!("Invalid FPS format: {}", fps_str)))
+        }
+    }
+    
+    /// Generate a thumbnail at a specific timestamp
+    pub fn generate_thumbnail(
+        &self,
+        file_path: &str,
+        timestamp: f64,
+        output_path: &str,
+        search_window: Option<f64>,
+    ) -> Result<(), Error> {
+        // Apply the display rotation explicitly so portrait phone footage
+        // comes out upright, matching what players show
+        let rotation = self.get_rotation(file_path).unwrap_or(0);
+        
+        let mut args = vec![
+            "-ss".to_string(),
+            timestamp.to_string(),
+        ];
+        
+        let mut filters = Vec::new();
+        
+        // Pick the most representative frame in the window instead of the exact
+        // timestamp, avoiding motion-blurred or dark posters
+        if let Some(window) = search_window {
+            if !window.is_finite() || window <= 0.0 {
+                return Err(Error::InvalidInput(format!("Thumbnail search window must be positive, got {}", window)));
+            }
+            args.push("-t".to_string());
+            args.push(window.to_string());
+            // Size the batch for 60 fps so the whole window is considered;
+            // at lower frame rates the filter picks from the partial batch at end of input
+            filters.push(format!("thumbnail=n={}", (window * 60.0).ceil().max(2.0) as u32));
+        }
+        
+        args.extend_from_slice(&[
+            "-noautorotate".to_string(),
+            "-i".to_string(),
+            file_path.to_string(),
+        ]);
+        
+        if rotation != 0 {
+            filters.extend(orientation_filters(rotation, false, false).into_iter().map(String::from));
+        }
+        
+        if !filters.is_empty() {
+            args.push("-vf".to_string());
+            args.push(filters.join(","));
+        }
+        
+        args.extend_from_slice(&[
+            "-vframes".to_string(),
+            "1".to_string(),
+            "-q:v".to_string(),
+            "2".to_string(),
+            "-f".to_string(),
+            "image2".to_string(),
+            output_path.to_string(),
+        ]);
+        
+        let output = output_with_timeout(
+            Command::new(&self.ffmpeg_path).args(&args),
+            self.thumbnail_timeout,
+            &format!("Thumbnail generation at {}s", timestamp),
+        )?;
+        
+        if !output.status.success() {
+            return Err(Error::process("Thumbnail generation failed", String::from_utf8_lossy(&output.stderr)));
+        }
+        
+        Ok(())
+    }
+    
+    /// Extract a frame at each timestamp to the matching output path
+    /// Each timestamp becomes a fast-seeked input with its own output, so a whole
+    /// batch costs one FFmpeg process instead of one per frame
+    pub fn generate_thumbnails(&self, file_path: &str, timestamps: &[f64], output_paths: &[String]) -> Result<(), Error> {
+        if timestamps.len() != output_paths.len() {
+            return Err(Error::InvalidInput("Each thumbnail timestamp needs an output path".to_string()));
+        }
+        if let Some(timestamp) = timestamps.iter().find(|t| !t.is_finite() || **t < 0.0) {
+            return Err(Error::InvalidInput(format!("Invalid timestamp: {}", timestamp)));
+        }
+        
+        // Rotate explicitly, as generate_thumbnail does, so portrait footage comes out upright
+        let rotation = self.get_rotation(file_path).unwrap_or(0);
+        let orientation = orientation_filters(rotation, false, false).join(",");
+        
+        for (batch_timestamps, batch_paths) in timestamps
+            .chunks(THUMBNAIL_BATCH_SIZE)
+            .zip(output_paths.chunks(THUMBNAIL_BATCH_SIZE))
+        {
+            let mut args = vec!["-y".to_string()];
+            for timestamp in batch_timestamps {
+                args.extend_from_slice(&[
+                    "-ss".to_string(),
+                    timestamp.to_string(),
+                    "-noautorotate".to_string(),
+                    "-i".to_string(),
+                    file_path.to_string(),
+                ]);
+            }
+            for (input, path) in batch_paths.iter().enumerate() {
+                args.extend_from_slice(&["-map".to_string(), format!("{}:v:0", input)]);
+                if !orientation.is_empty() {
+                    args.extend_from_slice(&["-vf".to_string(), orientation.clone()]);
+                }
+                args.extend_from_slice(&[
+                    "-frames:v".to_string(),
+                    "1".to_string(),
+                    "-q:v".to_string(),
+                    "2".to_string(),
+                    "-f".to_string(),
+                    "image2".to_string(),
+                    path.clone(),
+                ]);
+            }
+            
+            // Each frame in the batch gets the single-thumbnail budget
+            let output = output_with_timeout(
+                Command::new(&self.ffmpeg_path).args(&args),
+                self.thumbnail_timeout * batch_timestamps.len() as u32,
+                &format!("Generating {} thumbnails", batch_timestamps.len()),
+            )?;
+            
+            if !output.status.success() {
+                return Err(Error::process("Thumbnail generation failed", String::from_utf8_lossy(&output.stderr)));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Tile `count` evenly spaced frames of a video into one sprite image
+    /// Sprites are cached in the temp directory by path, modification time and count,
+    /// so reopening a project reuses them instead of decoding the file again
+    /// (up to MEDIA_CACHE_MAX_BYTES, least recently used dropped first)
+    pub fn generate_filmstrip(&self, file_path: &str, count: u32) -> Result<Filmstrip, Error> {
+        if count == 0 || count > FILMSTRIP_MAX_FRAMES {
+            return Err(Error::InvalidInput(format!("Filmstrip frame count must be between 1 and {}, got {}", FILMSTRIP_MAX_FRAMES, count)));
+        }
+        
+        let metadata = self.get_metadata(file_path)?;
+        if metadata.duration <= 0.0 || metadata.width == 0 || metadata.height == 0 {
+            return Err(Error::InvalidInput(format!("Can't build a filmstrip for {}: no video frames", file_path)));
+        }
+        
+        // FFmpeg autorotates while decoding, so portrait footage comes out with swapped sides
+        let (width, height) = match metadata.rotation {
+            90 | 270 => (metadata.height, metadata.width),
+            _ => (metadata.width, metadata.height),
+        };
+        let cell_height = FILMSTRIP_CELL_HEIGHT;
+        let cell_width = ((cell_height as f64 * width as f64 / height as f64 / 2.0).round() as u32 * 2).max(2);
+        let columns = count.min(FILMSTRIP_MAX_COLUMNS);
+        let rows = count.div_ceil(columns);
+        
+        let output_path = media_cache_path("filmstrip", file_path, &count.to_string(), "jpg")?;
+        let filmstrip = Filmstrip {
+            path: path_str(&output_path)?.to_string(),
+            cell_width,
+            cell_height,
+            columns,
+            rows,
+        };
+        if media_cache_hit(&output_path) {
+            return Ok(filmstrip);
+        }
+        let partial_path = media_cache_partial_path(&output_path);
+        
+        // One frame from the middle of each of `count` equal slices of the video
+        let filter = format!(
+            "fps={}/{},scale={}:{},setsar=1,tile={}x{}",
+            count, metadata.duration, cell_width, cell_height, columns, rows
+        );
+        let output = Command::new(&self.ffmpeg_path)
+            .args([
+                "-y",
+                "-ss",
+                &(metadata.duration / count as f64 / 2.0).to_string(),
+                "-i",
+                file_path,
+                "-vf",
+                &filter,
+                "-frames:v",
+                "1",
+                "-q:v",
+                "4",
+            ])
+            .arg(&partial_path)
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+        
+        if !output.status.success() {
+            let _ = std::fs::remove_file(&partial_path);
+            return Err(Error::process("Filmstrip generation failed", String::from_utf8_lossy(&output.stderr)));
+        }
+        commit_media_cache(&partial_path, &output_path, "filmstrip")?;
+        
+        Ok(filmstrip)
+    }
+    
+    /// Timestamps (seconds) where the picture changes by more than `threshold`
+    /// (0.0-1.0, FFmpeg's scene score), in order and capped at `max_results`
+    pub fn detect_scene_changes(&self, file_path: &str, threshold: f64, max_results: usize) -> Result<Vec<f64>, Error> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(Error::InvalidInput(format!("Scene threshold must be between 0.0 and 1.0, got {}", threshold)));
+        }
+        let max_results = max_results.clamp(1, SCENE_CHANGES_MAX);
+        
+        // showinfo logs each selected frame's pts_time to stderr; nothing is written
+        let output = Command::new(&self.ffmpeg_path)
+            .args([
+                "-hide_banner",
+                "-nostats",
+                "-i",
+                file_path,
+                "-an",
+                "-vf",
+                &format!("select='gt(scene,{})',showinfo", threshold),
+                "-f",
+                "null",
+                "-",
+            ])
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+        
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            return Err(Error::process("Scene detection failed", &stderr));
+        }
+        
+        Ok(stderr
+            .lines()
+            .filter(|line| line.contains("Parsed_showinfo"))
+            .filter_map(|line| {
+                let value = line.split("pts_time:").nth(1)?;
+                value.split_whitespace().next()?.parse::<f64>().ok()
+            })
+            .take(max_results)
+            .collect())
+    }
+    
+    /// Render a clip region's audio as a waveform PNG of `width`x`height` in `color`
+    /// `duration` None runs to the end of the file. Images are cached in the temp
+    /// directory by path, modification time, range, size and color, since the
+    /// timeline asks for the same waveforms again and again while editing
+    /// (up to MEDIA_CACHE_MAX_BYTES, least recently used dropped first)
+    pub fn generate_waveform(
+        &self,
+        file_path: &str,
+        trim_start: f64,
+        duration: Option<f64>,
+        width: u32,
+        height: u32,
+        color: &str,
+    ) -> Result<PathBuf, Error> {
+        if !(1..=WAVEFORM_MAX_WIDTH).contains(&width) || !(1..=WAVEFORM_MAX_HEIGHT).contains(&height) {
+            return Err(Error::InvalidInput(format!(
+                "Waveform size must be at most {}x{}, got {}x{}",
+                WAVEFORM_MAX_WIDTH, WAVEFORM_MAX_HEIGHT, width, height
+            )));
+        }
+        if !trim_start.is_finite() || trim_start < 0.0 {
+            return Err(Error::InvalidInput(format!("Invalid waveform start: {}", trim_start)));
+        }
+        if let Some(duration) = duration.filter(|d| !d.is_finite() || *d <= 0.0) {
+            return Err(Error::InvalidInput(format!("Invalid waveform duration: {}", duration)));
+        }
+        validate_color(color)?;
+        
+        let key = format!(
+            "{}\0{:?}\0{}x{}\0{}",
+            trim_start.to_bits(),
+            duration.map(f64::to_bits),
+            width,
+            height,
+            color
+        );
+        let output_path = media_cache_path("waveform", file_path, &key, "png")?;
+        if media_cache_hit(&output_path) {
+            return Ok(output_path);
+        }
+        let partial_path = media_cache_partial_path(&output_path);
+        
+        if !self.has_audio_stream(file_path)? {
+            return Err(Error::InvalidInput(format!("{} has no audio to draw a waveform from", file_path)));
+        }
+        
+        let mut args = vec!["-y".to_string(), "-ss".to_string(), trim_start.to_string()];
+        if let Some(duration) = duration {
+            args.extend(["-t".to_string(), duration.to_string()]);
+        }
+        args.extend([
+            "-i".to_string(),
+            file_path.to_string(),
+            "-filter_complex".to_string(),
+            // Mix down to one channel so the picture is a single waveform
+            format!(
+                "[0:a]aformat=channel_layouts=mono,showwavespic=s={}x{}:colors={}",
+                width, height, color
+            ),
+            "-frames:v".to_string(),
+            "1".to_string(),
+        ]);
+        args.push(path_str(&partial_path)?.to_string());
+        
+        let output = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+        
+        if !output.status.success() {
+            let _ = std::fs::remove_file(&partial_path);
+            return Err(Error::process("Waveform generation failed", String::from_utf8_lossy(&output.stderr)));
+        }
+        commit_media_cache(&partial_path, &output_path, "waveform")?;
+        
+        Ok(output_path)
+    }
+    
+    /// Average color of the frame at a timestamp as a "#rrggbb" hex string
+    /// FFmpeg does the averaging by area-scaling the frame down to a single pixel
+    pub fn get_frame_color(&self, file_path: &str, timestamp: f64) -> Result<String, Error> {
+        if !timestamp.is_finite() || timestamp < 0.0 {
+            return Err(Error::InvalidInput(format!("Invalid timestamp: {}", timestamp)));
+        }
+        
+        let output = Command::new(&self.ffmpeg_path)
+            .args([
+                "-v", "error",
+                "-ss", &timestamp.to_string(),
+                "-i", file_path,
+                "-vf", "scale=1:1:flags=area",
+                "-frames:v", "1",
+                "-pix_fmt", "rgb24",
+                "-f", "rawvideo",
+                "pipe:1",
+            ])
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+        
+        if !output.status.success() {
+            return Err(Error::process("Frame color extraction failed", String::from_utf8_lossy(&output.stderr)));
+        }
+        
+        match output.stdout.as_slice() {
+            [r, g, b, ..] => Ok(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+            _ => Err(Error::InvalidInput(format!("No frame found at {}s in {}", timestamp, file_path))),
+        }
+    }
+    
+    /// Render the composed timeline frame at a timeline timestamp to a PNG
+    /// Uses the export filtergraph, so gaps, transitions and finishing filters look
+    /// exactly as they will in the export; a timestamp in a gap gives a gap frame
+    #[allow(clippy::too_many_arguments)]
+    pub fn export_timeline_frame(
+        &self,
+        clips: &[ClipInfo],
+        timestamp: f64,
+        output_path: &str,
+        resolution: &str,
+        fps: u32,
+        composition_length: f64,
+        options: &ExportOptions,
+    ) -> Result<(), Error> {
+        if clips.is_empty() {
+            return Err(Error::InvalidInput("No clips to render a frame from".to_string()));
+        }
+        validate_main_track(clips)?;
+        
+        if !composition_length.is_finite() || composition_length <= 0.0 {
+            return Err(Error::InvalidInput(format!("Invalid composition length: {}", composition_length)));
+        }
+        if !timestamp.is_finite() || timestamp < 0.0 || timestamp >= composition_length {
+            return Err(Error::InvalidInput(format!(
+                "Timestamp {}s is outside the composition (0–{}s)",
+                timestamp, composition_length
+            )));
+        }
+        
+        let segments = timeline_segments(clips, &options.gaps, composition_length)?;
+        let rendered_length = composition_length - crossfade_overlap(&segments);
+        let render_time = render_time(&segments, timestamp);
+        
+        // Overlay clips are probed after the main clips, matching their input order
+        let mut probes = self.probe_clips(clips)?;
+        probes.extend(self.probe_clips(&options.overlay_clips())?);
+        let mut filter_complex = self.build_filter_complex(
+            &segments,
+            resolution,
+            fps,
+            rendered_length,
+            options,
+            None,
+            &probes,
+            None,
+        )?;
+        filter_complex.push_str(";[outa]anullsink");
+        
+        let mut args = vec!["-y".to_string()];
+        args.extend(timeline_input_args(clips, &segments, fps, options));
+        args.extend_from_slice(&[
+            "-filter_complex".to_string(),
+            filter_complex,
+            "-map".to_string(),
+            "[outv]".to_string(),
+            // Output-side seek: frames before the timestamp are decoded and discarded
+            "-ss".to_string(),
+            render_time.to_string(),
+            "-frames:v".to_string(),
+            "1".to_string(),
+            "-f".to_string(),
+            "image2".to_string(),
+            "-c:v".to_string(),
+            "png".to_string(),
+            output_path.to_string(),
+        ]);
+        
+        let output = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+        
+        if !output.status.success() {
+            return Err(Error::process("Timeline frame rendering failed", String::from_utf8_lossy(&output.stderr)));
+        }
+        
+        Ok(())
+    }
+    
+    /// Export video with clips and settings
+    /// `on_progress` receives the percent complete as FFmpeg renders
+    /// Returns warnings about the result that didn't stop the export (e.g. frame rate mismatches)
+    #[allow(clippy::too_many_arguments)]
+    pub fn export_video(
+        &self,
+        clips: &[ClipInfo],
+        output_path: &str,
+        resolution: &str,
+        fps: u32,
+        composition_length: f64,
+        options: &ExportOptions,
+        on_progress: &mut dyn FnMut(f64),
+    ) -> Result<Vec<String>, Error> {
+        if clips.is_empty() {
+            return Err(Error::InvalidInput("No clips to export".to_string()));
+        }
+        
+        validate_main_track(clips)?;
+        
+        if !composition_length.is_finite() || composition_length < 0.0 {
+            return Err(Error::InvalidInput(format!("Invalid composition length: {}", composition_length)));
+        }
+        
+        // Allow a millisecond of slack for floating-point drift in the frontend's sum
+        let minimum_length = compute_composition_length(clips);
+        if composition_length < minimum_length - 0.001 {
+            return Err(Error::InvalidInput(format!(
+                "Composition length ({}s) is shorter than the clips it contains ({}s)",
+                composition_length, minimum_length
+            )));
+        }
+        
+        if options.strip_metadata && options.copy_metadata {
+            return Err(Error::InvalidInput("strip_metadata and copy_metadata cannot both be enabled".to_string()));
+        }
+        
+        if fps == 0 || fps > MAX_EXPORT_FPS {
+            return Err(Error::InvalidInput(format!("Frame rate must be between 1 and {} fps, got {}", MAX_EXPORT_FPS, fps)));
+        }
+        
+        if let Some(timecode) = &options.start_timecode {
+            validate_timecode(timecode, fps)?;
+        }
+        
+        options.quality.validate(options.codec)?;
+        options.video_bitrate.as_deref().map(parse_bitrate).transpose()?;
+        
+        match options.format {
+            ExportFormat::Video => {
+                options.codec.validate_container(output_path)?;
+                let software_encoder_needed = options.codec != VideoCodec::H264 && self.hardware_encoder(options)?.is_none();
+                if software_encoder_needed && !self.probe_capabilities()?.has_encoder(options.codec.encoder()) {
+                    return Err(Error::InvalidInput(format!("This FFmpeg build doesn't include the {} encoder", options.codec.encoder())));
+                }
+                if let Some(target_size_mb) = options.target_size_mb {
+                    if options.codec != VideoCodec::H264 || options.hardware_encoding {
+                        return Err(Error::InvalidInput("Target file size requires software H.264 encoding".to_string()));
+                    }
+                    if !options.extra_outputs.is_empty() {
+                        return Err(Error::InvalidInput("Extra outputs aren't supported with a target file size".to_string()));
+                    }
+                    target_video_bitrate(target_size_mb, composition_length, !options.strip_audio)?;
+                }
+                if options.color_tagging.is_hdr() {
+                    if options.codec.ten_bit_args().is_none() {
+                        return Err(Error::InvalidInput("HDR (PQ) exports need a 10-bit codec: HEVC, VP9 or ProRes".to_string()));
+                    }
+                    if self.hardware_encoder(options)?.is_some() {
+                        return Err(Error::InvalidInput("HDR (PQ) exports require software encoding".to_string()));
+                    }
+                    if !options.extra_outputs.is_empty() {
+                        // Extra renditions are 8-bit H.264
+                        return Err(Error::InvalidInput("Extra outputs aren't supported for HDR (PQ) exports".to_string()));
+                    }
+                }
+            }
+            ExportFormat::Gif => {
+                options.gif.validate()?;
+                if !output_path.to_lowercase().ends_with(".gif") {
+                    return Err(Error::InvalidInput("GIF export requires a .gif output path".to_string()));
+                }
+                if composition_length > MAX_GIF_DURATION {
+                    return Err(Error::InvalidInput(format!(
+                        "GIF exports are limited to {}s; this composition is {:.1}s. Trim it or export video instead",
+                        MAX_GIF_DURATION, composition_length
+                    )));
+                }
+                if !options.extra_outputs.is_empty() {
+                    return Err(Error::InvalidInput("Extra outputs aren't supported for GIF exports".to_string()));
+                }
+                if options.target_size_mb.is_some() {
+                    return Err(Error::InvalidInput("A target file size isn't supported for GIF exports".to_string()));
+                }
+            }
+            ExportFormat::Audio => {
+                options.audio.validate(output_path)?;
+                if options.strip_audio {
+                    return Err(Error::InvalidInput("An audio-only export can't also strip the audio".to_string()));
+                }
+                if !options.extra_outputs.is_empty() {
+                    return Err(Error::InvalidInput("Extra outputs aren't supported for audio-only exports".to_string()));
+                }
+                if options.target_size_mb.is_some() {
+                    return Err(Error::InvalidInput("A target file size isn't supported for audio-only exports".to_string()));
+                }
+            }
+        }
+        if let Some(path) = &options.lut_path {
+            validate_lut_path(path)?;
+        }
+        if let Some(watermark) = &options.watermark {
+            watermark.validate()?;
+        }
+        options.validate_overlay_tracks()?;
+        validate_output_path(output_path)?;
+        
+        for spec in &options.extra_outputs {
+            if spec.path == output_path {
+                return Err(Error::InvalidInput(format!("Extra output path must differ from the main output: {}", spec.path)));
+            }
+            if spec.crf > 51 {
+                return Err(Error::InvalidInput(format!("CRF must be between 0 and 51, got {}", spec.crf)));
+            }
+            validate_output_path(&spec.path)?;
+        }
+        
+        // Overlay clips are probed after the main clips, matching their input order
+        let overlay_clips = options.overlay_clips();
+        let mut probes = self.probe_clips(clips)?;
+        probes.extend(self.probe_clips(&overlay_clips)?);
+        let warnings = match options.format {
+            ExportFormat::Video => {
+                Self::frame_rate_warnings(clips.iter().chain(&overlay_clips), &probes, fps, options.frame_interpolation)
+            }
+            _ => Vec::new(),
+        };
+        
+        // Stabilization analyses the clip in a first pass; the transforms file
+        // it writes is consumed by the export pass and removed afterwards
+        let transforms = match &options.stabilization {
+            Some(stabilization) => {
+                if clips.len() != 1 {
+                    return Err(Error::InvalidInput("Stabilization is only supported when exporting a single clip".to_string()));
+                }
+                if stabilization.smoothing > 1000 {
+                    return Err(Error::InvalidInput(format!(
+                        "Stabilization smoothing must be between 0 and 1000, got {}",
+                        stabilization.smoothing
+                    )));
+                }
+                let capabilities = self.probe_capabilities()?;
+                if !capabilities.has_filter("vidstabdetect") || !capabilities.has_filter("vidstabtransform") {
+                    return Err(Error::InvalidInput("Stabilization requires an FFmpeg build with libvidstab".to_string()));
+                }
+                Some(self.detect_camera_motion(&clips[0], options, &mut |percent| {
+                    on_progress(percent * STABILIZATION_ANALYSIS_PROGRESS / 100.0)
+                })?)
+            }
+            None => None,
+        };
+        let render_start = if transforms.is_some() { STABILIZATION_ANALYSIS_PROGRESS } else { 0.0 };
+        
+        let result = self.render_export(
+            clips,
+            output_path,
+            resolution,
+            fps,
+            composition_length,
+            options,
+            transforms.as_deref(),
+            &probes,
+            &mut |percent| on_progress(render_start + percent * (100.0 - render_start) / 100.0),
+        );
+        
+        if let Some(path) = transforms {
+            let _ = std::fs::remove_file(path);
+        }
+        
+        result.map(|()| warnings)
+    }
+    
+    /// Optional components available in this FFmpeg build
+    /// Probed once and cached, since the binary can't change while the app runs
+    pub fn probe_capabilities(&self) -> Result<&'static FfmpegCapabilities, Error> {
+        if let Some(capabilities) = CAPABILITIES.get() {
+            return Ok(capabilities);
+        }
+        
+        let filters = self.list_components("-filters")?;
+        let encoders = self.list_components("-encoders")?;
+        let display_rotation = self.has_option("-display_rotation")?;
+        Ok(CAPABILITIES.get_or_init(|| FfmpegCapabilities { filters, encoders, display_rotation }))
+    }
+    
+    /// Whether the full help lists a command-line option, for options newer than
+    /// some of the FFmpeg builds we may find on PATH
+    fn has_option(&self, option: &str) -> Result<bool, Error> {
+        let output = Command::new(&self.ffmpeg_path)
+            .args(["-hide_banner", "-h", "long"])
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+        
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            // Newer builds append the stream specifier: -display_rotation[:<stream_spec>]
+            .filter_map(|line| line.split_whitespace().next())
+            .any(|name| name.split(['[', ':']).next() == Some(option)))
+    }
+    
+    fn list_components(&self, flag: &str) -> Result<HashSet<String>, Error> {
+        let output = Command::new(&self.ffmpeg_path)
+            .args(["-hide_banner", flag])
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+        
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::process(format!("Failed to list FFmpeg {}", flag.trim_start_matches('-')), stderr));
+        }
+        
+        Ok(parse_component_list(&String::from_utf8_lossy(&output.stdout)))
+    }
+    
+    /// Warnings for clips whose frame rate (after speed changes) is far from the export's:
+    /// repeated frames bloat the file and look choppy, dropped frames can stutter
+    /// `probes` follow `clips`, main track then overlay clips
+    fn frame_rate_warnings<'a>(
+        clips: impl IntoIterator<Item = &'a ClipInfo>,
+        probes: &[ClipProbe],
+        fps: u32,
+        interpolating: bool,
+    ) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (clip, probe) in clips.into_iter().zip(probes) {
+            // Stills report no frame rate
+            let clip_fps = probe.fps * clip.speed;
+            if clip_fps <= 0.0 {
+                continue;
+            }
+            let ratio = fps as f64 / clip_fps;
+            if ratio > FPS_MISMATCH_RATIO && !interpolating {
+                warnings.push(format!(
+                    "Exporting {} ({:.2} fps) at {} fps repeats frames; enable frame interpolation for smooth motion",
+                    clip.file_path, clip_fps, fps
+                ));
+            } else if ratio < 1.0 / FPS_MISMATCH_RATIO {
+                warnings.push(format!(
+                    "Exporting {} ({:.2} fps) at {} fps drops most of its frames; motion may stutter",
+                    clip.file_path, clip_fps, fps
+                ));
+            }
+        }
+        warnings
+    }
+    
+    /// The VideoToolbox encoder to use, if hardware encoding was requested and is present
+    fn hardware_encoder(&self, options: &ExportOptions) -> Result<Option<&'static str>, Error> {
+        if !options.hardware_encoding {
+            return Ok(None);
+        }
+        match options.codec.hardware_encoder() {
+            Some(encoder) if self.probe_capabilities()?.has_encoder(encoder) => Ok(Some(encoder)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Encoder arguments for the main output, using VideoToolbox when requested and present
+    fn encoder_args(&self, options: &ExportOptions) -> Result<Vec<String>, Error> {
+        if let Some(encoder) = self.hardware_encoder(options)? {
+            let bitrate = match options.video_bitrate.as_deref() {
+                Some(bitrate) => parse_bitrate(bitrate)?,
+                None => DEFAULT_HARDWARE_BITRATE,
+            };
+            let mut args = vec![
+                "-c:v".to_string(),
+                encoder.to_string(),
+                "-b:v".to_string(),
+                bitrate.to_string(),
+            ];
+            if options.codec == VideoCodec::Hevc {
+                args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+            }
+            args.extend(options.codec.audio_args());
+            return Ok(args);
+        }
+        
+        if options.hardware_encoding {
+            eprintln!(
+                "⚠ No hardware encoder available for {}, falling back to software encoding",
+                options.codec.encoder()
+            );
+        }
+        let mut args = options.codec.output_args(&options.quality)?;
+        if options.color_tagging.is_hdr() {
+            // Checked in export_video
+            args.extend(options.codec.ten_bit_args().unwrap_or_default());
+        }
+        Ok(args)
+    }
+    
+    /// First stabilization pass: record per-frame camera motion for the trimmed clip
+    fn detect_camera_motion(
+        &self,
+        clip: &ClipInfo,
+        options: &ExportOptions,
+        on_progress: &mut dyn FnMut(f64),
+    ) -> Result<PathBuf, Error> {
+        let transforms_path = std::env::temp_dir().join(format!("transforms_{}.trf", uuid::Uuid::new_v4()));
+        
+        // Trim, orient and crop exactly as the export pass does so transforms line up frame for frame
+        // (the input is autorotated here, so only the clip's own turn and flip remain)
+        let orientation: String = orientation_filters(
+            clip.rotate,
+            clip.flip == Some(Flip::Horizontal),
+            clip.flip == Some(Flip::Vertical),
+        )
+        .into_iter()
+        .map(|filter| format!("{},", filter))
+        .chain(clip.crop.map(|crop| format!("{},", crop.filter())))
+        .collect();
+        let filter = format!(
+            "trim=start={}:duration={},setpts=PTS-STARTPTS,{}vidstabdetect=shakiness=5:accuracy=15:result={}",
+            clip.trim_start,
+            clip.duration,
+            orientation,
+            escape_filter_path(&transforms_path)
+        );
+        
+        let args: Vec<String> = [
+            "-hide_banner",
+            "-progress",
+            "pipe:1",
+            "-nostats",
+            "-i",
+            &clip.file_path,
+            "-vf",
+            &filter,
+            "-an",
+            "-f",
+            "null",
+            "-",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        
+        let result = self.run_export(
+            &args,
+            path_str(&transforms_path)?,
+            clip.duration,
+            options,
+            "Stabilization analysis failed",
+            on_progress,
+        );
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&transforms_path);
+            return Err(e);
+        }
+        
+        Ok(transforms_path)
+    }
+    
+    #[allow(clippy::too_many_arguments)]
+    fn render_export(
+        &self,
+        clips: &[ClipInfo],
+        output_path: &str,
+        resolution: &str,
+        fps: u32,
+        composition_length: f64,
+        options: &ExportOptions,
+        transforms: Option<&Path>,
+        probes: &[ClipProbe],
+        on_progress: &mut dyn FnMut(f64),
+    ) -> Result<(), Error> {
+        let segments = timeline_segments(clips, &options.gaps, composition_length)?;
+        // Crossfades overlap clips, so the rendered composition is shorter than the timeline
+        let composition_length = composition_length - crossfade_overlap(&segments);
+        
+        // Two-pass loudness normalization measures the composed audio before rendering
+        let measured_loudness = match &options.loudness {
+            Some(loudness) if loudness.two_pass && !options.strip_audio && options.format != ExportFormat::Gif => Some(self.measure_composition_loudness(
+                clips,
+                &segments,
+                resolution,
+                fps,
+                composition_length,
+                output_path,
+                options,
+                probes,
+                &mut |percent| on_progress(percent * LOUDNESS_ANALYSIS_PROGRESS / 100.0),
+            )?),
+            _ => None,
+        };
+        let render_start = if measured_loudness.is_some() { LOUDNESS_ANALYSIS_PROGRESS } else { 0.0 };
+        let on_progress = &mut |percent: f64| on_progress(render_start + percent * (100.0 - render_start) / 100.0);
+        
+        // Create FFmpeg filter complex for concatenation and trimming
+        let mut filter_complex = self.build_filter_complex(
+            &segments,
+            resolution,
+            fps,
+            composition_length,
+            options,
+            transforms,
+            probes,
+            measured_loudness.as_ref(),
+        )?;
+        
+        // Fan the composed video and audio out to every extra rendition
+        let mut main_video = "[outv]".to_string();
+        let mut main_audio = "[outa]".to_string();
+        let gif = options.format == ExportFormat::Gif;
+        if options.strip_audio || gif {
+            // Every labeled filter output must be consumed, so discard the composed audio
+            filter_complex.push_str(";[outa]anullsink");
+        }
+        if gif {
+            filter_complex.push_str(&format!(";[outv]{}[gif]", options.gif.filter()));
+            main_video = "[gif]".to_string();
+        }
+        if !options.extra_outputs.is_empty() {
+            let count = options.extra_outputs.len() + 1;
+            let labels: String = (0..count).map(|i| format!("[split{}]", i)).collect();
+            filter_complex.push_str(&format!(";[outv]split={}{}", count, labels));
+            if !options.strip_audio {
+                let audio_labels: String = (0..count).map(|i| format!("[asplit{}]", i)).collect();
+                filter_complex.push_str(&format!(";[outa]asplit={}{}", count, audio_labels));
+            }
+            
+            for (i, spec) in options.extra_outputs.iter().enumerate() {
+                filter_complex.push_str(&format!(
+                    ";[split{}]scale={}[extra{}]",
+                    i + 1,
+                    resolution_scale(&spec.resolution)?,
+                    i
+                ));
+            }
+            main_video = "[split0]".to_string();
+            main_audio = "[asplit0]".to_string();
+        }
+        
+        let mut args = vec![
+            "-y".to_string(), // Overwrite output
+            // Machine-readable progress on stdout instead of the stats line on stderr
+            "-progress".to_string(),
+            "pipe:1".to_string(),
+            "-nostats".to_string(),
+        ];
+        
+        args.extend(timeline_input_args(clips, &segments, fps, options));
+        
+        // Two-pass target-size encoding: the first pass only writes rate-control stats
+        let two_pass = match options.target_size_mb {
+            Some(target_size_mb) if options.format == ExportFormat::Video => {
+                let bitrate = target_video_bitrate(target_size_mb, composition_length, !options.strip_audio)?;
+                let passlog = std::env::temp_dir().join(format!("x264pass_{}", uuid::Uuid::new_v4()));
+                Some((bitrate, passlog))
+            }
+            _ => None,
+        };
+        
+        if let Some((bitrate, passlog)) = &two_pass {
+            let mut first_pass = args.clone();
+            let mut first_pass_filter = filter_complex.clone();
+            if !options.strip_audio {
+                // The stats pass ignores sound, but the composed audio must still be consumed
+                first_pass_filter.push_str(";[outa]anullsink");
+            }
+            first_pass.extend_from_slice(&[
+                "-filter_complex".to_string(),
+                first_pass_filter,
+                "-map".to_string(),
+                main_video.clone(),
+                "-an".to_string(),
+                "-r".to_string(),
+                fps.to_string(),
+            ]);
+            first_pass.extend(x264_pass_args(&options.quality, *bitrate, passlog, 1));
+            first_pass.extend_from_slice(&["-f".to_string(), "null".to_string(), "-".to_string()]);
+            
+            let result = self.run_export(
+                &first_pass,
+                output_path,
+                composition_length,
+                options,
+                "Video export failed",
+                &mut |percent| on_progress(percent / 2.0),
+            );
+            if let Err(e) = result {
+                remove_pass_logs(passlog);
+                return Err(e);
+            }
+        }
+        
+        // Add filter complex
+        args.push("-filter_complex".to_string());
+        args.push(filter_complex);
         
         // Output settings
+        match options.format {
+            ExportFormat::Audio => {
+                args.extend_from_slice(&["-map".to_string(), main_audio, "-vn".to_string()]);
+                args.extend(options.audio.output_args());
+                args.extend(options.metadata_args());
+            }
+            ExportFormat::Gif => {
+                // The palette filters set the GIF's own frame rate; loop forever like shared GIFs do
+                args.extend_from_slice(&["-map".to_string(), main_video]);
+                args.extend_from_slice(&["-an".to_string(), "-loop".to_string(), "0".to_string()]);
+            }
+            ExportFormat::Video => {
+                args.extend_from_slice(&["-map".to_string(), main_video]);
+                if options.strip_audio {
+                    args.push("-an".to_string());
+                } else {
+                    args.extend_from_slice(&["-map".to_string(), main_audio]);
+                }
+                args.extend_from_slice(&["-r".to_string(), fps.to_string()]);
+                match &two_pass {
+                    Some((bitrate, passlog)) => {
+                        args.extend(x264_pass_args(&options.quality, *bitrate, passlog, 2));
+                        // The same AAC bitrate target_video_bitrate left room for
+                        args.extend(VideoCodec::H264.audio_args());
+                    }
+                    None => args.extend(self.encoder_args(options)?),
+                }
+                args.extend(options.color_tagging.output_args());
+                args.extend(options.metadata_args());
+            }
+        }
+        args.push(output_path.to_string());
+        
+        // Extra renditions favour speed over compression efficiency
+        for (i, spec) in options.extra_outputs.iter().enumerate() {
+            args.extend_from_slice(&["-map".to_string(), format!("[extra{}]", i)]);
+            if options.strip_audio {
+                args.push("-an".to_string());
+            } else {
+                args.extend_from_slice(&["-map".to_string(), format!("[asplit{}]", i + 1)]);
+            }
+            args.extend_from_slice(&[
+                "-r".to_string(),
+                fps.to_string(),
+                "-c:v".to_string(),
+                "libx264".to_string(),
+                "-preset".to_string(),
+                "veryfast".to_string(),
+                "-crf".to_string(),
+                spec.crf.to_string(),
+                "-c:a".to_string(),
+                "aac".to_string(),
+                "-b:a".to_string(),
+                "128k".to_string(),
+            ]);
+            args.extend(options.color_tagging.output_args());
+            args.extend(options.metadata_args());
+            args.push(spec.path.clone());
+        }
+        
+        match &two_pass {
+            Some((_, passlog)) => {
+                let result = self.run_export(
+                    &args,
+                    output_path,
+                    composition_length,
+                    options,
+                    "Video export failed",
+                    &mut |percent| on_progress(50.0 + percent / 2.0),
+                );
+                remove_pass_logs(passlog);
+                result.map(drop)
+            }
+            None => self
+                .run_export(&args, output_path, composition_length, options, "Video export failed", on_progress)
+                .map(drop),
+        }
+    }
+    
+    /// Run one FFmpeg export process, reporting progress and honoring cancel_export
+    /// Analysis passes run through here too so they can be cancelled; returns FFmpeg's stderr
+    fn run_export(
+        &self,
+        args: &[String],
+        output_path: &str,
+        composition_length: f64,
+        options: &ExportOptions,
+        context: &str,
+        on_progress: &mut dyn FnMut(f64),
+    ) -> Result<Vec<u8>, Error> {
+        let mut command = Command::new(&self.ffmpeg_path);
+        command
+            .args(args)
+            .stdin(Stdio::piped()) // Must capture stdin for graceful cancellation
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        options.priority.apply(&mut command);
+        
+        // Keep the process where cancel_export can reach it while we read its output
+        let (stdout, stderr) = {
+            let mut process_guard = EXPORT_PROCESS.lock().unwrap_or_else(PoisonError::into_inner);
+            if process_guard.is_some() {
+                return Err(Error::InvalidInput("An export is already in progress".to_string()));
+            }
+            
+            let mut child = command
+                .spawn()
+                .map_err(Error::io("FFmpeg execution failed"))?;
+            let pipes = (child.stdout.take(), child.stderr.take());
+            *process_guard = Some(child);
+            pipes
+        };
+        
+        // Drain stderr separately so a long render can't block on a full pipe
+        let stderr_reader = spawn_pipe_reader(stderr);
+        
+        if let Some(stdout) = stdout {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if let Some(seconds) = parse_progress_time(&line) {
+                    // Hold back 100% until FFmpeg has exited successfully
+                    let percent = if composition_length > 0.0 {
+                        (seconds / composition_length * 100.0).clamp(0.0, 99.0)
+                    } else {
+                        0.0
+                    };
+                    on_progress(percent);
+                }
+            }
+        }
+        
+        let child = EXPORT_PROCESS.lock().unwrap_or_else(PoisonError::into_inner).take();
+        let Some(mut child) = child else {
+            // cancel_export stopped FFmpeg; don't leave truncated files behind
+            let _ = stderr_reader.join();
+            let _ = std::fs::remove_file(output_path);
+            for spec in &options.extra_outputs {
+                let _ = std::fs::remove_file(&spec.path);
+            }
+            return Err(Error::Cancelled("Export cancelled".to_string()));
+        };
+        
+        let status = child
+            .wait()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+        let stderr = stderr_reader.join().unwrap_or_default();
+        
+        if !status.success() {
+            return Err(Error::process(context, String::from_utf8_lossy(&stderr)));
+        }
+        
+        Ok(stderr)
+    }
+    
+    /// Analysis pass of a two-pass loudness normalization: composes only the audio
+    /// (before normalization and fades), renders it to nowhere and reads loudnorm's statistics
+    #[allow(clippy::too_many_arguments)]
+    fn measure_composition_loudness(
+        &self,
+        clips: &[ClipInfo],
+        segments: &[Segment],
+        resolution: &str,
+        fps: u32,
+        composition_length: f64,
+        output_path: &str,
+        options: &ExportOptions,
+        probes: &[ClipProbe],
+        on_progress: &mut dyn FnMut(f64),
+    ) -> Result<LoudnessMeasurement, Error> {
+        let target_lufs = options.loudness.as_ref().map_or(PODCAST_TARGET_LUFS, |l| l.target_lufs);
+        let analysis_options = ExportOptions {
+            format: ExportFormat::Audio,
+            loudness: None,
+            fade_in: 0.0,
+            end_fade: 0.0,
+            ..options.clone()
+        };
+        let mut filter_complex = self.build_filter_complex(
+            segments,
+            resolution,
+            fps,
+            composition_length,
+            &analysis_options,
+            None,
+            probes,
+            None,
+        )?;
+        filter_complex.push_str(&format!(
+            ";[outa]{}:print_format=json[measured]",
+            loudnorm_filter(target_lufs)
+        ));
+        
+        let mut args: Vec<String> = ["-hide_banner", "-progress", "pipe:1", "-nostats"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        args.extend(timeline_input_args(clips, segments, fps, options));
+        args.extend(
+            ["-filter_complex", &filter_complex, "-map", "[measured]", "-f", "null", "-"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        );
+        
+        let stderr = self.run_export(&args, output_path, composition_length, options, "Loudness analysis failed", on_progress)?;
+        parse_loudnorm_stats(&String::from_utf8_lossy(&stderr))
+    }
+    
+    /// Build FFmpeg filter complex for concatenation with gap handling
+    #[allow(clippy::too_many_arguments)]
+    fn build_filter_complex(
+        &self,
+        segments: &[Segment],
+        resolution: &str,
+        fps: u32,
+        composition_length: f64,
+        options: &ExportOptions,
+        transforms: Option<&Path>,
+        probes: &[ClipProbe],
+        loudness: Option<&LoudnessMeasurement>,
+    ) -> Result<String, Error> {
+        // Audio-only exports and analysis passes compose just the sound
+        let picture = options.format != ExportFormat::Audio;
+        let scale = resolution_scale(resolution)?;
+        let clip_scale = options.scale_mode.filter(resolution)?;
+        // Generate gaps at the output size so concat sees matching dimensions;
+        // "source" keeps clip sizes, so gaps take the first clip's size
+        let (gap_width, gap_height) = match resolution_size(resolution)? {
+            Some(size) => size,
+            None => match segments.iter().find_map(|s| match s {
+                Segment::Clip { clip, .. } => Some(*clip),
+                Segment::Gap { .. } => None,
+            }) {
+                Some(clip) => match clip.crop {
+                    Some(crop) => (crop.width, crop.height),
+                    None => {
+                        let metadata = self.get_metadata(&clip.file_path)?;
+                        match (metadata.rotation + clip.rotate) % 360 {
+                            90 | 270 => (metadata.height, metadata.width),
+                            _ => (metadata.width, metadata.height),
+                        }
+                    }
+                },
+                None => (1920, 1080),
+            },
+        };
+        let gap_size = format!("{}x{}", gap_width, gap_height);
+        
+        // Freeze gaps don't render on their own; they extend a neighboring clip
+        // with cloned frames: (seconds before, seconds after) per segment
+        let mut freezes = vec![(0.0, 0.0); segments.len()];
+        for (n, segment) in segments.iter().enumerate() {
+            if let Segment::Gap { start_time, duration, fill: GapFill::Freeze } = segment {
+                let previous_is_clip = n > 0 && matches!(segments[n - 1], Segment::Clip { .. });
+                let next_is_clip = matches!(segments.get(n + 1), Some(Segment::Clip { .. }));
+                if previous_is_clip {
+                    freezes[n - 1].1 += duration;
+                } else if next_is_clip {
+                    freezes[n + 1].0 += duration;
+                } else {
+                    return Err(Error::InvalidInput(format!("Freeze gap at {}s has no adjacent clip to freeze", start_time)));
+                }
+            }
+        }
+        
+        let mut filters = Vec::new();
+        // Rendered (video label, audio label, length, crossfade from previous) per segment
+        let mut outputs: Vec<(String, String, f64, Option<f64>)> = Vec::new();
+        let mut image_input = segments.iter().filter(|s| matches!(s, Segment::Clip { .. })).count();
+        let clip_count = image_input;
+        
+        for (n, segment) in segments.iter().enumerate() {
+            let (i, clip) = match segment {
+                Segment::Clip { input, clip, .. } => (*input, *clip),
+                Segment::Gap { duration, fill, .. } => {
+                    let gap_filter = match fill {
+                        GapFill::Black => format!(
+                            "color=c=black:s={}:d={}:r={},scale={}[gap{}]",
+                            gap_size, duration, fps, scale, n
+                        ),
+                        GapFill::Color { color } => format!(
+                            "color=c={}:s={}:d={}:r={},scale={}[gap{}]",
+                            color, gap_size, duration, fps, scale, n
+                        ),
+                        GapFill::Image { .. } => {
+                            let filter = format!(
+                                "[{}:v]trim=duration={},setpts=PTS-STARTPTS,{}[gap{}]",
+                                image_input, duration, clip_scale, n
+                            );
+                            image_input += 1;
+                            filter
+                        }
+                        GapFill::Freeze => continue,
+                    };
+                    if picture {
+                        filters.push(gap_filter);
+                    }
+                    filters.push(format!(
+                        "anullsrc=channel_layout=stereo:sample_rate=48000:d={}[gap{}a]",
+                        duration, n
+                    ));
+                    outputs.push((format!("gap{}", n), format!("gap{}a", n), *duration, None));
+                    continue;
+                }
+            };
+            
+            // Clone the first frame for the intro hold and any freeze gap before the clip,
+            // and the last frame for the end hold and any freeze gap after it
+            let (freeze_before, freeze_after) = freezes[n];
+            let mut pad = Vec::new();
+            if clip.hold_start + freeze_before > 0.0 {
+                pad.push(format!("start_mode=clone:start_duration={}", clip.hold_start + freeze_before));
+            }
+            if clip.hold_end + freeze_after > 0.0 {
+                pad.push(format!("stop_mode=clone:stop_duration={}", clip.hold_end + freeze_after));
+            }
+            let hold = if pad.is_empty() {
+                String::new()
+            } else {
+                format!(",tpad={}", pad.join(":"))
+            };
+            
+            // Smooth camera motion before the hold so transforms match the analysed frames
+            let stabilize = match (transforms, &options.stabilization) {
+                (Some(path), Some(stabilization)) => format!(
+                    ",vidstabtransform=input={}:smoothing={}:optzoom=1,unsharp=5:5:0.8:3:3:0.4",
+                    escape_filter_path(path),
+                    stabilization.smoothing
+                ),
+                _ => String::new(),
+            };
+            
+            // Turn rotated footage (e.g. portrait phone video) upright, then apply the
+            // clip's own rotation, flip and crop, all before scaling
+            let orientation = clip.orientation_filter(probes[i].rotation);
+            // A sideways turn, of the stream or the clip, swaps the clip's width and height,
+            // so stretching it to the output frame would squash it; fit it inside instead
+            let clip_scale = if (probes[i].rotation + clip.rotate) % 180 == 90 {
+                ScaleMode::Fit.filter(resolution)?
+            } else {
+                clip_scale.clone()
+            };
+            
+            // Add the actual clip
+            // Motion-compensated interpolation up to the export rate; after stabilizing and
+            // reversing, which need the clip's own frames. Stills have no motion to interpolate
+            let interpolate = if options.frame_interpolation && probes[i].below_frame_rate(clip.speed, fps) {
+                format!(",{}", interpolation_filter(fps))
+            } else {
+                String::new()
+            };
+            
+            // A looped still starts at its only frame, so there's nothing to trim off the front
+            let trim_start = if is_still_image(&clip.file_path) { 0.0 } else { clip.trim_start };
+            let trim_filter = format!(
+                "[{}:v]trim=start={}:duration={},setpts=PTS-STARTPTS{}{}{}{}{}{}{},{}[clip{}]",
+                i,
+                trim_start,
+                clip.duration,
+                orientation,
+                clip.color_filter(),
+                clip.speed_filter(),
+                stabilize,
+                // After stabilizing, whose transforms follow the analyzed (forward) frame order
+                clip.reverse_filter(),
+                interpolate,
+                hold,
+                clip_scale,
+                i
+            );
+            if picture {
+                filters.push(trim_filter);
+            }
+            
+            // xfade needs both sides at the same frame rate, pixel format and SAR
+            let crossfaded = segment.crossfade().is_some()
+                || segments.get(n + 1).and_then(Segment::crossfade).is_some();
+            let video_label = if crossfaded && picture {
+                let pixel_format = if options.color_tagging.is_hdr() { "yuv420p10le" } else { "yuv420p" };
+                filters.push(format!(
+                    "[clip{i}]fps={fps},format={pixel_format},setsar=1[clipx{i}]",
+                    i = i,
+                    fps = fps,
+                    pixel_format = pixel_format
+                ));
+                format!("clipx{}", i)
+            } else {
+                format!("clip{}", i)
+            };
+            
+            // Audio covers the same span as the video: silent during held or frozen
+            // frames, and padded if the clip's audio is shorter than its picture
+            let clip_length = clip.timeline_duration() + freeze_before + freeze_after;
+            let audio_filter = if probes[i].has_audio {
+                let delay = clip.hold_start + freeze_before;
+                let delay = if delay > 0.0 {
+                    format!(",adelay={}:all=1", (delay * 1000.0).round() as u64)
+                } else {
+                    String::new()
+                };
+                let volume = if clip.volume != 1.0 {
+                    format!(",volume={}", clip.volume)
+                } else {
+                    String::new()
+                };
+                format!(
+                    "[{}:a]atrim=start={}:duration={},asetpts=PTS-STARTPTS{},\
+                     aformat=sample_rates=48000:channel_layouts=stereo{}{}{},apad,atrim=duration={}[clip{}a]",
+                    i, clip.trim_start, clip.duration, clip.areverse_filter(), clip.tempo_filters(), volume, delay, clip_length, i
+                )
+            } else {
+                // Clips without an audio stream contribute silence
+                format!(
+                    "anullsrc=channel_layout=stereo:sample_rate=48000:d={}[clip{}a]",
+                    clip_length, i
+                )
+            };
+            filters.push(audio_filter);
+            outputs.push((video_label, format!("clip{}a", i), clip_length, segment.crossfade()));
+        }
+        
+        // Crossfaded clips are blended into runs; runs and everything else are concatenated
+        let concat_label = |video: &str, audio: &str| {
+            if picture {
+                format!("[{}][{}]", video, audio)
+            } else {
+                format!("[{}]", audio)
+            }
+        };
+        let mut segment_labels = Vec::new();
+        let mut run: Option<(String, String, f64)> = None;
+        for (n, (video, audio, length, crossfade)) in outputs.into_iter().enumerate() {
+            run = match (crossfade, run.take()) {
+                (Some(duration), Some((run_video, run_audio, run_length))) => {
+                    if picture {
+                        filters.push(format!(
+                            "[{}][{}]xfade=transition=fade:duration={}:offset={}[xfade{}]",
+                            run_video, video, duration, run_length - duration, n
+                        ));
+                    }
+                    filters.push(format!(
+                        "[{}][{}]acrossfade=d={}[xfade{}a]",
+                        run_audio, audio, duration, n
+                    ));
+                    Some((format!("xfade{}", n), format!("xfade{}a", n), run_length + length - duration))
+                }
+                (_, previous) => {
+                    if let Some((run_video, run_audio, _)) = previous {
+                        segment_labels.push(concat_label(&run_video, &run_audio));
+                    }
+                    Some((video, audio, length))
+                }
+            };
+        }
+        if let Some((run_video, run_audio, _)) = run {
+            segment_labels.push(concat_label(&run_video, &run_audio));
+        }
+        
+        // Concatenate all segments (clips and gaps), video and audio together
+        let concat_inputs: String = segment_labels.join("");
+        let finishing = options.finishing_filters(resolution, composition_length)?;
+        let audio_finishing = options.audio_finishing_filters(composition_length, loudness);
+        let overlays: Vec<&OverlayClip> = options.overlay_tracks.iter().flatten().collect();
+        // Each stage after concat reads the previous stage's label; the last one writes [outv]
+        let mut video_stages = overlays.len() + usize::from(!finishing.is_empty()) + usize::from(options.watermark.is_some());
+        let video_out = if video_stages == 0 { "outv" } else { "composed" };
+        let mut stage_label = |label: &str| {
+            video_stages -= 1;
+            if video_stages == 0 { "outv".to_string() } else { label.to_string() }
+        };
+        let overlay_audio: Vec<usize> = (0..overlays.len())
+            .filter(|&k| probes[clip_count + k].has_audio && overlays[k].clip.volume > 0.0)
+            .collect();
+        let audio_out = if audio_finishing.is_empty() && options.music_path.is_none() && overlay_audio.is_empty() {
+            "outa"
+        } else {
+            "composeda"
+        };
+        
+        if picture {
+            filters.push(format!(
+                "{}concat=n={}:v=1:a=1[{}][{}]",
+                concat_inputs,
+                segment_labels.len(),
+                video_out,
+                audio_out
+            ));
+        } else {
+            filters.push(format!("{}concat=n={}:v=0:a=1[{}]", concat_inputs, segment_labels.len(), audio_out));
+        }
+        // Inputs run clips, gap images, music, the watermark, then overlay clips
+        let watermark_input = image_input + usize::from(options.music_path.is_some());
+        let overlay_input = watermark_input + usize::from(options.watermark.is_some());
+        
+        // Overlay clips sit over the composed main track, under grading and fades
+        if picture {
+            let mut video = video_out.to_string();
+            for (k, overlay) in overlays.iter().enumerate() {
+                let clip = &overlay.clip;
+                let start = render_time(segments, clip.start_time);
+                let width = ((gap_width as f64 * overlay.scale / 2.0).round() as u32 * 2).max(2);
+                let trim_start = if is_still_image(&clip.file_path) { 0.0 } else { clip.trim_start };
+                let hold = if clip.hold_start > 0.0 || clip.hold_end > 0.0 {
+                    format!(
+                        ",tpad=start_mode=clone:start_duration={}:stop_mode=clone:stop_duration={}",
+                        clip.hold_start, clip.hold_end
+                    )
+                } else {
+                    String::new()
+                };
+                let interpolate = if options.frame_interpolation && probes[clip_count + k].below_frame_rate(clip.speed, fps) {
+                    format!(",{}", interpolation_filter(fps))
+                } else {
+                    String::new()
+                };
+                let opacity = if clip.opacity < 1.0 {
+                    format!(",format=rgba,colorchannelmixer=aa={}", clip.opacity)
+                } else {
+                    String::new()
+                };
+                // Shift the overlay's timestamps to its place on the timeline; overlay shows
+                // the main track alone until its first frame and again after its last
+                filters.push(format!(
+                    "[{}:v]trim=start={}:duration={},setpts=PTS-STARTPTS{}{}{}{}{}{},scale={}:-2{},setpts=PTS+{}/TB[overlay{}]",
+                    overlay_input + k,
+                    trim_start,
+                    clip.duration,
+                    clip.orientation_filter(probes[clip_count + k].rotation),
+                    clip.color_filter(),
+                    clip.speed_filter(),
+                    clip.reverse_filter(),
+                    interpolate,
+                    hold,
+                    width,
+                    opacity,
+                    start,
+                    k
+                ));
+                let output = stage_label(&format!("overlaid{}", k));
+                filters.push(format!(
+                    "[{}][overlay{}]overlay={}:eof_action=pass:format=auto[{}]",
+                    video, k, overlay.position.overlay_position(), output
+                ));
+                video = output;
+            }
+            if !finishing.is_empty() {
+                let output = stage_label("finished");
+                filters.push(format!("[{}]{}[{}]", video, finishing.join(","), output));
+                video = output;
+            }
+            // The watermark goes on last so grading and fades leave it untouched
+            if let Some(watermark) = &options.watermark {
+                let output = stage_label("outv");
+                filters.extend(watermark.overlay_filters(watermark_input, gap_width, &video, &output));
+            }
+        }
+        
+        // Overlay clip audio joins the main track's at the overlay's place on the timeline
+        let mut audio = audio_out.to_string();
+        if !overlay_audio.is_empty() {
+            let mut mix_inputs = format!("[{}]", audio);
+            for &k in &overlay_audio {
+                let clip = &overlays[k].clip;
+                let delay = render_time(segments, clip.start_time) + clip.hold_start;
+                let volume = if clip.volume != 1.0 {
+                    format!(",volume={}", clip.volume)
+                } else {
+                    String::new()
+                };
+                filters.push(format!(
+                    "[{}:a]atrim=start={}:duration={},asetpts=PTS-STARTPTS{},\
+                     aformat=sample_rates=48000:channel_layouts=stereo{}{},adelay={}:all=1[overlay{}a]",
+                    overlay_input + k,
+                    clip.trim_start,
+                    clip.duration,
+                    clip.areverse_filter(),
+                    clip.tempo_filters(),
+                    volume,
+                    (delay * 1000.0).round() as u64,
+                    k
+                ));
+                mix_inputs.push_str(&format!("[overlay{}a]", k));
+            }
+            let mixed = if audio_finishing.is_empty() && options.music_path.is_none() { "outa" } else { "overlaymix" };
+            filters.push(format!(
+                "{}amix=inputs={}:duration=first:dropout_transition=0:normalize=0[{}]",
+                mix_inputs,
+                overlay_audio.len() + 1,
+                mixed
+            ));
+            audio = mixed.to_string();
+        }
+        
+        // Background music goes under the joined clip audio, so it plays through gaps too
+        if options.music_path.is_some() {
+            let volume = options.music_volume.unwrap_or(0.3);
+            if !(0.0..=1.0).contains(&volume) {
+                return Err(Error::InvalidInput(format!("Music volume must be between 0.0 and 1.0, got {}", volume)));
+            }
+            // Inputs run clips, gap images, then music, so the next image index is the music's
+            filters.push(format!(
+                "[{}:a]aformat=sample_rates=48000:channel_layouts=stereo,volume={},atrim=duration={},asetpts=PTS-STARTPTS[music]",
+                image_input, volume, composition_length
+            ));
+            let mixed = if audio_finishing.is_empty() { "outa" } else { "musicmix" };
+            if options.duck_music {
+                filters.extend(ducked_mix_filters(&audio, "music", mixed));
+            } else {
+                filters.push(format!(
+                    "[{}][music]amix=inputs=2:duration=first:dropout_transition=0:normalize=0[{}]",
+                    audio, mixed
+                ));
+            }
+            audio = mixed.to_string();
+        }
+        if !audio_finishing.is_empty() {
+            filters.push(format!("[{}]{}[outa]", audio, audio_finishing.join(",")));
+        }
+        
+        Ok(filters.join(";"))
+    }
+
+    /// Start screen recording using FFmpeg's avfoundation device (gdigrab on Windows)
+    /// Returns the spawned process handle
+    #[allow(clippy::too_many_arguments)]
+    pub fn start_screen_recording(
+        &self,
+        output_path: &str,
+        resolution: &str,
+        fps: u32,
+        capture_cursor: bool,
+        capture_clicks: bool,
+        audio_device: Option<&str>,
+        options: &ScreenRecordingOptions,
+    ) -> Result<std::process::Child, Error> {
+        // Validate bitrates before spawning so bad input fails fast
+        let video_bitrate = options.video_bitrate.as_deref().map(parse_bitrate).transpose()?;
+        let audio_bitrate = options.audio_bitrate.as_deref().map(parse_bitrate).transpose()?;
+        
+        let crop = self.screen_crop(options)?;
+        let mut args = self.screen_input_args(fps, capture_cursor, capture_clicks, audio_device, options)?;
+        
+        let audio = self.recording_audio(&mut args, audio_device, options)?;
+        let mut filters = Vec::new();
+        let video = match crop {
+            Some(crop) => {
+                filters.push(format!("[0:v]{}[screen]", crop));
+                "[screen]"
+            }
+            None => "0:v",
+        };
+        filters.extend(audio.filter.clone());
+        if !filters.is_empty() {
+            args.push("-filter_complex".to_string());
+            args.push(filters.join(";"));
+        }
+        args.push("-map".to_string());
+        args.push(video.to_string());
+        if let Some(stream) = &audio.stream {
+            args.push("-map".to_string());
+            args.push(stream.clone());
+        }
+        
+        let audio_bitrate = audio_bitrate.filter(|_| audio.stream.is_some());
+        args.extend(recording_output_args(output_path, resolution, fps, video_bitrate, audio_bitrate));
+        self.spawn_recording(&args, "Failed to start FFmpeg recording")
+    }
+
+    /// Record the screen with the webcam composited into a corner, in one FFmpeg process
+    /// Audio comes from the same devices as a plain screen recording
+    #[allow(clippy::too_many_arguments)]
+    pub fn start_pip_recording(
+        &self,
+        output_path: &str,
+        camera_index: u32,
+        resolution: &str,
+        fps: u32,
+        capture_cursor: bool,
+        audio_device: Option<&str>,
+        pip: &PipOptions,
+        options: &ScreenRecordingOptions,
+    ) -> Result<std::process::Child, Error> {
+        if !(0.1..=0.5).contains(&pip.size) {
+            return Err(Error::InvalidInput(format!("Webcam size must be between 0.1 and 0.5 of the screen height, got {}", pip.size)));
+        }
+        let video_bitrate = options.video_bitrate.as_deref().map(parse_bitrate).transpose()?;
+        let audio_bitrate = options.audio_bitrate.as_deref().map(parse_bitrate).transpose()?;
+        
+        let crop = self.screen_crop(options)?;
+        let mut args = self.screen_input_args(fps, capture_cursor, false, audio_device, options)?;
+        let camera_input = args.iter().filter(|arg| *arg == "-i").count();
+        args.extend(self.webcam_input_args(camera_index, None)?);
+        let audio = self.recording_audio(&mut args, audio_device, options)?;
+        
+        let mut filters = Vec::new();
+        let screen = match crop {
+            Some(crop) => {
+                filters.push(format!("[0:v]{}[cropped]", crop));
+                "[cropped]"
+            }
+            None => "[0:v]",
+        };
+        // Size the webcam relative to the screen, then place it in the chosen corner
+        filters.extend([
+            format!(
+                "[{}:v]{}scale2ref=w=-2:h=trunc(main_h*{}/2)*2[camera][screen]",
+                camera_input, screen, pip.size
+            ),
+            format!(
+                "[screen][camera]overlay={}:eof_action=pass:shortest=0[outv]",
+                pip.corner.overlay_position()
+            ),
+        ]);
+        filters.extend(audio.filter);
+        args.push("-filter_complex".to_string());
+        args.push(filters.join(";"));
+        args.push("-map".to_string());
+        args.push("[outv]".to_string());
+        if let Some(stream) = &audio.stream {
+            args.push("-map".to_string());
+            args.push(stream.clone());
+        }
+        
+        let audio_bitrate = audio_bitrate.filter(|_| audio.stream.is_some());
+        args.extend(recording_output_args(output_path, resolution, fps, video_bitrate, audio_bitrate));
+        self.spawn_recording(&args, "Failed to start FFmpeg picture-in-picture recording")
+    }
+
+    /// Start webcam recording using FFmpeg's avfoundation device
+    /// Returns the spawned process handle
+    pub fn start_webcam_recording(
+        &self,
+        output_path: &str,
+        camera_index: u32,
+        resolution: &str,
+        fps: u32,
+        audio_device: Option<&str>,
+    ) -> Result<std::process::Child, Error> {
+        
+        let mut args = self.webcam_input_args(camera_index, audio_device)?;
+        
+        // Frame rate - use exact integer (30) instead of fractional for camera compatibility
+        // Most cameras support 15-30 fps, so clamp to 30 max and use integer value
+        let clamped_fps = fps.min(30);
+        args.extend(recording_output_args(output_path, resolution, clamped_fps, None, None));
+        self.spawn_recording(&args, "Failed to start FFmpeg webcam recording")
+    }
+    
+    /// Spawn an FFmpeg recording with stdin open so it can be asked to quit
+    fn spawn_recording(&self, args: &[String], context: &str) -> Result<std::process::Child, Error> {
+        Command::new(&self.ffmpeg_path)
+            .args(args)
+            .stdin(Stdio::piped()) // Must capture stdin for graceful shutdown
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(Error::io(context))
+    }
+
+    /// avfoundation inputs for screen capture
+    #[cfg(not(target_os = "windows"))]
+    fn screen_input_args(
+        &self,
+        _fps: u32,
+        capture_cursor: bool,
+        capture_clicks: bool,
+        audio_device: Option<&str>,
+        options: &ScreenRecordingOptions,
+    ) -> Result<Vec<String>, Error> {
+        // avfoundation device format: "<video_device>:<audio_device>"
+        // Screens are listed after the cameras, so their device index varies by machine
+        // Audio device is typically index 1 (microphone) or "none"
+        let video_device = self.screen_device_index(options.screen_index)?;
+        let audio = audio_device.unwrap_or("none");
+        let device_input = format!("{}:{}", video_device, audio);
+
+        let mut args = vec![
+            "-f".to_string(),
+            "avfoundation".to_string(),
+        ];
+
+        if capture_cursor {
+            args.push("-capture_cursor".to_string());
+            args.push("1".to_string());
+        }
+
+        if capture_clicks {
+            args.push("-capture_mouse_clicks".to_string());
+            args.push("1".to_string());
+        }
+
+        args.push("-i".to_string());
+        args.push(device_input);
+        Ok(args)
+    }
+    
+    /// gdigrab inputs for screen capture, plus a DirectShow input for the microphone
+    /// gdigrab can't highlight clicks, so `capture_clicks` has no effect on Windows
+    #[cfg(target_os = "windows")]
+    fn screen_input_args(
+        &self,
+        fps: u32,
+        capture_cursor: bool,
+        _capture_clicks: bool,
+        audio_device: Option<&str>,
+        options: &ScreenRecordingOptions,
+    ) -> Result<Vec<String>, Error> {
+        let mut args = vec![
+            "-f".to_string(),
+            "gdigrab".to_string(),
+            "-framerate".to_string(),
+            fps.to_string(),
+            "-draw_mouse".to_string(),
+            if capture_cursor { "1" } else { "0" }.to_string(),
+        ];
+        
+        // Without a region gdigrab records the whole desktop, across all displays
+        if let Some(region) = &options.region {
+            args.extend_from_slice(&[
+                "-offset_x".to_string(),
+                region.x.to_string(),
+                "-offset_y".to_string(),
+                region.y.to_string(),
+                "-video_size".to_string(),
+                format!("{}x{}", region.width, region.height),
+            ]);
+        }
+        args.push("-i".to_string());
+        args.push("desktop".to_string());
+        
+        // gdigrab is video-only, so audio comes from a second input
+        if let Some(device) = audio_device {
+            args.extend_from_slice(&[
+                "-f".to_string(),
+                "dshow".to_string(),
+                "-i".to_string(),
+                format!("audio={}", self.dshow_device_name(device, "audio")?),
+            ]);
+        }
+        Ok(args)
+    }
+    
+    /// Validate the capture region against the screen and return the crop filter it
+    /// needs, if any; Windows grabs the region natively so nothing is cropped there
+    fn screen_crop(&self, options: &ScreenRecordingOptions) -> Result<Option<String>, Error> {
+        let region = match &options.region {
+            Some(region) => region,
+            None => return Ok(None),
+        };
+        let screen = self.probe_screen_bounds(options)?;
+        region.validate(&screen)?;
+        
+        if cfg!(target_os = "windows") {
+            Ok(None)
+        } else {
+            Ok(Some(region.crop_filter()))
+        }
+    }
+    
+    /// Area a recording would capture, from a brief probe of the device
+    /// avfoundation screens start at (0, 0); gdigrab logs where the virtual desktop starts
+    fn probe_screen_bounds(&self, options: &ScreenRecordingOptions) -> Result<ScreenBounds, Error> {
+        #[cfg(not(target_os = "windows"))]
+        let input = ["-f".to_string(), "avfoundation".to_string(), "-i".to_string(),
+            format!("{}:none", self.screen_device_index(options.screen_index)?)];
+        #[cfg(target_os = "windows")]
+        let input = {
+            let _ = options;
+            ["-f".to_string(), "gdigrab".to_string(), "-i".to_string(), "desktop".to_string()]
+        };
+        
+        // gdigrab reports the desktop origin at info level
+        let loglevel = if cfg!(target_os = "windows") { "info" } else { "error" };
+        let mut command = Command::new(&self.ffprobe_path);
+        command
+            .args(["-v", loglevel, "-select_streams", "v:0", "-show_entries", "stream=width,height", "-of", "csv=p=0"])
+            .args(&input);
+        let output = run_with_timeout(&mut command, DEVICE_LIST_TIMEOUT)
+            .map_err(Error::io("FFprobe execution failed"))?;
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let (width, height) = stdout
+            .trim()
+            .split_once(',')
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+            .ok_or_else(|| Error::process("Couldn't determine the screen size", &stderr))?;
+        let (x, y) = parse_gdigrab_origin(&stderr).unwrap_or((0, 0));
+        Ok(ScreenBounds { x, y, width, height })
+    }
+    
+    /// Add the system audio input (if any) to a screen recording's inputs and work out
+    /// which stream carries the recorded audio
+    fn recording_audio(
+        &self,
+        args: &mut Vec<String>,
+        audio_device: Option<&str>,
+        options: &ScreenRecordingOptions,
+    ) -> Result<RecordingAudio, Error> {
+        let system_device = match options.system_audio_device.as_deref() {
+            Some(device) => device,
+            None => {
+                return Ok(RecordingAudio {
+                    filter: None,
+                    stream: audio_device.map(|_| SCREEN_MIC_STREAM.to_string()),
+                })
+            }
+        };
+        
+        // System audio is a separate input; mix it with the microphone when there is one
+        let system_input = args.iter().filter(|arg| *arg == "-i").count();
+        args.extend(self.audio_input_args(system_device)?);
+        Ok(match audio_device {
+            Some(_) => RecordingAudio {
+                filter: Some(format!(
+                    "[{}][{}:a]amix=inputs=2:duration=longest[mixed]",
+                    SCREEN_MIC_STREAM, system_input
+                )),
+                stream: Some("[mixed]".to_string()),
+            },
+            None => RecordingAudio {
+                filter: None,
+                stream: Some(format!("{}:a", system_input)),
+            },
+        })
+    }
+    
+    /// Audio-only avfoundation input for an extra audio device
+    #[cfg(not(target_os = "windows"))]
+    fn audio_input_args(&self, device: &str) -> Result<Vec<String>, Error> {
+        Ok(vec![
+            "-f".to_string(),
+            "avfoundation".to_string(),
+            "-i".to_string(),
+            format!("none:{}", device),
+        ])
+    }
+    
+    /// Audio-only DirectShow input for an extra audio device
+    #[cfg(target_os = "windows")]
+    fn audio_input_args(&self, device: &str) -> Result<Vec<String>, Error> {
+        Ok(vec![
+            "-f".to_string(),
+            "dshow".to_string(),
+            "-i".to_string(),
+            format!("audio={}", self.dshow_device_name(device, "audio")?),
+        ])
+    }
+    
+    /// avfoundation input for a camera and optional microphone
+    #[cfg(not(target_os = "windows"))]
+    fn webcam_input_args(&self, camera_index: u32, audio_device: Option<&str>) -> Result<Vec<String>, Error> {
+        // avfoundation device format: "<video_device>:<audio_device>"
+        // Camera devices are typically at indices 0+ (before screen devices)
+        let audio = audio_device.unwrap_or("none");
+        let device_input = format!("{}:{}", camera_index, audio);
+
+        Ok(vec![
+            "-f".to_string(),
+            "avfoundation".to_string(),
+            "-framerate".to_string(),
+            "30".to_string(),  // Camera supports 30 fps (not 29.97)
+            "-i".to_string(),
+            device_input,
+        ])
+    }
+    
+    /// DirectShow input for a camera and optional microphone
+    /// DirectShow opens devices by name, so indices from list_cameras are resolved first
+    #[cfg(target_os = "windows")]
+    fn webcam_input_args(&self, camera_index: u32, audio_device: Option<&str>) -> Result<Vec<String>, Error> {
+        let mut device_input = format!("video={}", self.dshow_device_name(&camera_index.to_string(), "video")?);
+        if let Some(device) = audio_device {
+            device_input.push_str(&format!(":audio={}", self.dshow_device_name(device, "audio")?));
+        }
+        
+        Ok(vec![
+            "-f".to_string(),
+            "dshow".to_string(),
+            "-framerate".to_string(),
+            "30".to_string(),
+            "-i".to_string(),
+            device_input,
+        ])
+    }
+    
+    /// List available cameras using FFmpeg's avfoundation device list
+    /// Returns a vector of camera information (index and name)
+    #[cfg(not(target_os = "windows"))]
+    pub fn list_cameras(&self) -> Result<Vec<CameraInfo>, Error> {
+        let stderr = self.list_avfoundation_devices()?;
+        
+        // Skip screen capture devices (typically "Capture screen")
+        Ok(parse_device_section(&stderr, "AVFoundation video devices")
+            .into_iter()
+            .filter(|(_, name)| !name.contains("Capture screen"))
+            .map(|(index, name)| CameraInfo { index, name })
+            .collect())
+    }
+    
+    /// List available cameras from the DirectShow device list
+    /// Indices are positions in that list; DirectShow itself addresses devices by name
+    #[cfg(target_os = "windows")]
+    pub fn list_cameras(&self) -> Result<Vec<CameraInfo>, Error> {
+        Ok(self.list_dshow_devices("video")?
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| CameraInfo { index: index as u32, name })
+            .collect())
+    }
+    
+    /// Sizes and frame rates a camera supports
+    /// avfoundation has no listing option, so a mode no camera offers is requested and the
+    /// supported modes are read from the error FFmpeg prints
+    #[cfg(not(target_os = "windows"))]
+    pub fn get_camera_capabilities(&self, camera_index: u32) -> Result<Vec<CameraMode>, Error> {
+        let mut command = Command::new(&self.ffmpeg_path);
+        command.args([
+            "-hide_banner",
+            "-f", "avfoundation",
+            "-framerate", "1",
+            "-video_size", "1x1",
+            "-i", &format!("{}:none", camera_index),
+        ]);
+        let output = run_with_timeout(&mut command, DEVICE_LIST_TIMEOUT)
+            .map_err(Error::io("Failed to execute FFmpeg"))?;
+        
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let modes = parse_avfoundation_modes(&stderr);
+        if modes.is_empty() {
+            return Err(Error::process(format!("Couldn't read the modes of camera {}", camera_index), stderr));
+        }
+        Ok(sort_camera_modes(modes))
+    }
+    
+    /// Sizes and frame rates a camera supports, from DirectShow's option listing
+    #[cfg(target_os = "windows")]
+    pub fn get_camera_capabilities(&self, camera_index: u32) -> Result<Vec<CameraMode>, Error> {
+        let device = self.dshow_device_name(&camera_index.to_string(), "video")?;
+        let mut command = Command::new(&self.ffmpeg_path);
+        command.args([
+            "-hide_banner",
+            "-f", "dshow",
+            "-list_options", "true",
+            "-i", &format!("video={}", device),
+        ]);
+        let output = run_with_timeout(&mut command, DEVICE_LIST_TIMEOUT)
+            .map_err(Error::io("Failed to execute FFmpeg"))?;
+        
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let modes = parse_dshow_modes(&stderr);
+        if modes.is_empty() {
+            return Err(Error::process(format!("Couldn't read the modes of camera {}", camera_index), stderr));
+        }
+        Ok(sort_camera_modes(modes))
+    }
+    
+    /// List available audio input devices (microphones) from the avfoundation device list
+    #[cfg(not(target_os = "windows"))]
+    pub fn list_audio_devices(&self) -> Result<Vec<AudioDeviceInfo>, Error> {
+        let stderr = self.list_avfoundation_devices()?;
+        
+        Ok(parse_device_section(&stderr, "AVFoundation audio devices")
+            .into_iter()
+            .map(|(index, name)| AudioDeviceInfo { index, name })
+            .collect())
+    }
+    
+    /// List available audio input devices (microphones) from the DirectShow device list
+    #[cfg(target_os = "windows")]
+    pub fn list_audio_devices(&self) -> Result<Vec<AudioDeviceInfo>, Error> {
+        Ok(self.list_dshow_devices("audio")?
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| AudioDeviceInfo { index: index as u32, name })
+            .collect())
+    }
+    
+    /// Device index of a "Capture screen N" entry, or of the first screen when none is given
+    #[cfg(not(target_os = "windows"))]
+    fn screen_device_index(&self, screen: Option<u32>) -> Result<u32, Error> {
+        let stderr = self.list_avfoundation_devices()?;
+        let screens: Vec<(u32, String)> = parse_device_section(&stderr, "AVFoundation video devices")
+            .into_iter()
+            .filter(|(_, name)| name.contains("Capture screen"))
+            .collect();
+        
+        let device = match screen {
+            Some(n) => {
+                let name = format!("Capture screen {}", n);
+                screens.iter().find(|(_, device_name)| *device_name == name)
+            }
+            None => screens.first(),
+        };
+        device.map(|(index, _)| *index).ok_or_else(|| match screen {
+            Some(n) => Error::InvalidInput(format!("Screen {} not found ({} screen capture devices available)", n, screens.len())),
+            None => Error::PermissionDenied("No screen capture device found; check Screen Recording permission".to_string()),
+        })
+    }
+    
+    /// Raw avfoundation device dump (FFmpeg prints it to stderr)
+    #[cfg(not(target_os = "windows"))]
+    fn list_avfoundation_devices(&self) -> Result<String, Error> {
+        self.list_devices("avfoundation", "")
+    }
+    
+    /// DirectShow device names of one kind ("video" or "audio"), in listing order
+    #[cfg(target_os = "windows")]
+    fn list_dshow_devices(&self, kind: &str) -> Result<Vec<String>, Error> {
+        let stderr = self.list_devices("dshow", "dummy")?;
+        Ok(parse_dshow_devices(&stderr, kind))
+    }
+    
+    /// Resolve a DirectShow device given as a list index (e.g. "0") or a name
+    #[cfg(target_os = "windows")]
+    fn dshow_device_name(&self, device: &str, kind: &str) -> Result<String, Error> {
+        let index = match device.parse::<usize>() {
+            Ok(index) => index,
+            Err(_) => return Ok(device.to_string()),
+        };
+        let devices = self.list_dshow_devices(kind)?;
+        devices.get(index).cloned().ok_or_else(|| {
+            Error::InvalidInput(format!("No {} device at index {} ({} available)", kind, index, devices.len()))
+        })
+    }
+    
+    /// Raw device dump from an FFmpeg input device (FFmpeg prints it to stderr)
+    fn list_devices(&self, format: &str, input: &str) -> Result<String, Error> {
+        // FFmpeg exits with non-zero code when listing devices (can't open the input), which is expected
+        // A stuck camera driver can hang this call, so it runs under a timeout
+        let mut command = Command::new(&self.ffmpeg_path);
+        command.args([
+            "-f", format,
+            "-list_devices", "true",
+            "-i", input
+        ]);
+        let output = run_with_timeout(&mut command, DEVICE_LIST_TIMEOUT)
+            .map_err(Error::io("Failed to execute FFmpeg"))?;
+        
+        // The device list is always in stderr regardless of exit code
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        
+        if output.timed_out() {
+            eprintln!(
+                "Warning: FFmpeg device listing timed out after {}s; device list may be incomplete",
+                DEVICE_LIST_TIMEOUT.as_secs()
+            );
+        }
+        
+        // Log stderr for debugging (remove in production if desired)
+        eprintln!("FFmpeg list_devices stderr:\n{}", stderr);
+        
+        Ok(stderr)
+    }
+
+    /// Split an audio file into consecutive pieces of at most `segment_secs`, without re-encoding
+    /// Returns each piece with its start time in the original, in order
+    pub fn split_audio(&self, file_path: &str, segment_secs: f64) -> Result<Vec<(PathBuf, f64)>, Error> {
+        if !segment_secs.is_finite() || segment_secs <= 0.0 {
+            return Err(Error::InvalidInput(format!("Segment length must be positive, got {}", segment_secs)));
+        }
+        
+        let extension = Path::new(file_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("mp3");
+        let temp_dir = std::env::temp_dir();
+        let prefix = format!("audio_chunk_{}", uuid::Uuid::new_v4());
+        let pattern = temp_dir.join(format!("{}_%03d.{}", prefix, extension));
+        
+        let output = Command::new(&self.ffmpeg_path)
+            .args([
+                "-y",
+                "-i", file_path,
+                "-map", "0:a:0",
+                "-c", "copy",
+                "-f", "segment",
+                "-segment_time", &segment_secs.to_string(),
+                "-reset_timestamps", "1",
+            ])
+            .arg(&pattern)
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+        
+        let pieces: Vec<PathBuf> = (0..)
+            .map(|i| temp_dir.join(format!("{}_{:03}.{}", prefix, i, extension)))
+            .take_while(|path| path.exists())
+            .collect();
+        let remove_pieces = |pieces: &[PathBuf]| {
+            for path in pieces {
+                let _ = std::fs::remove_file(path);
+            }
+        };
+        
+        if !output.status.success() {
+            remove_pieces(&pieces);
+            return Err(Error::process("Splitting audio failed", String::from_utf8_lossy(&output.stderr)));
+        }
+        
+        // Pieces end on packet boundaries rather than exactly at segment_secs,
+        // so offsets come from their real durations
+        let durations = pieces
+            .iter()
+            .map(|path| self.probe_duration(&path.to_string_lossy()))
+            .collect::<Result<Vec<f64>, Error>>();
+        let durations = match durations {
+            Ok(durations) => durations,
+            Err(e) => {
+                remove_pieces(&pieces);
+                return Err(e);
+            }
+        };
+        
+        let starts = durations.iter().scan(0.0, |start, duration| {
+            let piece_start = *start;
+            *start += duration;
+            Some(piece_start)
+        });
+        Ok(pieces.into_iter().zip(starts).collect())
+    }
+    
+    /// Extract and combine audio from multiple clips in timeline order, as 16 kHz mono
+    /// speech audio in `format` (MP3 for the Whisper API, WAV for whisper.cpp)
+    /// Handles gaps between clips by inserting silence
+    pub fn extract_and_combine_audio(
+        &self,
+        clips: &[ClipInfo],
+        composition_length: f64,
+        format: AudioFormat,
+    ) -> Result<PathBuf, Error> {
+        if clips.is_empty() {
+            return Err(Error::InvalidInput("No clips to extract audio from".to_string()));
+        }
+        validate_clips(clips)?;
+        let codec: &[&str] = match format {
+            AudioFormat::Mp3 => &["libmp3lame", "-b:a", "128k"],
+            AudioFormat::Wav => &["pcm_s16le"],
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "Timeline audio is extracted as MP3 or WAV, not {}",
+                    format.extension()
+                )))
+            }
+        };
+
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join(format!("timeline_audio_{}.{}", uuid::Uuid::new_v4(), format.extension()));
+        
+        // Build FFmpeg filter complex for audio concatenation with gap handling
+        let filter_complex = self.build_audio_filter_complex(clips, composition_length)?;
+        
+        let mut args = vec![
+            "-y".to_string(), // Overwrite output
+        ];
+        
+        // Add input files
+        for clip in clips {
+            args.push("-i".to_string());
+            args.push(clip.file_path.clone());
+        }
+        
+        // Add filter complex
+        args.push("-filter_complex".to_string());
+        args.push(filter_complex);
+        
+        // Output audio only
+        args.extend_from_slice(&["-map".to_string(), "[outa]".to_string(), "-acodec".to_string()]);
+        args.extend(codec.iter().map(|arg| arg.to_string()));
         args.extend_from_slice(&[
-            "-map".to_string(),
-            "[outv]".to_string(),
-            "-r".to_string(),
-            fps.to_string(),
-            "-c:v".to_string(),
-            "libx264".to_string(),
-            "-preset".to_string(),
-            "medium".to_string(),
-            "-crf".to_string(),
-            "23".to_string(),
-            output_path.to_string(),
+            "-ar".to_string(),
+            "16000".to_string(), // 16kHz sample rate (Whisper optimal)
+            "-ac".to_string(),
+            "1".to_string(), // Mono
+            path_str(&output_path)?.to_string(),
         ]);
         
         let output = Command::new(&self.ffmpeg_path)
             .args(&args)
             .output()
-            .map_err(|e| format!("FFmpeg execution failed: {}", e))?;
+            .map_err(Error::io("FFmpeg execution failed"))?;
         
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("Video export failed: {}", stderr));
+            return Err(Error::process("Audio extraction failed", String::from_utf8_lossy(&output.stderr)));
         }
         
-        Ok(())
+        Ok(output_path)
     }
-    
-    /// Build FFmpeg filter complex for concatenation with gap handling
-    fn build_filter_complex(
+
+    /// Build audio filter complex for audio concatenation with gap handling
+    fn build_audio_filter_complex(
         &self,
         clips: &[ClipInfo],
-        resolution: &str,
-        fps: u32,
-        composition_length: f64
-    ) -> Result<String, String> {
-        let scale = match resolution {
-            "720p" => "1280:720",
-            "1080p" => "1920:1080",
-            "source" => "-1:-1",
-            _ => return Err(format!("Invalid resolution: {}", resolution)),
-        };
-        
+        composition_length: f64,
+    ) -> Result<String, Error> {
         let mut filters = Vec::new();
-        let mut video_indices = Vec::new();
+        let mut audio_indices = Vec::new();
         let mut current_time = 0.0;
         
-        // Build segments with gaps
+        // Build audio segments with silence gaps
         for (i, clip) in clips.iter().enumerate() {
             // Check if there's a gap before this clip
             if clip.start_time > current_time {
                 let gap_duration = clip.start_time - current_time;
                 
-                // Create a black gap segment
+                // Create silence gap segment
                 let gap_filter = format!(
-                    "color=c=black:s=1920x1080:d={}:r={},scale={}[gap{}]",
+                    "anullsrc=channel_layout=mono:sample_rate=16000:d={}[silence{}]",
                     gap_duration,
-                    fps,
-                    scale,
                     i
                 );
                 filters.push(gap_filter);
-                video_indices.push(format!("[gap{}]", i));
+                audio_indices.push(format!("[silence{}]", i));
             }
             
-            // Add the actual clip
+            // Held first and last frames are silent
+            let mut hold = String::new();
+            if clip.hold_start > 0.0 {
+                hold.push_str(&format!(",adelay={}:all=1", (clip.hold_start * 1000.0).round() as u64));
+            }
+            if clip.hold_end > 0.0 {
+                hold.push_str(&format!(",apad=pad_dur={}", clip.hold_end));
+            }
+            
+            // Extract and trim audio from clip
             let trim_filter = format!(
-                "[{}:v]trim=start={}:duration={},setpts=PTS-STARTPTS,scale={}[clip{}]",
+                "[{}:a]atrim=start={}:duration={},asetpts=PTS-STARTPTS{}{},aresample=16000:async=1[clip{}a]",
                 i,
                 clip.trim_start,
                 clip.duration,
-                scale,
+                clip.tempo_filters(),
+                hold,
                 i
             );
             filters.push(trim_filter);
-            video_indices.push(format!("[clip{}]", i));
+            audio_indices.push(format!("[clip{}a]", i));
             
-            current_time = clip.start_time + clip.duration;
+            current_time = clip.start_time + clip.timeline_duration();
         }
         
-        // Add gap to fill to composition length if needed
+        // Add silence to fill to composition length if needed
         if current_time < composition_length {
             let gap_duration = composition_length - current_time;
             let gap_index = clips.len();
             
             let gap_filter = format!(
-                "color=c=black:s=1920x1080:d={}:r={},scale={}[gap{}]",
+                "anullsrc=channel_layout=mono:sample_rate=16000:d={}[silence{}]",
                 gap_duration,
-                fps,
-                scale,
                 gap_index
             );
             filters.push(gap_filter);
-            video_indices.push(format!("[gap{}]", gap_index));
+            audio_indices.push(format!("[silence{}]", gap_index));
         }
         
-        // Concatenate all segments (gaps + clips + end gap)
-        let concat_inputs: String = video_indices.join("");
+        // Concatenate all audio segments (silence + clips + end silence)
+        let concat_inputs: String = audio_indices.join("");
         
         filters.push(format!(
-            "{}concat=n={}:v=1:a=0[outv]",
+            "{}concat=n={}:v=0:a=1[outa]",
             concat_inputs,
-            video_indices.len()
+            audio_indices.len()
         ));
         
         Ok(filters.join(";"))
     }
 
-    /// Start screen recording using FFmpeg's avfoundation device
-    /// Returns the spawned process handle
-    pub fn start_screen_recording(
+    /// Extract audio from video clip to temporary file
+    /// `speech` downmixes to 16 kHz mono, plenty for transcription and far smaller
+    /// to upload; otherwise the source channels and sample rate are kept
+    /// Returns path to extracted audio file
+    pub fn extract_audio(
+        &self,
+        video_path: &str,
+        trim_start: f64,
+        duration: f64,
+        output_format: AudioFormat,
+        speech: bool,
+    ) -> Result<PathBuf, Error> {
+        let temp_dir = std::env::temp_dir();
+        let output_file = temp_dir.join(format!(
+            "audio_{}_{}.{}",
+            uuid::Uuid::new_v4(),
+            chrono::Utc::now().timestamp(),
+            output_format.extension()
+        ));
+
+        let output_path = path_str(&output_file)?;
+
+        let args = extract_audio_args(video_path, trim_start, duration, output_format, speech, output_path);
+
+        // Execute FFmpeg using self.ffmpeg_path
+        let output = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+
+        if !output.status.success() {
+            return Err(Error::process("Audio extraction failed", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(output_file)
+    }
+
+    /// Export a podcast mix as MP3: voice with optional background music ducked
+    /// underneath it, loudness normalized to -16 LUFS, with ID3 chapter markers
+    pub fn export_podcast(
         &self,
+        voice_path: &str,
+        music_path: Option<&str>,
+        music_volume: f64,
+        chapters: &[Chapter],
         output_path: &str,
-        resolution: &str,
-        fps: u32,
-        capture_cursor: bool,
-        capture_clicks: bool,
-        audio_device: Option<&str>,
-    ) -> Result<std::process::Child, String> {
-        use std::process::{Command, Stdio};
-        
-        // avfoundation device format: "<video_device>:<audio_device>"
-        // Screen is typically index 3 ("Capture screen 0")
-        // Audio device is typically index 1 (microphone) or "none"
-        let video_device = "3"; // Capture screen 0
-        let audio = audio_device.unwrap_or("none");
-        let device_input = format!("{}:{}", video_device, audio);
+    ) -> Result<(), Error> {
+        if !output_path.to_lowercase().ends_with(".mp3") {
+            return Err(Error::InvalidInput("Podcast export requires an .mp3 output path".to_string()));
+        }
+        if !(0.0..=1.0).contains(&music_volume) {
+            return Err(Error::InvalidInput(format!("Music volume must be between 0.0 and 1.0, got {}", music_volume)));
+        }
 
         let mut args = vec![
-            "-f".to_string(),
-            "avfoundation".to_string(),
+            "-y".to_string(), // Overwrite output
+            "-i".to_string(),
+            voice_path.to_string(),
         ];
+        let mut filters = Vec::new();
+        let mut next_input = 1;
 
-        if capture_cursor {
-            args.push("-capture_cursor".to_string());
-            args.push("1".to_string());
+        if let Some(music) = music_path {
+            // Loop the music so it keeps playing under voice tracks longer than it
+            args.extend_from_slice(&[
+                "-stream_loop".to_string(),
+                "-1".to_string(),
+                "-i".to_string(),
+                music.to_string(),
+            ]);
+            next_input += 1;
+
+            filters.push(format!("[1:a]volume={}[music]", music_volume));
+            filters.extend(ducked_mix_filters("0:a", "music", "mixed"));
+            filters.push(format!("[mixed]{},aresample=44100[outa]", loudnorm_filter(PODCAST_TARGET_LUFS)));
+        } else {
+            filters.push(format!("[0:a]{},aresample=44100[outa]", loudnorm_filter(PODCAST_TARGET_LUFS)));
         }
 
-        if capture_clicks {
-            args.push("-capture_mouse_clicks".to_string());
-            args.push("1".to_string());
+        let metadata_path = if chapters.is_empty() {
+            None
+        } else {
+            let path = write_chapters_metadata(chapters)?;
+            args.push("-i".to_string());
+            args.push(path_str(&path)?.to_string());
+            Some(path)
+        };
+
+        args.push("-filter_complex".to_string());
+        args.push(filters.join(";"));
+        args.push("-map".to_string());
+        args.push("[outa]".to_string());
+
+        if metadata_path.is_some() {
+            args.extend_from_slice(&[
+                "-map_metadata".to_string(),
+                next_input.to_string(),
+                "-map_chapters".to_string(),
+                next_input.to_string(),
+            ]);
         }
 
-        args.push("-i".to_string());
-        args.push(device_input);
+        args.extend_from_slice(&[
+            "-c:a".to_string(),
+            "libmp3lame".to_string(),
+            "-b:a".to_string(),
+            "192k".to_string(),
+            "-id3v2_version".to_string(),
+            "3".to_string(), // ID3v2.3 has the widest CHAP frame support
+            "-write_id3v1".to_string(),
+            "1".to_string(),
+            output_path.to_string(),
+        ]);
+
+        let output = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"));
+
+        if let Some(path) = metadata_path {
+            let _ = std::fs::remove_file(path);
+        }
 
-        // Video codec settings
-        args.push("-c:v".to_string());
-        args.push("libx264".to_string());
-        args.push("-preset".to_string());
-        args.push("ultrafast".to_string()); // Low latency for real-time recording
-        args.push("-crf".to_string());
-        args.push("23".to_string()); // Quality
-        args.push("-r".to_string());
-        args.push(fps.to_string());
+        let output = output?;
+        if !output.status.success() {
+            return Err(Error::process("Podcast export failed", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(())
+    }
+    
+    /// Rotate (clockwise) and/or flip a clip into a new file
+    /// Rotation-only requests just rewrite the display matrix with stream copy when
+    /// FFmpeg supports -display_rotation (7+); anything else is re-encoded.
+    /// Returns true when the result is lossless.
+    pub fn transform_clip(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        rotate: u32,
+        flip_horizontal: bool,
+        flip_vertical: bool,
+    ) -> Result<bool, Error> {
+        validate_rotation(rotate)?;
 
-        // Resolution
-        if resolution != "source" {
-            args.push("-s".to_string());
-            args.push(resolution.to_string());
+        let lossless = !flip_horizontal && !flip_vertical && self.probe_capabilities()?.has_display_rotation();
+        let mut args = vec!["-y".to_string()]; // Overwrite output
+
+        if lossless {
+            // -display_rotation sets the absolute (counter-clockwise) rotation,
+            // so combine the requested turn with any rotation already present
+            let current = self.get_rotation(input_path)?;
+            let total_clockwise = (current + rotate) % 360;
+            args.extend_from_slice(&[
+                "-display_rotation".to_string(),
+                ((360 - total_clockwise) % 360).to_string(),
+                "-i".to_string(),
+                input_path.to_string(),
+                "-map".to_string(),
+                "0".to_string(),
+                "-c".to_string(),
+                "copy".to_string(),
+            ]);
+        } else {
+            // Input is auto-rotated while decoding, so the filters apply on top
+            // of the orientation the clip is displayed with
+            let filters = orientation_filters(rotate, flip_horizontal, flip_vertical);
+            args.extend_from_slice(&[
+                "-i".to_string(),
+                input_path.to_string(),
+                "-vf".to_string(),
+                filters.join(","),
+                "-c:v".to_string(),
+                "libx264".to_string(),
+                "-preset".to_string(),
+                "medium".to_string(),
+                "-crf".to_string(),
+                "18".to_string(), // Near-transparent quality for a re-importable intermediate
+                "-c:a".to_string(),
+                "copy".to_string(),
+            ]);
         }
 
-        args.push("-y".to_string()); // Overwrite output
         args.push(output_path.to_string());
 
-        let mut cmd = Command::new(&self.ffmpeg_path);
-        cmd.args(&args);
-        cmd.stdin(Stdio::piped()); // Must capture stdin for graceful shutdown
-        cmd.stderr(Stdio::piped());
-        cmd.stdout(Stdio::piped());
+        let output = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
 
-        let child = cmd.spawn()
-            .map_err(|e| format!("Failed to start FFmpeg recording: {}", e))?;
+        if !output.status.success() {
+            return Err(Error::process("Clip transform failed", String::from_utf8_lossy(&output.stderr)));
+        }
 
-        Ok(child)
+        Ok(lossless)
     }
 
-    /// Start webcam recording using FFmpeg's avfoundation device
-    /// Returns the spawned process handle
-    pub fn start_webcam_recording(
+    /// Measure loudness (integrated LUFS, true peak, LRA) of a file region
+    /// Runs loudnorm in analysis-only mode; nothing is written
+    pub fn measure_loudness(
         &self,
-        output_path: &str,
-        camera_index: u32,
-        resolution: &str,
-        fps: u32,
-        audio_device: Option<&str>,
-    ) -> Result<std::process::Child, String> {
-        use std::process::{Command, Stdio};
-        
-        // avfoundation device format: "<video_device>:<audio_device>"
-        // Camera devices are typically at indices 0+ (before screen devices)
-        let audio = audio_device.unwrap_or("none");
-        let device_input = format!("{}:{}", camera_index, audio);
+        file_path: &str,
+        trim_start: f64,
+        duration: f64,
+    ) -> Result<LoudnessMeasurement, Error> {
+        let filter = format!("{}:print_format=json", loudnorm_filter(PODCAST_TARGET_LUFS));
+
+        let output = Command::new(&self.ffmpeg_path)
+            .args([
+                "-hide_banner",
+                "-nostats",
+                "-ss", &trim_start.to_string(),
+                "-t", &duration.to_string(),
+                "-i", file_path,
+                "-vn",
+                "-af", &filter,
+                "-f", "null",
+                "-",
+            ])
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            return Err(Error::process("Loudness analysis failed", &stderr));
+        }
+
+        parse_loudnorm_stats(&stderr)
+    }
+
+    /// Get the container duration of any media file (audio-only files included)
+    pub fn probe_duration(&self, file_path: &str) -> Result<f64, Error> {
+        let output = Command::new(&self.ffprobe_path)
+            .args([
+                "-v", "error",
+                "-show_entries", "format=duration",
+                "-of", "csv=p=0",
+                file_path
+            ])
+            .output()
+            .map_err(Error::io("FFprobe execution failed"))?;
+
+        if !output.status.success() {
+            return Err(Error::process("FFprobe failed to read duration", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| Error::process(format!("Failed to parse duration of {}", file_path), String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Compare where the first video and audio streams start
+    /// A non-zero offset means trims will drift out of lip sync unless the audio is shifted
+    pub fn check_av_sync(&self, file_path: &str) -> Result<AvSyncInfo, Error> {
+        let video_start = self
+            .first_packet_time(file_path, "v:0")?
+            .ok_or_else(|| Error::InvalidInput(format!("No video stream found in {}", file_path)))?;
+        let audio_start = self
+            .first_packet_time(file_path, "a:0")?
+            .ok_or_else(|| Error::InvalidInput(format!("No audio stream found in {}", file_path)))?;
+
+        Ok(AvSyncInfo {
+            video_start,
+            audio_start,
+            offset: audio_start - video_start,
+        })
+    }
+
+    /// Earliest presentation time among the first packets of a stream, or None if absent
+    fn first_packet_time(&self, file_path: &str, stream: &str) -> Result<Option<f64>, Error> {
+        // Packets are in decode order, so read a few in case reordered frames are shown first
+        let output = Command::new(&self.ffprobe_path)
+            .args([
+                "-v", "error",
+                "-select_streams", stream,
+                "-show_entries", "packet=pts_time",
+                "-read_intervals", "%+#8",
+                "-of", "csv=p=0",
+                file_path
+            ])
+            .output()
+            .map_err(Error::io("FFprobe execution failed"))?;
+
+        if !output.status.success() {
+            return Err(Error::process("FFprobe failed to read packet times", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().trim_end_matches(',').parse::<f64>().ok())
+            .reduce(f64::min))
+    }
+
+    /// Per-clip stream facts the export filtergraph depends on, in clip order
+    fn probe_clips(&self, clips: &[ClipInfo]) -> Result<Vec<ClipProbe>, Error> {
+        clips
+            .iter()
+            .map(|clip| {
+                let metadata = self.get_metadata(&clip.file_path)?;
+                // Crop rects are in displayed pixels, so compare them to the turned frame
+                if let Some(crop) = &clip.crop {
+                    let (width, height) = match (metadata.rotation + clip.rotate) % 360 {
+                        90 | 270 => (metadata.height, metadata.width),
+                        _ => (metadata.width, metadata.height),
+                    };
+                    crop.validate_within(width, height)
+                        .map_err(|e| Error::InvalidInput(format!("{}: {}", clip.file_path, e)))?;
+                }
+                Ok(ClipProbe {
+                    has_audio: metadata.audio_codec.is_some(),
+                    rotation: metadata.rotation,
+                    // Stills have no motion to interpolate
+                    fps: if is_still_image(&clip.file_path) { 0.0 } else { metadata.fps },
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the file contains at least one audio stream
+    pub fn has_audio_stream(&self, file_path: &str) -> Result<bool, Error> {
+        let output = Command::new(&self.ffprobe_path)
+            .args([
+                "-v", "error",
+                "-select_streams", "a",
+                "-show_entries", "stream=index",
+                "-of", "csv=p=0",
+                file_path
+            ])
+            .output()
+            .map_err(Error::io("FFprobe execution failed"))?;
+
+        if !output.status.success() {
+            return Err(Error::process("FFprobe failed to read streams", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+    }
+
+    /// Split a file into a video-only and an audio-only file without re-encoding
+    /// Both outputs are written in one pass; the audio output is skipped when the
+    /// source has no audio. Output extensions must suit the source codecs
+    /// (e.g. .m4a for AAC audio)
+    pub fn demux(&self, file_path: &str, video_out: &str, audio_out: &str) -> Result<DemuxResult, Error> {
+        if video_out == audio_out {
+            return Err(Error::InvalidInput("Video and audio outputs must be different files".to_string()));
+        }
+
+        let has_audio = self.has_audio_stream(file_path)?;
 
         let mut args = vec![
-            "-f".to_string(),
-            "avfoundation".to_string(),
-            "-framerate".to_string(),
-            "30".to_string(),  // Camera supports 30 fps (not 29.97)
-            "-i".to_string(),
-            device_input,
+            "-y", "-i", file_path,
+            "-map", "0:v:0", "-c", "copy", "-an", video_out,
         ];
+        if has_audio {
+            args.extend_from_slice(&["-map", "0:a:0", "-c", "copy", "-vn", audio_out]);
+        }
 
-        // Video codec settings
-        args.push("-c:v".to_string());
-        args.push("libx264".to_string());
-        args.push("-preset".to_string());
-        args.push("ultrafast".to_string());
-        args.push("-crf".to_string());
-        args.push("23".to_string());
-        
-        // Frame rate - use exact integer (30) instead of fractional for camera compatibility
-        // Most cameras support 15-30 fps, so clamp to 30 max and use integer value
-        let clamped_fps = fps.min(30);
-        args.push("-r".to_string());
-        args.push(clamped_fps.to_string());
+        let output = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
 
-        // Resolution
-        if resolution != "source" {
-            args.push("-s".to_string());
-            args.push(resolution.to_string());
+        if !output.status.success() {
+            return Err(Error::process("Demux failed", String::from_utf8_lossy(&output.stderr)));
         }
 
-        args.push("-y".to_string()); // Overwrite output
-        args.push(output_path.to_string());
+        Ok(DemuxResult {
+            video_path: video_out.to_string(),
+            audio_path: has_audio.then(|| audio_out.to_string()),
+        })
+    }
+
+    /// List the subtitle tracks embedded in a file, in stream order
+    pub fn list_subtitle_streams(&self, file_path: &str) -> Result<Vec<SubtitleStream>, Error> {
+        let output = output_with_timeout(
+            Command::new(&self.ffprobe_path).args([
+                "-v", "error",
+                "-select_streams", "s",
+                "-show_entries", "stream=codec_name:stream_tags=language,title",
+                "-of", "json",
+                file_path
+            ]),
+            self.probe_timeout,
+            &format!("FFprobe reading {}", file_path),
+        )?;
+
+        if !output.status.success() {
+            return Err(Error::process("FFprobe failed to read subtitle streams", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        let json: Value = serde_json::from_slice(&output.stdout)
+            .map_err(|e| Error::process("Failed to parse FFprobe output", e.to_string()))?;
+
+        let streams = json["streams"].as_array().map(Vec::as_slice).unwrap_or_default();
+        Ok(streams
+            .iter()
+            .enumerate()
+            .map(|(index, stream)| SubtitleStream {
+                index: index as u32,
+                language: stream["tags"]["language"].as_str().map(String::from),
+                codec: stream["codec_name"].as_str().unwrap_or("unknown").to_string(),
+                title: stream["tags"]["title"].as_str().map(String::from),
+            })
+            .collect())
+    }
+
+    /// Write one of a file's embedded subtitle tracks to an SRT file
+    /// `stream` is the track's index from list_subtitle_streams; only text-based
+    /// tracks can be converted
+    pub fn extract_subtitles(&self, file_path: &str, stream: u32, output_path: &str) -> Result<(), Error> {
+        let streams = self.list_subtitle_streams(file_path)?;
+        let subtitle = streams.get(stream as usize).ok_or_else(|| {
+            Error::InvalidInput(format!("No subtitle stream {} in {} ({} available)", stream, file_path, streams.len()))
+        })?;
+        if BITMAP_SUBTITLE_CODECS.contains(&subtitle.codec.as_str()) {
+            return Err(Error::InvalidInput(format!(
+                "Subtitle stream {} is an image-based ({}) track and can't be converted to SRT",
+                stream, subtitle.codec
+            )));
+        }
+        if !output_path.to_lowercase().ends_with(".srt") {
+            return Err(Error::InvalidInput(format!("Subtitles must be saved as .srt, got {}", output_path)));
+        }
+        validate_output_path(output_path)?;
+
+        let output = Command::new(&self.ffmpeg_path)
+            .args([
+                "-y",
+                "-i", file_path,
+                "-map", &format!("0:s:{}", stream),
+                "-c:s", "srt",
+                output_path,
+            ])
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+
+        if !output.status.success() {
+            return Err(Error::process("Subtitle extraction failed", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(())
+    }
+
+    /// Find the timestamp of the last video keyframe at or before `timestamp`
+    /// Falls back to `timestamp` itself when the file has no video keyframes
+    fn find_keyframe_before(&self, file_path: &str, timestamp: f64) -> Result<f64, Error> {
+        // read_intervals seeks to the keyframe preceding the interval start,
+        // so a short window is enough to include the keyframe we're after
+        let interval = format!("{}%{}", (timestamp - 1.0).max(0.0), timestamp + 0.001);
+        let output = Command::new(&self.ffprobe_path)
+            .args([
+                "-v", "error",
+                "-select_streams", "v:0",
+                "-read_intervals", &interval,
+                "-show_entries", "packet=pts_time,flags",
+                "-of", "csv=p=0",
+                file_path
+            ])
+            .output()
+            .map_err(Error::io("FFprobe execution failed"))?;
 
-        let mut cmd = Command::new(&self.ffmpeg_path);
-        cmd.args(&args);
-        cmd.stdin(Stdio::piped()); // Must capture stdin for graceful shutdown
-        cmd.stderr(Stdio::piped());
-        cmd.stdout(Stdio::piped());
+        if !output.status.success() {
+            return Err(Error::process("FFprobe failed to read keyframes", String::from_utf8_lossy(&output.stderr)));
+        }
 
-        let child = cmd.spawn()
-            .map_err(|e| format!("Failed to start FFmpeg webcam recording: {}", e))?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let keyframe = stdout
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(',');
+                let pts = fields.next()?.trim().parse::<f64>().ok()?;
+                let flags = fields.next()?;
+                (flags.starts_with('K') && pts <= timestamp + 0.001).then_some(pts)
+            })
+            .fold(None, |latest: Option<f64>, pts| Some(latest.map_or(pts, |l| l.max(pts))));
 
-        Ok(child)
+        Ok(keyframe.unwrap_or(timestamp))
     }
 
-    /// List available cameras using FFmpeg's avfoundation device list
-    /// Returns a vector of camera information (index and name)
-    pub fn list_cameras(&self) -> Result<Vec<CameraInfo>, String> {
-        use std::process::Command;
+    /// Join files with identical codec settings end to end without re-encoding
+    /// Used to stitch paused recording segments back into one file
+    pub fn concat_files(&self, inputs: &[String], output_path: &str) -> Result<(), Error> {
+        if inputs.is_empty() {
+            return Err(Error::InvalidInput("No files to join".to_string()));
+        }
+        
+        // The concat demuxer reads its inputs from a list file; quotes inside
+        // paths are closed, escaped and reopened
+        let list: String = inputs
+            .iter()
+            .map(|path| format!("file '{}'\n", path.replace('\'', "'\\''")))
+            .collect();
+        let list_path = std::env::temp_dir().join(format!("concat_{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&list_path, list)
+            .map_err(Error::io("Failed to write concat list"))?;
         
-        // Run FFmpeg with list_devices flag
-        // Output goes to stderr, not stdout
-        // FFmpeg exits with non-zero code when listing devices (can't open empty input), which is expected
         let output = Command::new(&self.ffmpeg_path)
-            .args(&[
-                "-f", "avfoundation",
-                "-list_devices", "true",
-                "-i", ""
+            .args([
+                "-y",
+                "-f", "concat",
+                "-safe", "0",
+                "-i", path_str(&list_path)?,
+                "-c", "copy",
+                output_path,
             ])
-            .output()
-            .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
-        
-        // FFmpeg exits with error code when listing devices, but that's expected
-        // The device list is always in stderr regardless of exit code
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        
-        // Log stderr for debugging (remove in production if desired)
-        eprintln!("FFmpeg list_devices stderr:\n{}", stderr);
-        
-        let mut cameras = Vec::new();
-        let lines: Vec<&str> = stderr.lines().collect();
+            .output();
+        let _ = std::fs::remove_file(&list_path);
+        let output = output.map_err(Error::io("FFmpeg execution failed"))?;
         
-        let mut in_video_devices = false;
-        
-        for line in lines {
-            // Look for video device section
-            if line.contains("AVFoundation video devices") {
-                in_video_devices = true;
-                continue;
-            }
-            
-            // Stop when we hit audio devices section
-            if line.contains("AVFoundation audio devices") {
-                break;
-            }
-            
-            if !in_video_devices {
-                continue;
-            }
-            
-            // Parse device line format: [AVFoundation indev @ 0x...] [<index>] <name>
-            // Example: "[AVFoundation indev @ 0x156630da0] [0] FaceTime HD Camera"
-            // Skip screen capture devices (typically "Capture screen")
-            if line.contains("Capture screen") {
-                continue;
-            }
-            
-            // Find the second bracket pair which contains the device index
-            // Pattern: ...] [<index>] <name>
-            let trimmed = line.trim();
-            // Find the last occurrence of "] [" pattern which indicates the start of device index
-            if let Some(device_start) = trimmed.rfind("] [") {
-                // Extract the part after "] ["
-                let device_part = &trimmed[device_start + 3..];
-                if let Some(bracket_end) = device_part.find(']') {
-                    // Extract index
-                    if let Ok(index) = device_part[..bracket_end].parse::<u32>() {
-                        // Extract name (everything after "] ")
-                        let name = device_part[bracket_end + 1..].trim();
-                        if !name.is_empty() {
-                            cameras.push(CameraInfo {
-                                index,
-                                name: name.to_string(),
-                            });
-                        }
-                    }
-                }
-            }
+        if !output.status.success() {
+            return Err(Error::process("Joining files failed", String::from_utf8_lossy(&output.stderr)));
         }
         
-        Ok(cameras)
+        Ok(())
     }
-
-    /// Extract and combine audio from multiple clips in timeline order
-    /// Handles gaps between clips by inserting silence
-    pub fn extract_and_combine_audio(
+    
+    /// Render the trimmed region of a clip to a standalone file
+    /// Without re-encoding the cut snaps back to the preceding keyframe;
+    /// with re-encoding it is frame-accurate
+    pub fn render_trim(
         &self,
-        clips: &[ClipInfo],
-        composition_length: f64,
-    ) -> Result<PathBuf, String> {
-        if clips.is_empty() {
-            return Err("No clips to extract audio from".to_string());
+        file_path: &str,
+        trim_start: f64,
+        trim_end: f64,
+        output_path: &str,
+        reencode: bool,
+    ) -> Result<TrimResult, Error> {
+        if !trim_start.is_finite() || !trim_end.is_finite() || trim_start < 0.0 || trim_end <= trim_start {
+            return Err(Error::InvalidInput(format!(
+                "Invalid trim range: {} to {} (end must be after start)",
+                trim_start, trim_end
+            )));
         }
 
-        let temp_dir = std::env::temp_dir();
-        let output_path = temp_dir.join(format!("timeline_audio_{}.mp3", uuid::Uuid::new_v4()));
-        
-        // Build FFmpeg filter complex for audio concatenation with gap handling
-        let filter_complex = self.build_audio_filter_complex(clips, composition_length)?;
-        
+        let actual_start = if reencode {
+            trim_start
+        } else {
+            self.find_keyframe_before(file_path, trim_start)?
+        };
+
         let mut args = vec![
             "-y".to_string(), // Overwrite output
+            "-ss".to_string(),
+            trim_start.to_string(),
+            "-i".to_string(),
+            file_path.to_string(),
+            "-t".to_string(),
+            (trim_end - trim_start).to_string(),
         ];
-        
-        // Add input files
-        for clip in clips {
-            args.push("-i".to_string());
-            args.push(clip.file_path.clone());
+
+        if reencode {
+            args.extend_from_slice(&[
+                "-c:v".to_string(),
+                "libx264".to_string(),
+                "-preset".to_string(),
+                "medium".to_string(),
+                "-crf".to_string(),
+                "18".to_string(),
+                "-c:a".to_string(),
+                "aac".to_string(),
+                "-b:a".to_string(),
+                "192k".to_string(),
+            ]);
+        } else {
+            args.extend_from_slice(&[
+                "-c".to_string(),
+                "copy".to_string(),
+                "-avoid_negative_ts".to_string(),
+                "make_zero".to_string(),
+            ]);
         }
-        
-        // Add filter complex
-        args.push("-filter_complex".to_string());
-        args.push(filter_complex);
-        
-        // Output audio only (MP3)
-        args.extend_from_slice(&[
-            "-map".to_string(),
-            "[outa]".to_string(),
-            "-acodec".to_string(),
-            "libmp3lame".to_string(),
-            "-ar".to_string(),
-            "16000".to_string(), // 16kHz sample rate (Whisper optimal)
-            "-ac".to_string(),
-            "1".to_string(), // Mono
-            "-b:a".to_string(),
-            "128k".to_string(), // Bitrate
-            output_path.to_str().ok_or("Invalid output path")?.to_string(),
-        ]);
-        
+
+        args.push(output_path.to_string());
+
         let output = Command::new(&self.ffmpeg_path)
             .args(&args)
             .output()
-            .map_err(|e| format!("FFmpeg execution failed: {}", e))?;
-        
+            .map_err(Error::io("FFmpeg execution failed"))?;
+
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("Audio extraction failed: {}", stderr));
+            return Err(Error::process("Trim render failed", String::from_utf8_lossy(&output.stderr)));
         }
-        
-        Ok(output_path)
+
+        let duration = self.probe_duration(output_path)?;
+
+        Ok(TrimResult {
+            output_path: output_path.to_string(),
+            actual_start,
+            actual_end: actual_start + duration,
+            duration,
+            snapped_to_keyframe: (actual_start - trim_start).abs() > 0.001,
+        })
     }
 
-    /// Build audio filter complex for audio concatenation with gap handling
-    fn build_audio_filter_complex(
+    /// Extract a numbered image sequence (frame_00001.png, ...) into `output_dir`
+    pub fn extract_frames(
         &self,
-        clips: &[ClipInfo],
-        composition_length: f64,
-    ) -> Result<String, String> {
-        let mut filters = Vec::new();
-        let mut audio_indices = Vec::new();
-        let mut current_time = 0.0;
-        
-        // Build audio segments with silence gaps
-        for (i, clip) in clips.iter().enumerate() {
-            // Check if there's a gap before this clip
-            if clip.start_time > current_time {
-                let gap_duration = clip.start_time - current_time;
-                
-                // Create silence gap segment
-                let gap_filter = format!(
-                    "anullsrc=channel_layout=mono:sample_rate=16000:d={}[silence{}]",
-                    gap_duration,
-                    i
-                );
-                filters.push(gap_filter);
-                audio_indices.push(format!("[silence{}]", i));
+        file_path: &str,
+        sampling: FrameSampling,
+        output_dir: &str,
+        format: ImageFormat,
+    ) -> Result<FrameExtraction, Error> {
+        let filter = match sampling {
+            FrameSampling::EveryNthFrame(0) => {
+                return Err(Error::InvalidInput("Frame step must be at least 1".to_string()));
             }
-            
-            // Extract and trim audio from clip
-            let trim_filter = format!(
-                "[{}:a]atrim=start={}:duration={},asetpts=PTS-STARTPTS,aresample=16000:async=1[clip{}a]",
-                i,
-                clip.trim_start,
-                clip.duration,
-                i
-            );
-            filters.push(trim_filter);
-            audio_indices.push(format!("[clip{}a]", i));
-            
-            current_time = clip.start_time + clip.duration;
-        }
-        
-        // Add silence to fill to composition length if needed
-        if current_time < composition_length {
-            let gap_duration = composition_length - current_time;
-            let gap_index = clips.len();
-            
-            let gap_filter = format!(
-                "anullsrc=channel_layout=mono:sample_rate=16000:d={}[silence{}]",
-                gap_duration,
-                gap_index
-            );
-            filters.push(gap_filter);
-            audio_indices.push(format!("[silence{}]", gap_index));
-        }
-        
-        // Concatenate all audio segments (silence + clips + end silence)
-        let concat_inputs: String = audio_indices.join("");
-        
-        filters.push(format!(
-            "{}concat=n={}:v=0:a=1[outa]",
-            concat_inputs,
-            audio_indices.len()
-        ));
-        
-        Ok(filters.join(";"))
-    }
+            FrameSampling::EveryNthFrame(n) => format!("select=not(mod(n\\,{}))", n),
+            FrameSampling::Interval(secs) if !secs.is_finite() || secs <= 0.0 => {
+                return Err(Error::InvalidInput(format!("Frame interval must be positive, got {}", secs)));
+            }
+            FrameSampling::Interval(secs) => format!("fps=1/{}", secs),
+        };
 
-    /// Extract audio from video clip to temporary file
-    /// Returns path to extracted audio file
-    pub fn extract_audio(
-        &self,
-        video_path: &str,
-        trim_start: f64,
-        duration: f64,
-        output_format: AudioFormat,
-    ) -> Result<PathBuf, String> {
-        let temp_dir = std::env::temp_dir();
-        let output_file = temp_dir.join(format!(
-            "audio_{}_{}.{}",
-            uuid::Uuid::new_v4(),
-            chrono::Utc::now().timestamp(),
-            output_format.extension()
-        ));
+        std::fs::create_dir_all(output_dir)
+            .map_err(Error::io("Failed to create output directory"))?;
 
-        let output_path = output_file.to_str().ok_or("Invalid output path")?;
+        let pattern = PathBuf::from(output_dir).join(format!("frame_%05d.{}", format.extension()));
+        let pattern = path_str(&pattern)?;
 
-        // Build FFmpeg command for audio extraction
         let mut args = vec![
-            "-ss".to_string(),
-            trim_start.to_string(),
+            "-y".to_string(), // Overwrite output
             "-i".to_string(),
-            video_path.to_string(),
-            "-t".to_string(),
-            duration.to_string(),
-            "-vn".to_string(), // No video
-            "-acodec".to_string(),
+            file_path.to_string(),
+            "-vf".to_string(),
+            filter,
+            "-fps_mode".to_string(),
+            "vfr".to_string(), // Don't duplicate frames to fill the dropped ones
         ];
+        if let ImageFormat::Jpg = format {
+            args.push("-q:v".to_string());
+            args.push("2".to_string());
+        }
+        args.extend_from_slice(&[
+            "-f".to_string(),
+            "image2".to_string(),
+            pattern.to_string(),
+        ]);
 
-        match output_format {
-            AudioFormat::Mp3 => {
-                args.push("libmp3lame".to_string());
-                args.push("-q:a".to_string());
-                args.push("2".to_string()); // High quality
-            }
-            AudioFormat::Wav => {
-                args.push("pcm_s16le".to_string());
-            }
-            AudioFormat::M4a => {
-                args.push("aac".to_string());
-                args.push("-b:a".to_string());
-                args.push("192k".to_string());
-            }
+        let output = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .output()
+            .map_err(Error::io("FFmpeg execution failed"))?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            return Err(Error::process("Frame extraction failed", &stderr));
         }
 
-        args.push("-y".to_string()); // Overwrite
-        args.push(output_path.to_string());
+        // The final stats line reports how many frames were written
+        let count = stderr
+            .rfind("frame=")
+            .and_then(|pos| {
+                stderr[pos + 6..]
+                    .split_whitespace()
+                    .next()
+                    .and_then(|n| n.parse::<u32>().ok())
+            })
+            .unwrap_or(0);
 
-        // Execute FFmpeg using self.ffmpeg_path
+        Ok(FrameExtraction {
+            count,
+            output_dir: output_dir.to_string(),
+        })
+    }
+    
+    /// Decode up to `max_secs` of a file's audio as mono 16-bit PCM, returned as
+    /// samples scaled to -1.0..=1.0
+    pub fn decode_pcm_mono(&self, file_path: &str, sample_rate: u32, max_secs: f64) -> Result<Vec<f32>, Error> {
+        if !max_secs.is_finite() || max_secs <= 0.0 {
+            return Err(Error::InvalidInput(format!("Invalid audio decode length: {}", max_secs)));
+        }
+        
         let output = Command::new(&self.ffmpeg_path)
-            .args(&args)
+            .args([
+                "-v", "error",
+                "-i", file_path,
+                "-t", &max_secs.to_string(),
+                "-vn",
+                "-ac", "1",
+                "-ar", &sample_rate.to_string(),
+                "-f", "s16le",
+                "pipe:1",
+            ])
             .output()
-            .map_err(|e| format!("FFmpeg execution failed: {}", e))?;
+            .map_err(Error::io("FFmpeg execution failed"))?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("Audio extraction failed: {}", stderr));
+            return Err(Error::process("Audio decode failed", String::from_utf8_lossy(&output.stderr)));
         }
 
-        Ok(output_file)
+        if output.stdout.is_empty() {
+            return Err(Error::InvalidInput(format!("No audio could be decoded from {}", file_path)));
+        }
+
+        Ok(output
+            .stdout
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]) as f32 / i16::MAX as f32)
+            .collect())
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Probe of a plain 30 fps clip with sound
+    const PROBE: ClipProbe = ClipProbe { has_audio: true, rotation: 0, fps: 30.0 };
+
+    fn clip(start_time: f64, duration: f64) -> ClipInfo {
+        serde_json::from_value(serde_json::json!({
+            "filePath": "clip.mp4",
+            "startTime": start_time,
+            "duration": duration,
+            "trimStart": 0.0,
+            "trimEnd": duration,
+        }))
+        .unwrap()
+    }
+
+    /// Filtergraph of a 30 fps main-track export; a fixed resolution keeps FFmpeg from being probed
+    fn filter_complex(clips: &[ClipInfo], resolution: &str, probes: &[ClipProbe]) -> String {
+        let length = compute_composition_length(clips);
+        let segments = timeline_segments(clips, &[], length).unwrap();
+        FFmpegExecutor::with_paths(PathBuf::from("ffmpeg"), PathBuf::from("ffprobe"))
+            .build_filter_complex(&segments, resolution, 30, length, &ExportOptions::default(), None, probes, None)
+            .unwrap()
+    }
+
+    #[test]
+    fn reverses_only_clips_marked_reverse() {
+        let mut reversed = clip(0.0, 5.0);
+        reversed.reverse = true;
+
+        assert!(filter_complex(&[reversed], "1920x1080", &[PROBE]).contains("reverse"));
+        assert!(!filter_complex(&[clip(0.0, 5.0)], "1920x1080", &[PROBE]).contains("reverse"));
+    }
+
+    #[test]
+    fn parses_negative_gdigrab_origin() {
+        let log = "[gdigrab @ 0x1] Capturing whole desktop as 3840x1080x32 at (-1920,0)\n";
+        assert_eq!(parse_gdigrab_origin(log), Some((-1920, 0)));
+        assert_eq!(parse_gdigrab_origin("Input #0, gdigrab, from 'desktop':"), None);
+    }
+
+    #[test]
+    fn capture_region_may_sit_left_of_primary_display() {
+        let desktop = ScreenBounds { x: -1920, y: 0, width: 3840, height: 1080 };
+        let region = |x, y| CaptureRegion { x, y, width: 1280, height: 720 };
+
+        assert!(region(-1920, 0).validate(&desktop).is_ok());
+        assert!(region(640, 360).validate(&desktop).is_ok());
+        assert!(region(-1922, 0).validate(&desktop).is_err());
+        assert!(region(700, 0).validate(&desktop).is_err());
+        assert!(region(0, -2).validate(&desktop).is_err());
+    }
+
+    #[test]
+    fn leading_gap_matches_the_export_size() {
+        let filter = filter_complex(&[clip(2.0, 5.0)], "720p", &[PROBE]);
+
+        assert!(filter.contains("color=c=black:s=1280x720:d=2:r=30,scale=1280:720[gap0]"), "{}", filter);
+        assert!(filter.contains("anullsrc=channel_layout=stereo:sample_rate=48000:d=2[gap0a]"));
+        assert!(filter.contains("[gap0][gap0a][clip0][clip0a]concat=n=2"));
+    }
+
+    #[test]
+    fn clip_volume_scales_only_that_clips_audio() {
+        let mut quiet = clip(0.0, 5.0);
+        quiet.volume = 0.5;
+        let filter = filter_complex(&[quiet.clone(), clip(5.0, 5.0)], "1920x1080", &[PROBE, PROBE]);
+
+        assert!(filter.contains("channel_layouts=stereo,volume=0.5,apad,atrim=duration=5[clip0a]"), "{}", filter);
+        assert_eq!(filter.matches("volume=").count(), 1);
+
+        quiet.volume = 2.5;
+        assert!(validate_clips(&[quiet]).is_err());
+    }
+
+    #[test]
+    fn frame_rate_warnings_cover_overlay_clips() {
+        let main = clip(0.0, 5.0);
+        let overlay = clip(1.0, 2.0);
+        let slow = || ClipProbe { fps: 10.0, ..PROBE };
+
+        let warnings = FFmpegExecutor::frame_rate_warnings([&main, &overlay], &[PROBE, slow()], 60, false);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("(10.00 fps) at 60 fps repeats frames"), "{}", warnings[0]);
+        assert!(FFmpegExecutor::frame_rate_warnings([&main, &overlay], &[PROBE, slow()], 60, true).is_empty());
+    }
+
+    #[test]
+    fn audio_only_graph_composes_no_picture() {
+        let clips = [clip(0.0, 5.0), clip(8.0, 5.0)];
+        let segments = timeline_segments(&clips, &[], 13.0).unwrap();
+        let options = ExportOptions { format: ExportFormat::Audio, ..ExportOptions::default() };
+        let filter = FFmpegExecutor::with_paths(PathBuf::from("ffmpeg"), PathBuf::from("ffprobe"))
+            .build_filter_complex(&segments, "1920x1080", 30, 13.0, &options, None, &[PROBE, PROBE], None)
+            .unwrap();
+
+        assert!(filter.contains("[clip0a][gap1a][clip1a]concat=n=3:v=0:a=1[outa]"), "{}", filter);
+        assert!(!filter.contains(":v]") && !filter.contains("color="), "{}", filter);
+    }
+
+    #[test]
+    fn rotated_stream_is_turned_upright_in_the_filtergraph() {
+        // A phone's display matrix reports -90 (counter-clockwise); older files tag "90"
+        let matrix = serde_json::json!({ "side_data_list": [{ "rotation": -90 }] });
+        let tagged = serde_json::json!({ "tags": { "rotate": "90" } });
+        assert_eq!(parse_rotation(&matrix), 90);
+        assert_eq!(parse_rotation(&tagged), 90);
+
+        let portrait = clip(0.0, 5.0);
+        assert!(clip_input_args(&portrait, 30).contains(&"-noautorotate".to_string()));
+        let rotated = ClipProbe { rotation: 90, ..PROBE };
+        let filter = filter_complex(&[portrait], "1920x1080", &[rotated]);
+        assert!(filter.contains("setpts=PTS-STARTPTS,transpose=clock,"), "{}", filter);
+    }
+
+    #[test]
+    fn sideways_turn_fits_inside_the_frame() {
+        let mut turned = clip(0.0, 5.0);
+        turned.rotate = 90;
+        let fit = ScaleMode::Fit.filter("1920x1080").unwrap();
+
+        // Stretching would squash the now-portrait 1080x1920 picture into 1920x1080
+        let filter = filter_complex(&[turned.clone()], "1920x1080", &[PROBE]);
+        assert!(filter.contains(&format!(",transpose=clock,{}[clip0]", fit)), "{}", filter);
+        assert!(filter_complex(&[clip(0.0, 5.0)], "1920x1080", &[PROBE]).contains(",scale=1920:1080[clip0]"));
+
+        // A portrait phone stream is already sideways; turning it again makes it landscape
+        let portrait = ClipProbe { rotation: 90, ..PROBE };
+        let filter = filter_complex(&[clip(0.0, 5.0)], "1920x1080", &[portrait]);
+        assert!(filter.contains(&format!("{}[clip0]", fit)), "{}", filter);
+        let turned_back = filter_complex(&[turned], "1920x1080", &[ClipProbe { rotation: 90, ..PROBE }]);
+        assert!(turned_back.contains(",scale=1920:1080[clip0]"), "{}", turned_back);
+    }
+
+    #[test]
+    fn speech_audio_is_downmixed_to_16khz_mono() {
+        let args = |speech| extract_audio_args("in.mp4", 12.5, 30.0, AudioFormat::Mp3, speech, "out.mp3");
+
+        assert!(args(true).windows(4).any(|w| w == ["-ar", "16000", "-ac", "1"]));
+        assert!(!args(false).iter().any(|arg| arg == "-ar" || arg == "-ac"));
+    }
+
+    #[test]
+    fn cursor_overlay_cap_keeps_every_click() {
+        let events: Vec<CursorEvent> = (0..MAX_CURSOR_OVERLAYS * 2)
+            .map(|i| CursorEvent { time: i as f64 * 0.5, x: 100.0, y: 100.0, click: i % 10 == 0 })
+            .collect();
+        let clicks = events.iter().filter(|e| e.click).count();
+
+        let filters = cursor_overlay_filters(&events, MAX_CURSOR_OVERLAYS as f64);
+
+        assert!(filters.len() <= MAX_CURSOR_OVERLAYS);
+        assert_eq!(filters.iter().filter(|f| f.contains("yellow")).count(), clicks);
+        // Thinned positions still span the recording rather than stopping early
+        let last_position = filters.iter().rfind(|f| f.contains("white")).unwrap();
+        assert!(last_position.contains(&format!(",{})'", MAX_CURSOR_OVERLAYS)));
+    }
+
+    #[test]
+    fn quality_ranges_follow_the_codec() {
+        let quality = |crf, preset: &str| ExportQuality { crf, preset: preset.to_string() };
+
+        assert!(quality(63, "veryslow").validate(VideoCodec::Vp9).is_ok());
+        assert!(quality(30, "anything").validate(VideoCodec::Vp9).is_err());
+        assert!(quality(30, "anything").validate(VideoCodec::ProRes).is_ok());
+        assert!(quality(64, "medium").validate(VideoCodec::Vp9).is_err());
+        assert!(quality(52, "medium").validate(VideoCodec::H264).is_err());
+        assert!(quality(23, "anything").validate(VideoCodec::Hevc).is_err());
+
+        let args = VideoCodec::Vp9.output_args(&quality(40, "veryslow")).unwrap().join(" ");
+        assert!(args.contains("-crf 40"));
+        assert!(args.contains("-deadline good -cpu-used 0"));
+        assert!(!args.contains("-preset"));
+    }
+
+    #[test]
+    fn h264_only_goes_into_listed_containers() {
+        assert!(VideoCodec::H264.validate_container("export.MOV").is_ok());
+        assert!(VideoCodec::Hevc.validate_container("export.mkv").is_ok());
+        assert!(VideoCodec::H264.validate_container("export.avi").is_err());
+        assert!(VideoCodec::H264.validate_container("export").is_err());
+    }
+
+    #[test]
+    fn output_path_needs_an_existing_folder() {
+        let folder = std::env::temp_dir().join(format!("missing-{}", uuid::Uuid::new_v4()));
+        let output = folder.join("export.mp4");
+
+        let error = validate_output_path(&output.to_string_lossy()).unwrap_err();
+        assert!(
+            matches!(&error, Error::InvalidInput(message) if message.starts_with("Output folder doesn't exist")),
+            "{}",
+            error
+        );
+        assert!(!folder.exists());
+        assert!(validate_output_path(&std::env::temp_dir().join("export.mp4").to_string_lossy()).is_ok());
+    }
+
+    #[test]
+    fn stable_hash_is_fnv1a() {
+        assert_eq!(stable_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn prune_media_cache_drops_least_recently_used() {
+        let kind = format!("prunetest{}", uuid::Uuid::new_v4().simple());
+        let entry = |name: &str, age_secs: u64| {
+            let path = std::env::temp_dir().join(format!("{}_{}", kind, name));
+            std::fs::write(&path, [0u8; 100]).unwrap();
+            let file = std::fs::File::options().append(true).open(&path).unwrap();
+            file.set_modified(std::time::SystemTime::now() - Duration::from_secs(age_secs)).unwrap();
+            path
+        };
+        let oldest = entry("a.jpg", 300);
+        let older = entry("b.jpg", 200);
+        let newest = entry("c.jpg", 100);
+        let partial = entry("d.partial-x.jpg", 400);
+
+        prune_media_cache(&kind, 150);
+
+        assert!(!oldest.exists());
+        assert!(!older.exists());
+        assert!(newest.exists());
+        assert!(partial.exists());
+        for path in [newest, partial] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -1,12 +1,16 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
+mod audio_sync;
 mod commands;
+mod error;
 mod ffmpeg;
 mod recording;
 mod transcription;
+mod whisper_cpp;
 
-use commands::{export_video, generate_thumbnail, get_media_metadata, list_cameras, transcribe_clip, transcribe_timeline, export_transcript};
-use recording::{start_screen_recording, start_webcam_recording, stop_recording, get_recording_status};
+use commands::{export_video, cancel_export, export_timeline_frame, export_podcast, transform_clip, render_trim, demux, list_subtitle_streams, extract_subtitles, extract_frames, find_audio_offset, measure_loudness, check_av_sync, generate_thumbnail, generate_thumbnails, generate_filmstrip, generate_waveform, detect_scene_changes, get_frame_color, get_media_metadata, has_audio, list_cameras, get_camera_capabilities, list_audio_devices, transcribe_clip, transcribe_clips, transcribe_timeline, export_transcript, verify_openai_key};
+use ffmpeg::FFmpegState;
+use recording::{start_screen_recording, start_webcam_recording, start_pip_recording, pause_recording, resume_recording, stop_recording, get_recording_status};
 
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -19,19 +23,45 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(FFmpegState::resolve())
         .invoke_handler(tauri::generate_handler![
             greet,
             get_media_metadata,
+            has_audio,
             generate_thumbnail,
+            generate_thumbnails,
+            generate_filmstrip,
+            generate_waveform,
+            detect_scene_changes,
+            get_frame_color,
             export_video,
+            cancel_export,
+            export_timeline_frame,
+            export_podcast,
+            transform_clip,
+            render_trim,
+            demux,
+            list_subtitle_streams,
+            extract_subtitles,
+            extract_frames,
+            find_audio_offset,
+            measure_loudness,
+            check_av_sync,
             list_cameras,
+            get_camera_capabilities,
+            list_audio_devices,
             start_screen_recording,
             start_webcam_recording,
+            start_pip_recording,
+            pause_recording,
+            resume_recording,
             stop_recording,
             get_recording_status,
             transcribe_clip,
+            transcribe_clips,
             transcribe_timeline,
-            export_transcript
+            export_transcript,
+            verify_openai_key
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
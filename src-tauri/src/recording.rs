@@ -2,97 +2,458 @@
 //
 // Manages screen and webcam recording state and provides Tauri commands.
 // Uses FFmpeg's avfoundation device for macOS recording.
+// Pausing stops FFmpeg; each resumed stretch is recorded to its own segment
+// file and the segments are joined into the output when recording stops.
 
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::path::Path;
 use std::process::Child;
 use std::time::{Duration, Instant};
 use std::io::Write;
-use crate::ffmpeg::FFmpegExecutor;
+use tauri::{Emitter, Manager, State};
+use crate::error::Error;
+use crate::ffmpeg::{validate_output_path, FFmpegExecutor, FFmpegState, PipOptions, ScreenRecordingOptions};
 
 #[derive(Clone)]
 pub struct RecordingState {
     pub is_recording: bool,
+    pub is_paused: bool,
+    /// Start of the segment being recorded; None while paused
     pub start_time: Option<Instant>,
+    /// Recorded time in segments that have already finished
+    pub recorded_before: Duration,
     pub output_path: Option<String>,
+    /// Segment files recorded so far, in order
+    pub segments: Vec<String>,
     pub recording_type: RecordingType,
+    /// Settings needed to start the next segment on resume
+    source: Option<RecordingSource>,
+    /// A pause or resume is stopping or starting FFmpeg with the lock released
+    transitioning: bool,
 }
 
 #[derive(Clone, Debug)]
 pub enum RecordingType {
     Screen,
     Webcam { camera_index: u32 },
+    /// Screen with the webcam composited into a corner
+    PictureInPicture { camera_index: u32 },
+}
+
+/// What is being recorded, with everything needed to restart FFmpeg after a pause
+#[derive(Clone, Debug)]
+enum RecordingSource {
+    Screen {
+        resolution: String,
+        fps: u32,
+        capture_cursor: bool,
+        capture_clicks: bool,
+        audio_device: Option<String>,
+        options: ScreenRecordingOptions,
+    },
+    Webcam {
+        camera_index: u32,
+        resolution: String,
+        fps: u32,
+        audio_device: Option<String>,
+    },
+    PictureInPicture {
+        camera_index: u32,
+        resolution: String,
+        fps: u32,
+        capture_cursor: bool,
+        audio_device: Option<String>,
+        pip: PipOptions,
+        options: ScreenRecordingOptions,
+    },
 }
 
 impl Default for RecordingState {
     fn default() -> Self {
         Self {
             is_recording: false,
+            is_paused: false,
             start_time: None,
+            recorded_before: Duration::ZERO,
             output_path: None,
+            segments: Vec::new(),
             recording_type: RecordingType::Screen,
+            source: None,
+            transitioning: false,
         }
     }
 }
 
+impl RecordingState {
+    /// Recorded time so far, excluding paused stretches
+    fn elapsed(&self) -> Duration {
+        self.recorded_before + self.start_time.map(|start| start.elapsed()).unwrap_or_default()
+    }
+}
+
 // Global recording state
 static RECORDING_STATE: Mutex<Option<Arc<Mutex<RecordingState>>>> = Mutex::new(None);
 static RECORDING_PROCESS: Mutex<Option<Child>> = Mutex::new(None);
 
+/// Incremented for every new recording so background watchers can tell
+/// whether the recording they were started for is still the current one
+static RECORDING_SESSION: AtomicU64 = AtomicU64::new(0);
+
+/// How often the duration limit watcher checks the elapsed time
+const DURATION_LIMIT_POLL: Duration = Duration::from_millis(250);
+
+/// Interval between `recording-tick` events
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Webcam start attempts when the camera is still held by another process
+const WEBCAM_START_ATTEMPTS: u32 = 3;
+
+/// Pause between webcam start attempts so the previous owner can release the camera
+const WEBCAM_RETRY_DELAY: Duration = Duration::from_millis(1000);
+
+/// How often a stop checks whether a pause or resume has finished
+const TRANSITION_POLL: Duration = Duration::from_millis(20);
+
+/// Marks a pause or resume that is stopping or starting FFmpeg outside the state lock
+/// The mark is cleared when this is dropped, so an early return or panic can't leave it set
+struct Transition<'a>(&'a Mutex<RecordingState>);
+
+impl Drop for Transition<'_> {
+    fn drop(&mut self) {
+        lock_state(self.0).transitioning = false;
+    }
+}
+
+/// Whether FFmpeg failed because the capture device is temporarily in use
+fn is_device_busy(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    ["device busy", "resource busy", "input/output error"]
+        .iter()
+        .any(|pattern| stderr.contains(pattern))
+}
+
 fn get_state() -> Arc<Mutex<RecordingState>> {
-    let mut state_guard = RECORDING_STATE.lock().unwrap();
+    // The slot only ever holds a finished Arc, so a panic can't leave it half-written
+    let mut state_guard = RECORDING_STATE.lock().unwrap_or_else(PoisonError::into_inner);
     if state_guard.is_none() {
         *state_guard = Some(Arc::new(Mutex::new(RecordingState::default())));
     }
     Arc::clone(state_guard.as_ref().unwrap())
 }
 
-/// Start screen recording
-#[tauri::command]
-pub async fn start_screen_recording(
-    output_path: String,
-    resolution: String,
+/// Lock the recording state, recovering it if a thread panicked while holding the lock
+/// A panic may have left the state claiming a recording with no FFmpeg behind it;
+/// that's reset so a new recording can start
+fn lock_state(state: &Mutex<RecordingState>) -> MutexGuard<'_, RecordingState> {
+    match state.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            let mut guard = poisoned.into_inner();
+            state.clear_poison();
+            if guard.is_recording && !guard.is_paused && lock_process().is_none() {
+                eprintln!("⚠ Recording state was left inconsistent by a panic; resetting it");
+                *guard = RecordingState::default();
+            }
+            guard
+        }
+    }
+}
+
+/// Lock the FFmpeg recording process slot, recovering it if a thread panicked while
+/// holding the lock; the child handle is still valid, so stopping it keeps working
+fn lock_process() -> MutexGuard<'static, Option<Child>> {
+    RECORDING_PROCESS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Segment file for the nth recorded stretch, next to the final output
+/// e.g. "/path/demo.mp4" -> "/path/demo.part0.mp4"
+fn segment_path(output_path: &str, index: usize) -> String {
+    let path = Path::new(output_path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.part{}.{}", stem, index, ext),
+        None => format!("{}.part{}", stem, index),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Spawn FFmpeg recording one segment from the given source
+fn start_segment(executor: &FFmpegExecutor, source: &RecordingSource, output_path: &str) -> Result<Child, Error> {
+    match source {
+        RecordingSource::Screen { resolution, fps, capture_cursor, capture_clicks, audio_device, options } => {
+            executor.start_screen_recording(
+                output_path,
+                resolution,
+                *fps,
+                *capture_cursor,
+                *capture_clicks,
+                audio_device.as_deref(),
+                options,
+            )
+        }
+        RecordingSource::Webcam { camera_index, resolution, fps, audio_device } => {
+            start_webcam_segment(executor, output_path, *camera_index, resolution, *fps, audio_device.as_deref())
+        }
+        RecordingSource::PictureInPicture { camera_index, resolution, fps, capture_cursor, audio_device, pip, options } => {
+            executor.start_pip_recording(
+                output_path,
+                *camera_index,
+                resolution,
+                *fps,
+                *capture_cursor,
+                audio_device.as_deref(),
+                pip,
+                options,
+            )
+        }
+    }
+}
+
+/// Start webcam capture, checking that FFmpeg actually got hold of the camera
+fn start_webcam_segment(
+    executor: &FFmpegExecutor,
+    output_path: &str,
+    camera_index: u32,
+    resolution: &str,
     fps: u32,
-    capture_cursor: bool,
-    capture_clicks: bool,
-    audio_device: Option<String>,
-) -> Result<(), String> {
+    audio: Option<&str>,
+) -> Result<Child, Error> {
+    let mut attempt = 1;
+    
+    // The camera can stay held for a moment after another app releases it,
+    // so retry a few times when FFmpeg reports the device as busy
+    loop {
+        let mut child = executor.start_webcam_recording(
+            output_path,
+            camera_index,
+            resolution,
+            fps,
+            audio,
+        )?;
+        
+        // Wait a moment to check if process starts successfully
+        std::thread::sleep(Duration::from_millis(500));
+        
+        // Check if process immediately exited (indicates startup failure)
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                // Process exited immediately - capture stderr to see why
+                use std::io::Read;
+                let mut stderr_bytes = Vec::new();
+                if let Some(mut stderr) = child.stderr.take() {
+                    let _ = stderr.read_to_end(&mut stderr_bytes);
+                }
+                let stderr_output = String::from_utf8_lossy(&stderr_bytes);
+                
+                let busy = is_device_busy(&stderr_output);
+                if busy && attempt < WEBCAM_START_ATTEMPTS {
+                    eprintln!(
+                        "Camera busy, retrying webcam start (attempt {} of {})",
+                        attempt + 1,
+                        WEBCAM_START_ATTEMPTS
+                    );
+                    attempt += 1;
+                    std::thread::sleep(WEBCAM_RETRY_DELAY);
+                    continue;
+                }
+                
+                let error = if busy {
+                    Error::ProcessFailed {
+                        context: format!(
+                            "Camera is still in use by another app after {} attempts (exit status: {:?})",
+                            attempt, status
+                        ),
+                        stderr: stderr_output.trim().to_string(),
+                    }
+                } else if !stderr_output.is_empty() {
+                    Error::process(
+                        format!(
+                            "FFmpeg webcam recording failed to start (exit status: {:?}). Possible causes: camera permission not granted, camera in use by another app, camera not found, or invalid camera index",
+                            status
+                        ),
+                        &stderr_output,
+                    )
+                } else {
+                    Error::ProcessFailed {
+                        context: format!("FFmpeg exited immediately with status: {:?}. No stderr output available.", status),
+                        stderr: String::new(),
+                    }
+                };
+                eprintln!("{}", error);
+                return Err(error);
+            }
+            Ok(None) => {
+                // Process is running - good!
+                return Ok(child);
+            }
+            Err(e) => {
+                eprintln!("Error checking process status: {}", e);
+                return Ok(child);
+            }
+        }
+    }
+}
+
+/// Whether the recording in progress writes to `path` (its output or one of its segments)
+pub fn is_recording_to(path: &str) -> bool {
+    let state = get_state();
+    let state_guard = lock_state(&state);
+    state_guard.is_recording
+        && (state_guard.output_path.as_deref() == Some(path) || state_guard.segments.iter().any(|s| s == path))
+}
+
+/// Begin a new recording from the first segment
+/// Returns the session number identifying this recording
+fn start_recording(
+    executor: &FFmpegExecutor,
+    output_path: String,
+    source: RecordingSource,
+    recording_type: RecordingType,
+) -> Result<u64, Error> {
     let state = get_state();
     
     // Check if already recording
     {
-        let state_guard = state.lock().unwrap();
+        let state_guard = lock_state(&state);
         if state_guard.is_recording {
-            return Err("Recording is already in progress".to_string());
+            return Err(Error::InvalidInput("Recording is already in progress".to_string()));
         }
     }
     
-    let executor = FFmpegExecutor::new()?;
+    validate_output_path(&output_path)?;
     
-    let audio = audio_device.as_deref();
-    let child = executor.start_screen_recording(
-        &output_path,
-        &resolution,
-        fps,
-        capture_cursor,
-        capture_clicks,
-        audio,
-    )?;
+    let segment = segment_path(&output_path, 0);
+    let child = start_segment(executor, &source, &segment)?;
     
     // Store process handle
     {
-        let mut process_guard = RECORDING_PROCESS.lock().unwrap();
+        let mut process_guard = lock_process();
         *process_guard = Some(child);
     }
     
     // Update state
     {
-        let mut state_guard = state.lock().unwrap();
-        state_guard.is_recording = true;
-        state_guard.start_time = Some(Instant::now());
-        state_guard.output_path = Some(output_path);
-        state_guard.recording_type = RecordingType::Screen;
+        let mut state_guard = lock_state(&state);
+        *state_guard = RecordingState {
+            is_recording: true,
+            is_paused: false,
+            start_time: Some(Instant::now()),
+            recorded_before: Duration::ZERO,
+            output_path: Some(output_path),
+            segments: vec![segment],
+            recording_type,
+            source: Some(source),
+            transitioning: false,
+        };
     }
     
+    Ok(RECORDING_SESSION.fetch_add(1, Ordering::SeqCst) + 1)
+}
+
+/// Emit `recording-tick` with the elapsed seconds and bytes written so far
+/// every second until the recording stops
+fn spawn_ticker(window: tauri::Window, session: u64) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK_INTERVAL);
+        
+        if RECORDING_SESSION.load(Ordering::SeqCst) != session {
+            return;
+        }
+        let (elapsed, segments) = {
+            let state = get_state();
+            let state_guard = lock_state(&state);
+            if !state_guard.is_recording {
+                return;
+            }
+            (state_guard.elapsed(), state_guard.segments.clone())
+        };
+        
+        // The output is still split across segment files while recording
+        let file_size: u64 = segments
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        let _ = window.emit(
+            "recording-tick",
+            serde_json::json!({ "elapsed": elapsed.as_secs_f64(), "fileSize": file_size }),
+        );
+    });
+}
+
+/// Stop the recording once it has recorded `limit` (paused time doesn't count)
+/// and emit `recording-stopped` with the output path or the error
+fn spawn_duration_limit(window: tauri::Window, session: u64, limit: Duration) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(DURATION_LIMIT_POLL);
+        
+        // Stopped by hand, or replaced by a newer recording
+        if RECORDING_SESSION.load(Ordering::SeqCst) != session {
+            return;
+        }
+        let elapsed = {
+            let state = get_state();
+            let state_guard = lock_state(&state);
+            if !state_guard.is_recording {
+                return;
+            }
+            state_guard.elapsed()
+        };
+        
+        if elapsed >= limit {
+            let ffmpeg = window.state::<FFmpegState>();
+            let payload = match ffmpeg.executor().and_then(finish_recording) {
+                Ok(output_path) => serde_json::json!({ "reason": "maxDuration", "outputPath": output_path }),
+                Err(error) => serde_json::json!({ "reason": "maxDuration", "error": error }),
+            };
+            let _ = window.emit("recording-stopped", payload);
+            return;
+        }
+    });
+}
+
+/// Start screen recording
+/// With `max_duration_secs` the recording stops itself after that much recorded
+/// time and emits `recording-stopped`; otherwise it runs until stop_recording
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_screen_recording(
+    output_path: String,
+    resolution: String,
+    fps: u32,
+    capture_cursor: bool,
+    capture_clicks: bool,
+    audio_device: Option<String>,
+    options: Option<ScreenRecordingOptions>,
+    max_duration_secs: Option<f64>,
+    window: tauri::Window,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<(), Error> {
+    let limit = max_duration_secs
+        .map(|secs| {
+            if secs.is_finite() && secs > 0.0 {
+                Ok(Duration::from_secs_f64(secs))
+            } else {
+                Err(Error::InvalidInput(format!("Maximum recording duration must be positive, got {}", secs)))
+            }
+        })
+        .transpose()?;
+    
+    let source = RecordingSource::Screen {
+        resolution,
+        fps,
+        capture_cursor,
+        capture_clicks,
+        audio_device,
+        options: options.unwrap_or_default(),
+    };
+    let session = start_recording(ffmpeg.executor()?, output_path, source, RecordingType::Screen)?;
+    
+    spawn_ticker(window.clone(), session);
+    if let Some(limit) = limit {
+        spawn_duration_limit(window, session, limit);
+    }
     Ok(())
 }
 
@@ -104,180 +465,287 @@ pub async fn start_webcam_recording(
     resolution: String,
     fps: u32,
     audio_device: Option<String>,
-) -> Result<(), String> {
-    let state = get_state();
-    
-    // Check if already recording
-    {
-        let state_guard = state.lock().unwrap();
-        if state_guard.is_recording {
-            return Err("Recording is already in progress".to_string());
-        }
-    }
-    
-    let executor = FFmpegExecutor::new()?;
+    window: tauri::Window,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<(), Error> {
+    let source = RecordingSource::Webcam {
+        camera_index,
+        resolution,
+        fps,
+        audio_device,
+    };
+    let session = start_recording(ffmpeg.executor()?, output_path, source, RecordingType::Webcam { camera_index })?;
     
-    let audio = audio_device.as_deref();
-    let mut child = executor.start_webcam_recording(
-        &output_path,
+    spawn_ticker(window, session);
+    Ok(())
+}
+
+/// Start recording the screen with the webcam overlaid in a corner, as one file
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_pip_recording(
+    output_path: String,
+    camera_index: u32,
+    resolution: String,
+    fps: u32,
+    capture_cursor: bool,
+    audio_device: Option<String>,
+    pip: Option<PipOptions>,
+    options: Option<ScreenRecordingOptions>,
+    window: tauri::Window,
+    ffmpeg: State<'_, FFmpegState>,
+) -> Result<(), Error> {
+    let source = RecordingSource::PictureInPicture {
         camera_index,
-        &resolution,
+        resolution,
         fps,
-        audio,
-    )?;
+        capture_cursor,
+        audio_device,
+        pip: pip.unwrap_or_default(),
+        options: options.unwrap_or_default(),
+    };
+    let session = start_recording(ffmpeg.executor()?, output_path, source, RecordingType::PictureInPicture { camera_index })?;
     
-    // Wait a moment to check if process starts successfully
-    std::thread::sleep(Duration::from_millis(500));
+    spawn_ticker(window, session);
+    Ok(())
+}
+
+/// Gracefully stop an FFmpeg recording process
+/// Returns any error FFmpeg reported
+fn stop_process(mut child: Child) -> Option<Error> {
+    let mut error_message = None;
     
-    // Check if process immediately exited (indicates startup failure)
+    // Check if process is still running
     match child.try_wait() {
         Ok(Some(status)) => {
-            // Process exited immediately - capture stderr to see why
-            use std::io::Read;
-            let mut stderr_bytes = Vec::new();
-            if let Some(mut stderr) = child.stderr.take() {
-                let _ = stderr.read_to_end(&mut stderr_bytes);
+            // Process already exited - capture stderr for diagnostics
+            if !status.success() {
+                use std::io::Read;
+                if let Some(mut stderr) = child.stderr.take() {
+                    let mut stderr_output = String::new();
+                    let _ = stderr.read_to_string(&mut stderr_output);
+                    if !stderr_output.is_empty() {
+                        error_message = Some(Error::process("FFmpeg process exited with error", &stderr_output));
+                        eprintln!("FFmpeg stderr on exit:\n{}", stderr_output);
+                    }
+                }
             }
-            let stderr_output = String::from_utf8_lossy(&stderr_bytes);
-            let error_msg = if !stderr_output.is_empty() {
-                format!(
-                    "FFmpeg webcam recording failed to start.\n\nExit status: {:?}\nFull stderr output:\n{}\n\nPossible causes:\n- Camera permission not granted\n- Camera in use by another app\n- Camera not found\n- Invalid camera index",
-                    status, stderr_output
-                )
-            } else {
-                format!("FFmpeg exited immediately with status: {:?}. No stderr output available.", status)
-            };
-            eprintln!("{}", error_msg);
-            return Err(error_msg);
         }
         Ok(None) => {
-            // Process is running - good!
+            // Process still running - gracefully stop it
+            // Step 1: Send 'q' to stdin for graceful quit
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(b"q");
+                let _ = stdin.flush();
+            }
+            
+            // Step 2: Give FFmpeg time to finalize (1 second for webcam)
+            std::thread::sleep(Duration::from_millis(1000));
+            
+            // Step 3: If still running, kill it
+            if let Ok(None) = child.try_wait() {
+                let _ = child.kill();
+            }
+            
+            // Step 4: Wait for completion and capture stderr
+            use std::io::Read;
+            if let Some(mut stderr) = child.stderr.take() {
+                let mut stderr_output = String::new();
+                let _ = stderr.read_to_string(&mut stderr_output);
+                if !stderr_output.is_empty() {
+                    eprintln!("FFmpeg stderr:\n{}", stderr_output);
+                    // Check for common errors and add helpful context
+                    let context = if stderr_output.contains("Device not found") || stderr_output.contains("No such device") {
+                        "Camera not found or not accessible"
+                    } else if stderr_output.contains("Input/output error") {
+                        "Camera I/O error - camera may be in use by another application"
+                    } else {
+                        "FFmpeg recording error"
+                    };
+                    
+                    error_message = Some(match Error::process(context, &stderr_output) {
+                        Error::PermissionDenied(_) => Error::PermissionDenied(format!(
+                            "Camera permission denied. Please grant camera access in System Settings → Privacy & Security → Camera.\n\nFull FFmpeg output:\n{}",
+                            stderr_output
+                        )),
+                        error => error,
+                    });
+                }
+            }
+            
+            let _ = child.wait();
         }
         Err(e) => {
             eprintln!("Error checking process status: {}", e);
         }
     }
     
-    // Store process handle
-    {
-        let mut process_guard = RECORDING_PROCESS.lock().unwrap();
-        *process_guard = Some(child);
-    }
+    error_message
+}
+
+/// Pause recording: FFmpeg stops and the current segment is finalized
+/// Stopping FFmpeg can take a second, so it happens with the state unlocked;
+/// status polls and ticks carry on meanwhile and see the recording as paused
+#[tauri::command]
+pub async fn pause_recording() -> Result<(), Error> {
+    let state = get_state();
+    let child = {
+        let mut state_guard = lock_state(&state);
+        if !state_guard.is_recording {
+            return Err(Error::InvalidInput("No recording in progress".to_string()));
+        }
+        if state_guard.is_paused || state_guard.transitioning {
+            return Err(Error::InvalidInput("Recording is already paused".to_string()));
+        }
+        
+        // Freeze the elapsed time at the end of this segment
+        state_guard.recorded_before = state_guard.elapsed();
+        state_guard.start_time = None;
+        state_guard.is_paused = true;
+        state_guard.transitioning = true;
+        lock_process().take()
+    };
+    let _transition = Transition(&state);
     
-    // Update state
-    {
-        let mut state_guard = state.lock().unwrap();
-        state_guard.is_recording = true;
-        state_guard.start_time = Some(Instant::now());
-        state_guard.output_path = Some(output_path.clone());
-        state_guard.recording_type = RecordingType::Webcam { camera_index };
+    if let Some(child) = child {
+        if let Some(error) = stop_process(child) {
+            eprintln!("Warning while pausing: {}", error);
+        }
     }
     
     Ok(())
 }
 
+/// Resume a paused recording into a new segment
+/// The segment is started with the state unlocked (a busy webcam is retried for a
+/// few seconds); the recording stays paused until FFmpeg is running
+#[tauri::command]
+pub async fn resume_recording(ffmpeg: State<'_, FFmpegState>) -> Result<(), Error> {
+    let state = get_state();
+    let (source, segment) = {
+        let mut state_guard = lock_state(&state);
+        if !state_guard.is_recording {
+            return Err(Error::InvalidInput("No recording in progress".to_string()));
+        }
+        if !state_guard.is_paused || state_guard.transitioning {
+            return Err(Error::InvalidInput("Recording is not paused".to_string()));
+        }
+        
+        let output_path = state_guard
+            .output_path
+            .clone()
+            .ok_or_else(|| Error::ProcessNotRunning("No output path found".to_string()))?;
+        let source = state_guard
+            .source
+            .clone()
+            .ok_or_else(|| Error::ProcessNotRunning("Recording settings not found".to_string()))?;
+        let segment = segment_path(&output_path, state_guard.segments.len());
+        state_guard.transitioning = true;
+        (source, segment)
+    };
+    let _transition = Transition(&state);
+    
+    let child = start_segment(ffmpeg.executor()?, &source, &segment)?;
+    
+    *lock_process() = Some(child);
+    let mut state_guard = lock_state(&state);
+    state_guard.segments.push(segment);
+    state_guard.start_time = Some(Instant::now());
+    state_guard.is_paused = false;
+    
+    Ok(())
+}
+
+/// Join recorded segments into the output file and remove them
+/// Segments that are missing or empty (e.g. paused right after starting) are skipped
+fn finalize_segments(executor: &FFmpegExecutor, segments: &[String], output_path: &str) -> Result<(), Error> {
+    let recorded: Vec<String> = segments
+        .iter()
+        .filter(|path| std::fs::metadata(path).map(|m| m.len() > 0).unwrap_or(false))
+        .cloned()
+        .collect();
+    
+    let result = match recorded.as_slice() {
+        [] => Ok(()),
+        [only] => std::fs::rename(only, output_path)
+            .map_err(Error::io("Failed to move recording into place")),
+        _ => executor.concat_files(&recorded, output_path),
+    };
+    
+    // Keep the segments if joining failed so the footage isn't lost
+    if result.is_ok() {
+        for path in segments {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+    result
+}
+
 /// Stop recording gracefully
 #[tauri::command]
-pub async fn stop_recording() -> Result<String, String> {
+pub async fn stop_recording(ffmpeg: State<'_, FFmpegState>) -> Result<String, Error> {
+    finish_recording(ffmpeg.executor()?)
+}
+
+/// Stop FFmpeg and join the segments into the output file
+/// Shared by stop_recording and the duration limit watcher
+fn finish_recording(executor: &FFmpegExecutor) -> Result<String, Error> {
     let state = get_state();
     
-    // Get output path before stopping
-    let output_path = {
-        let state_guard = state.lock().unwrap();
+    // Claim the recording and get its output path before stopping, so a
+    // concurrent stop (manual or automatic) can't finish it twice
+    // A pause or resume in flight is waited out so its segment is complete
+    let (output_path, segments, is_paused) = loop {
+        let mut state_guard = lock_state(&state);
         if !state_guard.is_recording {
-            return Err("No recording in progress".to_string());
+            return Err(Error::InvalidInput("No recording in progress".to_string()));
+        }
+        if state_guard.transitioning {
+            drop(state_guard);
+            std::thread::sleep(TRANSITION_POLL);
+            continue;
         }
-        state_guard.output_path.clone()
+        state_guard.is_recording = false;
+        break (state_guard.output_path.clone(), state_guard.segments.clone(), state_guard.is_paused);
     };
     
     // Gracefully stop FFmpeg process and capture any errors
-    let mut process_guard = RECORDING_PROCESS.lock().unwrap();
+    // A paused recording has no process running
     let mut error_message = None;
-    
-    if let Some(mut child) = process_guard.take() {
-        // Check if process is still running
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                // Process already exited - capture stderr for diagnostics
-                if !status.success() {
-                    use std::io::Read;
-                    if let Some(mut stderr) = child.stderr.take() {
-                        let mut stderr_output = String::new();
-                        let _ = stderr.read_to_string(&mut stderr_output);
-                        if !stderr_output.is_empty() {
-                            error_message = Some(format!("FFmpeg process exited with error: {}", stderr_output));
-                            eprintln!("FFmpeg stderr on exit:\n{}", stderr_output);
-                        }
-                    }
-                }
-            }
-            Ok(None) => {
-                // Process still running - gracefully stop it
-                // Step 1: Send 'q' to stdin for graceful quit
-                if let Some(mut stdin) = child.stdin.take() {
-                    let _ = stdin.write_all(b"q");
-                    let _ = stdin.flush();
-                }
-                
-                // Step 2: Give FFmpeg time to finalize (1 second for webcam)
-                std::thread::sleep(Duration::from_millis(1000));
-                
-                // Step 3: If still running, kill it
-                if let Ok(None) = child.try_wait() {
-                    let _ = child.kill();
-                }
-                
-                // Step 4: Wait for completion and capture stderr
-                use std::io::Read;
-                if let Some(mut stderr) = child.stderr.take() {
-                    let mut stderr_output = String::new();
-                    let _ = stderr.read_to_string(&mut stderr_output);
-                    if !stderr_output.is_empty() {
-                        eprintln!("FFmpeg stderr:\n{}", stderr_output);
-                        // Build detailed error message with full context
-                        let mut detailed_error = format!("FFmpeg recording error:\n{}", stderr_output);
-                        
-                        // Check for common errors and add helpful context
-                        if stderr_output.contains("Permission denied") || stderr_output.contains("No permission") {
-                            detailed_error = format!("Camera permission denied.\n\nFull FFmpeg output:\n{}\n\nPlease grant camera access in System Settings → Privacy & Security → Camera.", stderr_output);
-                        } else if stderr_output.contains("Device not found") || stderr_output.contains("No such device") {
-                            detailed_error = format!("Camera not found or not accessible.\n\nFull FFmpeg output:\n{}", stderr_output);
-                        } else if stderr_output.contains("Input/output error") {
-                            detailed_error = format!("Camera I/O error - camera may be in use by another application.\n\nFull FFmpeg output:\n{}", stderr_output);
-                        }
-                        
-                        error_message = Some(detailed_error);
-                    }
-                }
-                
-                let _ = child.wait();
-            }
-            Err(e) => {
-                eprintln!("Error checking process status: {}", e);
-            }
-        }
-    } else {
-        error_message = Some("Recording process not found".to_string());
+    if !is_paused {
+        let child = lock_process().take();
+        error_message = match child {
+            Some(child) => stop_process(child),
+            None => Some(Error::ProcessNotRunning("Recording process not found".to_string())),
+        };
     }
     
     // Update state
     {
-        let mut state_guard = state.lock().unwrap();
-        state_guard.is_recording = false;
+        let mut state_guard = lock_state(&state);
+        state_guard.is_paused = false;
         state_guard.start_time = None;
+        state_guard.source = None;
     }
     
-    let output = output_path.ok_or("No output path found".to_string())?;
+    let output = output_path.ok_or_else(|| Error::ProcessNotRunning("No output path found".to_string()))?;
+    finalize_segments(executor, &segments, &output)?;
     
     // Check if output file exists and has content
     if let Ok(metadata) = std::fs::metadata(&output) {
         if metadata.len() == 0 {
-            let msg = error_message.unwrap_or_else(|| "Recording produced an empty file. Camera may not have been accessed.".to_string());
-            return Err(msg);
+            let error = error_message.unwrap_or_else(|| Error::ProcessFailed {
+                context: "Recording produced an empty file. Camera may not have been accessed.".to_string(),
+                stderr: String::new(),
+            });
+            return Err(error);
         }
     } else {
-        let msg = error_message.unwrap_or_else(|| format!("Recording file not found at: {}", output));
-        return Err(msg);
+        let error = error_message.unwrap_or_else(|| Error::ProcessFailed {
+            context: format!("Recording file not found at: {}", output),
+            stderr: String::new(),
+        });
+        return Err(error);
     }
     
     // Return error message if we have one, but file exists and has content
@@ -290,26 +758,74 @@ pub async fn stop_recording() -> Result<String, String> {
 
 /// Get current recording status
 #[tauri::command]
-pub async fn get_recording_status() -> Result<serde_json::Value, String> {
+pub async fn get_recording_status() -> Result<serde_json::Value, Error> {
     let state = get_state();
-    let state_guard = state.lock().unwrap();
+    let state_guard = lock_state(&state);
     
-    let elapsed = state_guard.start_time
-        .map(|start| start.elapsed().as_secs_f64())
-        .unwrap_or(0.0);
+    // Paused time isn't counted, so elapsed holds still while paused
+    let elapsed = state_guard.elapsed().as_secs_f64();
+    
+    // FFmpeg only exits on its own while recording if it failed (device unplugged,
+    // disk full); the UI stops the recording to salvage what was written
+    let crashed = state_guard.is_recording
+        && !state_guard.is_paused
+        && lock_process()
+            .as_mut()
+            .is_some_and(|child| matches!(child.try_wait(), Ok(Some(_))));
     
     let recording_type_json = match &state_guard.recording_type {
         RecordingType::Screen => serde_json::json!("screen"),
         RecordingType::Webcam { camera_index } => {
             serde_json::json!({"type": "webcam", "cameraIndex": camera_index})
         }
+        RecordingType::PictureInPicture { camera_index } => {
+            serde_json::json!({"type": "pictureInPicture", "cameraIndex": camera_index})
+        }
     };
 
     Ok(serde_json::json!({
         "isRecording": state_guard.is_recording,
+        "isPaused": state_guard.is_paused,
+        "crashed": crashed,
         "elapsed": elapsed,
         "outputPath": state_guard.output_path,
         "recordingType": recording_type_json
     }))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn recovers_from_a_panic_holding_the_recording_locks() {
+        // Panic while claiming a recording that never got an FFmpeg process,
+        // holding every recording lock
+        let _ = std::thread::spawn(|| {
+            let mut slot = RECORDING_STATE.lock().unwrap_or_else(PoisonError::into_inner);
+            let state = Arc::new(Mutex::new(RecordingState::default()));
+            *slot = Some(Arc::clone(&state));
+            let mut state_guard = state.lock().unwrap();
+            state_guard.is_recording = true;
+            let _process = lock_process();
+            panic!("recording thread failed");
+        })
+        .join();
+        assert!(RECORDING_STATE.is_poisoned());
+        assert!(RECORDING_PROCESS.is_poisoned());
+
+        // The stale claim is reset, so a new recording gets as far as starting FFmpeg
+        let missing = std::env::temp_dir().join(format!("missing-ffmpeg-{}", uuid::Uuid::new_v4()));
+        let executor = FFmpegExecutor::with_paths(missing.clone(), missing);
+        let output_path: PathBuf = std::env::temp_dir().join("poisoned-recording.mp4");
+        let result = start_recording(
+            &executor,
+            output_path.to_string_lossy().into_owned(),
+            RecordingSource::Webcam { camera_index: 0, resolution: "source".to_string(), fps: 30, audio_device: None },
+            RecordingType::Webcam { camera_index: 0 },
+        );
+        assert!(matches!(result, Err(Error::Io { .. })), "{:?}", result.err());
+        assert!(!lock_state(&get_state()).is_recording);
+    }
+}
@@ -7,6 +7,14 @@ use serde::{Deserialize, Serialize};
 use std::path::Path;
 use reqwest::multipart;
 use chrono::Utc;
+use crate::error::Error;
+use crate::ffmpeg::Chapter;
+
+/// Largest audio file the Whisper API accepts in one request
+pub const WHISPER_MAX_UPLOAD_BYTES: u64 = 25 * 1024 * 1024;
+
+/// Longest part of a response body quoted in an error message
+const MAX_ERROR_BODY_CHARS: usize = 500;
 
 // Public data structures
 
@@ -27,12 +35,23 @@ pub struct TranscriptWord {
     pub confidence: Option<f64>,
 }
 
+/// Stretch of audio left out of a transcript because the API failed on its chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedRange {
+    pub start: f64,
+    pub end: f64,
+    pub message: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transcript {
     pub id: String,
     #[serde(rename = "clipId")]
     pub clip_id: String,
     pub language: String,
+    /// Language of the audio when it was translated to English
+    #[serde(rename = "sourceLanguage", default)]
+    pub source_language: Option<String>,
     pub segments: Vec<TranscriptSegment>,
     pub words: Vec<TranscriptWord>,
     #[serde(rename = "fullText")]
@@ -41,6 +60,9 @@ pub struct Transcript {
     #[serde(rename = "createdAt")]
     pub created_at: String,
     pub hashtags: Option<Vec<String>>,
+    /// Chunks of long audio that couldn't be transcribed; the rest of the transcript is kept
+    #[serde(rename = "failedRanges", default)]
+    pub failed_ranges: Vec<FailedRange>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,30 +81,116 @@ pub struct TranscriptionConfig {
     #[serde(rename = "responseFormat")]
     pub response_format: String, // "verbose_json"
     pub temperature: f64,
+    pub organization: Option<String>, // OpenAI-Organization header for org-scoped keys
+    pub project: Option<String>,      // OpenAI-Project header for project billing
+    #[serde(default)]
+    pub translate: bool, // Translate to English instead of transcribing as spoken
+    #[serde(rename = "wordTimestamps", default)]
+    pub word_timestamps: bool, // Ask for word-level timings (verbose_json only)
+}
+
+/// One clip to transcribe in a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipSpec {
+    #[serde(rename = "clipId")]
+    pub clip_id: String,
+    #[serde(rename = "filePath")]
+    pub file_path: String,
+    #[serde(rename = "trimStart")]
+    pub trim_start: f64,
+    pub duration: f64,
+}
+
+/// Outcome for one clip of a batch: its transcript, or the error that stopped it
+#[derive(Debug, Serialize)]
+pub struct ClipTranscription {
+    #[serde(rename = "clipId")]
+    pub clip_id: String,
+    pub transcript: Option<Transcript>,
+    pub error: Option<Error>,
+}
+
+/// Result of checking an API key against the OpenAI API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status")]
+pub enum ApiKeyStatus {
+    #[serde(rename = "valid")]
+    Valid,
+    #[serde(rename = "invalid")]
+    Invalid { message: String },
+    #[serde(rename = "networkError")]
+    NetworkError { message: String },
+}
+
+/// How the API key is sent to the server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum AuthMode {
+    /// `Authorization: Bearer <key>` (OpenAI and most compatible servers)
+    #[default]
+    #[serde(rename = "bearer")]
+    Bearer,
+    /// `api-key: <key>` (Azure OpenAI)
+    #[serde(rename = "apiKey")]
+    ApiKey,
+}
+
+/// Azure OpenAI deployment to send audio to
+/// Azure serves Whisper from a named deployment under the resource endpoint and
+/// versions its API with an `api-version` query parameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AzureDeployment {
+    /// Deployment name chosen when the Whisper model was deployed
+    pub deployment: String,
+    /// e.g. "2024-06-01"
+    #[serde(rename = "apiVersion")]
+    pub api_version: String,
+}
+
+/// How finely subtitle exports are split into cues
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum CaptionGranularity {
+    /// One cue per transcript segment
+    #[default]
+    #[serde(rename = "segment")]
+    Segment,
+    /// One cue per word, for word-by-word (karaoke-style) captions
+    #[serde(rename = "word")]
+    Word,
 }
 
 // Internal API response structures
 
+// Fields that don't affect the transcript default so a partially-formed
+// response can still be recovered by the lenient parser
+
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct WhisperResponse {
+    #[serde(default)]
     task: String,
     language: String,
     duration: f64,
     text: String,
+    #[serde(default)]
     segments: Vec<WhisperSegment>,
     words: Option<Vec<WhisperWord>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct WhisperSegment {
+    #[serde(default)]
     id: i32,
+    #[serde(default)]
     seek: i32,
     start: f64,
     end: f64,
     text: String,
+    #[serde(default)]
     temperature: f64,
-    avg_logprob: f64,
+    #[serde(default)]
+    avg_logprob: Option<f64>,
+    #[serde(default)]
     compression_ratio: f64,
+    #[serde(default)]
     no_speech_prob: f64,
 }
 
@@ -95,10 +203,15 @@ struct WhisperWord {
 
 // OpenAI API Client
 
+#[derive(Clone)]
 pub struct OpenAIClient {
     api_key: String,
     client: reqwest::Client,
     base_url: String,
+    auth_mode: AuthMode,
+    azure: Option<AzureDeployment>,
+    organization: Option<String>,
+    project: Option<String>,
 }
 
 impl OpenAIClient {
@@ -107,18 +220,130 @@ impl OpenAIClient {
             api_key,
             client: reqwest::Client::new(),
             base_url: "https://api.openai.com/v1".to_string(),
+            auth_mode: AuthMode::Bearer,
+            azure: None,
+            organization: None,
+            project: None,
+        }
+    }
+
+    /// Create a client for an OpenAI-compatible endpoint other than api.openai.com
+    pub fn with_base_url(api_key: String, base_url: String) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            ..Self::new(api_key)
         }
     }
 
+    /// Send the key the way a non-OpenAI server expects (e.g. Azure's `api-key` header)
+    pub fn with_auth_mode(mut self, auth_mode: AuthMode) -> Self {
+        self.auth_mode = auth_mode;
+        self
+    }
+
+    /// Route requests through an Azure OpenAI deployment; the base URL is then the
+    /// resource endpoint, e.g. `https://<resource>.openai.azure.com`
+    pub fn with_azure_deployment(mut self, azure: AzureDeployment) -> Self {
+        self.azure = Some(azure);
+        self
+    }
+
+    /// Bill requests to an organization and/or project (needed by some org-scoped keys)
+    pub fn with_organization(mut self, organization: Option<String>, project: Option<String>) -> Self {
+        self.organization = organization;
+        self.project = project;
+        self
+    }
+
+    /// URL of an audio endpoint ("transcriptions" or "translations")
+    fn audio_url(&self, endpoint: &str) -> Result<reqwest::Url, Error> {
+        match &self.azure {
+            Some(azure) => self.endpoint_url(&["openai", "deployments", &azure.deployment, "audio", endpoint]),
+            None => self.endpoint_url(&["audio", endpoint]),
+        }
+    }
+
+    /// URL of the model list, used to check the key
+    fn models_url(&self) -> Result<reqwest::Url, Error> {
+        match &self.azure {
+            Some(_) => self.endpoint_url(&["openai", "models"]),
+            None => self.endpoint_url(&["models"]),
+        }
+    }
+
+    /// Base URL with `segments` appended as escaped path segments, plus Azure's api-version
+    fn endpoint_url(&self, segments: &[&str]) -> Result<reqwest::Url, Error> {
+        let invalid = || Error::InvalidInput(format!("Invalid API base URL: {}", self.base_url));
+        let mut url = reqwest::Url::parse(&self.base_url).map_err(|_| invalid())?;
+        url.path_segments_mut()
+            .map_err(|_| invalid())?
+            .pop_if_empty()
+            .extend(segments);
+        if let Some(azure) = &self.azure {
+            url.query_pairs_mut().append_pair("api-version", &azure.api_version);
+        }
+        Ok(url)
+    }
+
+    /// Attach the API key, organization and project to a request
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let mut request = match self.auth_mode {
+            AuthMode::Bearer => request.header("Authorization", format!("Bearer {}", self.api_key)),
+            AuthMode::ApiKey => request.header("api-key", &self.api_key),
+        };
+        if let Some(organization) = &self.organization {
+            request = request.header("OpenAI-Organization", organization);
+        }
+        if let Some(project) = &self.project {
+            request = request.header("OpenAI-Project", project);
+        }
+        request
+    }
+
+    /// Verify the API key with a cheap authenticated request (list models)
+    /// Network failures are reported separately from rejected keys
+    pub async fn verify_key(&self) -> Result<ApiKeyStatus, Error> {
+        let response = match self
+            .authorize(self.client.get(self.models_url()?))
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(ApiKeyStatus::NetworkError {
+                    message: format!("API request failed: {}", e),
+                })
+            }
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(ApiKeyStatus::Valid);
+        }
+
+        let body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Ok(ApiKeyStatus::Invalid {
+                message: format!("API key rejected ({}): {}", status, api_error_message(&body)),
+            });
+        }
+
+        Err(Error::Api(format!("API error {}: {}", status, api_error_message(&body))))
+    }
+
     pub async fn transcribe(
         &self,
         audio_path: &Path,
         config: &TranscriptionConfig,
-    ) -> Result<WhisperResponse, String> {
+    ) -> Result<WhisperResponse, Error> {
         // Read audio file
         let file_bytes = tokio::fs::read(audio_path)
             .await
-            .map_err(|e| format!("Failed to read audio file: {}", e))?;
+            .map_err(Error::io("Failed to read audio file"))?;
 
         let file_name = audio_path
             .file_name()
@@ -129,7 +354,10 @@ impl OpenAIClient {
         let file_part = multipart::Part::bytes(file_bytes)
             .file_name(file_name.to_string())
             .mime_str("audio/mpeg")
-            .map_err(|e| format!("Failed to create file part: {}", e))?;
+            .map_err(|e| Error::Api(format!("Failed to create file part: {}", e)))?;
+
+        // Translations always produce English and don't accept a language hint
+        let endpoint = if config.translate { "translations" } else { "transcriptions" };
 
         let mut form = multipart::Form::new()
             .part("file", file_part)
@@ -137,19 +365,25 @@ impl OpenAIClient {
             .text("response_format", config.response_format.clone())
             .text("temperature", config.temperature.to_string());
 
-        if let Some(lang) = &config.language {
+        if let Some(lang) = config.language.as_ref().filter(|_| !config.translate) {
             form = form.text("language", lang.clone());
         }
 
+        // Word timings are only returned when asked for, and asking replaces the default
+        // segment timings, so both are requested. Translations don't support either
+        if config.word_timestamps && !config.translate {
+            form = form
+                .text("timestamp_granularities[]", "word")
+                .text("timestamp_granularities[]", "segment");
+        }
+
         // Make API request
         let response = self
-            .client
-            .post(format!("{}/audio/transcriptions", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
+            .authorize(self.client.post(self.audio_url(endpoint)?))
             .multipart(form)
             .send()
             .await
-            .map_err(|e| format!("API request failed: {}", e))?;
+            .map_err(|e| Error::Api(format!("API request failed: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -157,24 +391,191 @@ impl OpenAIClient {
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(format!("API error {}: {}", status, body));
+            return Err(Error::Api(format!("API error {}: {}", status, api_error_message(&body))));
         }
 
-        let whisper_response: WhisperResponse = response
-            .json()
+        // Read the raw body first so it can be recovered if parsing fails
+        let body = response
+            .text()
             .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+            .map_err(|e| Error::Api(format!("Failed to read response: {}", e)))?;
+
+        let mut parsed = parse_whisper_response(&body)?;
+        // Some compatible servers leave out `task`; it decides the transcript language
+        if config.translate {
+            parsed.task = "translate".to_string();
+        }
+        Ok(parsed)
+    }
+}
+
+// Response parsing helpers
+
+/// Readable message from an API error body: OpenAI's `error.message` when it's there,
+/// otherwise the start of the body
+fn api_error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| json["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| truncate_body(body))
+}
+
+/// The first MAX_ERROR_BODY_CHARS characters of a response body
+fn truncate_body(body: &str) -> String {
+    let body = body.trim();
+    match body.char_indices().nth(MAX_ERROR_BODY_CHARS) {
+        Some((cut, _)) => format!("{}... ({} bytes total)", &body[..cut], body.len()),
+        None => body.to_string(),
+    }
+}
+
+/// Parse a Whisper JSON body, retrying with a lenient parse before giving up
+/// If both fail, the raw body is saved to a temp file so the transcription text
+/// isn't lost; the error quotes it in full only when it couldn't be saved
+fn parse_whisper_response(body: &str) -> Result<WhisperResponse, Error> {
+    let strict_error = match serde_json::from_str::<WhisperResponse>(body) {
+        Ok(parsed) => return Ok(parsed),
+        Err(e) => e,
+    };
+
+    if let Some(parsed) = lenient_parse(body) {
+        eprintln!("Warning: Whisper response was malformed ({}), recovered with lenient parse", strict_error);
+        return Ok(parsed);
+    }
+
+    let raw_path = std::env::temp_dir().join(format!("whisper_response_{}.json", uuid::Uuid::new_v4()));
+    let (saved_note, quoted) = match std::fs::write(&raw_path, body) {
+        Ok(()) => (format!(" (saved to {})", raw_path.display()), truncate_body(body)),
+        Err(_) => (String::new(), body.to_string()),
+    };
+
+    Err(Error::Api(format!(
+        "Failed to parse response: {}\nRaw response{}:\n{}",
+        strict_error, saved_note, quoted
+    )))
+}
+
+/// Attempt to repair common JSON damage (trailing commas, unclosed arrays/objects,
+/// trailing garbage after the document) and parse again
+fn lenient_parse(body: &str) -> Option<WhisperResponse> {
+    let repaired = repair_json(body.trim());
+
+    // Take the first complete JSON value, ignoring anything after it
+    let value = serde_json::Deserializer::from_str(&repaired)
+        .into_iter::<serde_json::Value>()
+        .next()?
+        .ok()?;
+
+    serde_json::from_value(value).ok()
+}
+
+/// Remove trailing commas and close any arrays/objects left open at the end of the text
+fn repair_json(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut repaired = String::with_capacity(text.len());
+    let mut open_brackets = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            repaired.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' => open_brackets.push('}'),
+            '[' => open_brackets.push(']'),
+            '}' | ']' => {
+                open_brackets.pop();
+            }
+            ',' => {
+                // Drop commas that are directly followed by a closing bracket or the end of input
+                let next = chars[i + 1..].iter().find(|ch| !ch.is_whitespace());
+                if matches!(next, None | Some('}') | Some(']')) {
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        repaired.push(c);
+    }
+
+    if in_string {
+        repaired.push('"');
+    }
+    while let Some(closer) = open_brackets.pop() {
+        repaired.push(closer);
+    }
+
+    repaired
+}
+
+/// Join the responses for consecutive chunks of one audio file
+/// Each chunk comes with its start time in the full audio, and its segment and word
+/// times are shifted by that so the result reads as a single transcription
+pub(crate) fn stitch_whisper_responses(chunks: Vec<(f64, WhisperResponse)>) -> Option<WhisperResponse> {
+    let mut chunks = chunks.into_iter();
+    let (first_offset, mut stitched) = chunks.next()?;
+    shift_times(&mut stitched, first_offset);
+    stitched.duration += first_offset;
+
+    for (offset, mut chunk) in chunks {
+        shift_times(&mut chunk, offset);
+        stitched.duration = stitched.duration.max(offset + chunk.duration);
+
+        let next_id = stitched.segments.len() as i32;
+        for (i, segment) in chunk.segments.iter_mut().enumerate() {
+            segment.id = next_id + i as i32;
+        }
+        stitched.segments.extend(chunk.segments);
+
+        if let Some(words) = chunk.words {
+            stitched.words.get_or_insert_with(Vec::new).extend(words);
+        }
+
+        let text = chunk.text.trim();
+        if !text.is_empty() {
+            if !stitched.text.is_empty() {
+                stitched.text.push(' ');
+            }
+            stitched.text.push_str(text);
+        }
+    }
 
-        Ok(whisper_response)
+    Some(stitched)
+}
+
+/// Move every segment and word of a response later by `offset` seconds
+fn shift_times(response: &mut WhisperResponse, offset: f64) {
+    for segment in &mut response.segments {
+        segment.start += offset;
+        segment.end += offset;
+    }
+    for word in response.words.iter_mut().flatten() {
+        word.start += offset;
+        word.end += offset;
     }
 }
 
 // Export helper functions
 
 /// Convert WhisperResponse to our Transcript format
+/// Whisper times are relative to the audio it was sent; `offset` (seconds) is added
+/// to every segment, word and failed range so they line up with the source media instead
 pub fn whisper_to_transcript(
     whisper: WhisperResponse,
     clip_id: String,
+    offset: f64,
+    failed_ranges: Vec<FailedRange>,
 ) -> Transcript {
     let segments: Vec<TranscriptSegment> = whisper
         .segments
@@ -182,9 +583,9 @@ pub fn whisper_to_transcript(
         .map(|s| TranscriptSegment {
             id: uuid::Uuid::new_v4().to_string(),
             text: s.text.trim().to_string(),
-            start: s.start,
-            end: s.end,
-            confidence: None, // Whisper doesn't provide per-segment confidence
+            start: s.start + offset,
+            end: s.end + offset,
+            confidence: s.avg_logprob.map(logprob_to_confidence),
         })
         .collect();
 
@@ -194,89 +595,325 @@ pub fn whisper_to_transcript(
         .iter()
         .map(|w| TranscriptWord {
             word: w.word.clone(),
-            start: w.start,
-            end: w.end,
+            start: w.start + offset,
+            end: w.end + offset,
             confidence: None,
         })
         .collect();
 
+    // A translation is English; `language` is then the detected source language
+    let (language, source_language) = if whisper.task == "translate" {
+        ("en".to_string(), Some(whisper.language))
+    } else {
+        (whisper.language, None)
+    };
+
     Transcript {
         id: uuid::Uuid::new_v4().to_string(),
         clip_id,
-        language: whisper.language,
+        language,
+        source_language,
         hashtags: None, // Hashtags will be generated in the frontend after transcription
         segments,
         words,
         full_text: whisper.text,
         duration: whisper.duration,
         created_at: Utc::now().to_rfc3339(),
+        failed_ranges: failed_ranges
+            .into_iter()
+            .map(|range| FailedRange { start: range.start + offset, end: range.end + offset, ..range })
+            .collect(),
+    }
+}
+
+/// Map a segment's average token log-probability to a 0-1 confidence
+fn logprob_to_confidence(avg_logprob: f64) -> f64 {
+    avg_logprob.exp().clamp(0.0, 1.0)
+}
+
+/// Derive chapter markers from pauses in the transcript
+/// A new chapter starts at the first segment following a silence of at least `min_pause` seconds
+pub fn chapters_from_pauses(transcript: &Transcript, min_pause: f64) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = Vec::new();
+    let mut previous_end: Option<f64> = None;
+
+    for segment in &transcript.segments {
+        let starts_chapter = match previous_end {
+            None => true,
+            Some(end) => segment.start - end >= min_pause,
+        };
+
+        if starts_chapter {
+            let start = if chapters.is_empty() { 0.0 } else { segment.start };
+            if let Some(last) = chapters.last_mut() {
+                last.end = start;
+            }
+            chapters.push(Chapter {
+                title: chapter_title(&segment.text, chapters.len() + 1),
+                start,
+                end: segment.end,
+            });
+        }
+
+        previous_end = Some(segment.end);
     }
+
+    // Last chapter runs to the end of the audio
+    if let Some(last) = chapters.last_mut() {
+        last.end = last.end.max(transcript.duration);
+    }
+
+    chapters
+}
+
+/// Build a chapter title from the opening words of its first segment
+fn chapter_title(text: &str, number: usize) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return format!("Chapter {}", number);
+    }
+    let mut title = words.iter().take(6).copied().collect::<Vec<_>>().join(" ");
+    if words.len() > 6 {
+        title.push_str("...");
+    }
+    title
 }
 
 /// Export transcript to TXT format
-pub async fn export_as_txt(transcript: &Transcript, path: &str) -> Result<(), String> {
+pub async fn export_as_txt(transcript: &Transcript, path: &str) -> Result<(), Error> {
     tokio::fs::write(path, &transcript.full_text)
         .await
-        .map_err(|e| format!("Failed to write file: {}", e))
+        .map_err(Error::io("Failed to write file"))
+}
+
+/// Subtitle cues as (start, end, text)
+/// Word granularity falls back to segments when the transcript has no word timings
+fn caption_cues(transcript: &Transcript, granularity: CaptionGranularity) -> Vec<(f64, f64, &str)> {
+    match granularity {
+        CaptionGranularity::Word if !transcript.words.is_empty() => transcript
+            .words
+            .iter()
+            .map(|w| (w.start, w.end, w.word.trim()))
+            .filter(|(_, _, word)| !word.is_empty())
+            .collect(),
+        _ => transcript
+            .segments
+            .iter()
+            .map(|s| (s.start, s.end, s.text.as_str()))
+            .collect(),
+    }
 }
 
 /// Export transcript to SRT format
-pub async fn export_as_srt(transcript: &Transcript, path: &str) -> Result<(), String> {
+pub async fn export_as_srt(
+    transcript: &Transcript,
+    path: &str,
+    granularity: CaptionGranularity,
+) -> Result<(), Error> {
     let mut srt = String::new();
-    for (i, segment) in transcript.segments.iter().enumerate() {
+    for (i, (start, end, text)) in caption_cues(transcript, granularity).into_iter().enumerate() {
         srt.push_str(&format!("{}\n", i + 1));
         srt.push_str(&format!(
             "{} --> {}\n",
-            format_srt_time(segment.start),
-            format_srt_time(segment.end)
+            format_srt_time(start),
+            format_srt_time(end)
         ));
-        srt.push_str(&format!("{}\n\n", segment.text));
+        srt.push_str(&format!("{}\n\n", text));
     }
     tokio::fs::write(path, srt)
         .await
-        .map_err(|e| format!("Failed to write file: {}", e))
+        .map_err(Error::io("Failed to write file"))
 }
 
 /// Export transcript to VTT format
-pub async fn export_as_vtt(transcript: &Transcript, path: &str) -> Result<(), String> {
+pub async fn export_as_vtt(
+    transcript: &Transcript,
+    path: &str,
+    granularity: CaptionGranularity,
+) -> Result<(), Error> {
     let mut vtt = String::from("WEBVTT\n\n");
-    for segment in &transcript.segments {
+    for (start, end, text) in caption_cues(transcript, granularity) {
         vtt.push_str(&format!(
             "{} --> {}\n",
-            format_vtt_time(segment.start),
-            format_vtt_time(segment.end)
+            format_vtt_time(start),
+            format_vtt_time(end)
         ));
-        vtt.push_str(&format!("{}\n\n", segment.text));
+        vtt.push_str(&format!("{}\n\n", text));
     }
     tokio::fs::write(path, vtt)
         .await
-        .map_err(|e| format!("Failed to write file: {}", e))
+        .map_err(Error::io("Failed to write file"))
 }
 
 /// Export transcript to JSON format
-pub async fn export_as_json(transcript: &Transcript, path: &str) -> Result<(), String> {
+pub async fn export_as_json(transcript: &Transcript, path: &str) -> Result<(), Error> {
     let json = serde_json::to_string_pretty(transcript)
-        .map_err(|e| format!("Failed to serialize: {}", e))?;
+        .map_err(|e| Error::io("Failed to serialize transcript")(e.into()))?;
     tokio::fs::write(path, json)
         .await
-        .map_err(|e| format!("Failed to write file: {}", e))
+        .map_err(Error::io("Failed to write file"))
+}
+
+/// Export transcript segments to CSV (`index,start,end,text`, times in seconds)
+pub async fn export_as_csv(transcript: &Transcript, path: &str) -> Result<(), Error> {
+    let rows = transcript
+        .segments
+        .iter()
+        .map(|s| (s.start, s.end, s.text.as_str()));
+    write_csv(path, "text", rows).await
+}
+
+/// Export word timestamps to CSV (`index,start,end,word`)
+pub async fn export_words_as_csv(transcript: &Transcript, path: &str) -> Result<(), Error> {
+    let rows = transcript
+        .words
+        .iter()
+        .map(|w| (w.start, w.end, w.word.trim()));
+    write_csv(path, "word", rows).await
+}
+
+async fn write_csv<'a>(
+    path: &str,
+    text_column: &str,
+    rows: impl Iterator<Item = (f64, f64, &'a str)>,
+) -> Result<(), Error> {
+    let mut csv = format!("index,start,end,{}\n", text_column);
+    for (i, (start, end, text)) in rows.enumerate() {
+        csv.push_str(&format!("{},{:.3},{:.3},{}\n", i + 1, start, end, csv_field(text)));
+    }
+    tokio::fs::write(path, csv)
+        .await
+        .map_err(Error::io("Failed to write file"))
+}
+
+/// Quote a CSV field if it contains a delimiter, quote, or line break
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
 }
 
 // Time formatting helpers
 
 fn format_srt_time(seconds: f64) -> String {
-    let hours = (seconds / 3600.0).floor() as i32;
-    let minutes = ((seconds % 3600.0) / 60.0).floor() as i32;
-    let secs = (seconds % 60.0).floor() as i32;
-    let millis = ((seconds % 1.0) * 1000.0).floor() as i32;
+    let (hours, minutes, secs, millis) = split_time(seconds);
     format!("{:02}:{:02}:{:02},{:03}", hours, minutes, secs, millis)
 }
 
 fn format_vtt_time(seconds: f64) -> String {
-    let hours = (seconds / 3600.0).floor() as i32;
-    let minutes = ((seconds % 3600.0) / 60.0).floor() as i32;
-    let secs = (seconds % 60.0).floor() as i32;
-    let millis = ((seconds % 1.0) * 1000.0).floor() as i32;
+    let (hours, minutes, secs, millis) = split_time(seconds);
     format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, secs, millis)
 }
 
+/// Split seconds into (hours, minutes, seconds, milliseconds), rounded to the nearest millisecond
+/// Rounding the total first lets e.g. 3599.9995 carry over to 01:00:00.000 instead of showing 1000 ms
+fn split_time(seconds: f64) -> (u64, u64, u64, u64) {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    (
+        total_ms / 3_600_000,
+        total_ms / 60_000 % 60,
+        total_ms / 1000 % 60,
+        total_ms % 1000,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// verbose_json body for a 4-second clip, as returned with word and segment granularities
+    const VERBOSE_JSON: &str = r#"{
+        "task": "transcribe",
+        "language": "english",
+        "duration": 4.0,
+        "text": "Hello world. Thanks.",
+        "segments": [
+            {"id": 0, "seek": 0, "start": 0.0, "end": 1.5, "text": " Hello world.", "avg_logprob": -0.2},
+            {"id": 1, "seek": 0, "start": 2.0, "end": 3.25, "text": " Thanks.", "avg_logprob": -0.4}
+        ],
+        "words": [
+            {"word": "Hello", "start": 0.0, "end": 0.6},
+            {"word": "world", "start": 0.7, "end": 1.5},
+            {"word": "Thanks", "start": 2.0, "end": 3.25}
+        ]
+    }"#;
+
+    #[test]
+    fn transcript_times_are_shifted_by_the_trim_offset() {
+        let whisper = parse_whisper_response(VERBOSE_JSON).unwrap();
+        let failed = vec![FailedRange { start: 3.5, end: 4.0, message: "timeout".to_string() }];
+
+        let transcript = whisper_to_transcript(whisper, "clip".to_string(), 12.5, failed);
+
+        let segments: Vec<(f64, f64)> = transcript.segments.iter().map(|s| (s.start, s.end)).collect();
+        assert_eq!(segments, [(12.5, 14.0), (14.5, 15.75)]);
+        assert_eq!((transcript.words[2].start, transcript.words[2].end), (14.5, 15.75));
+        assert_eq!((transcript.failed_ranges[0].start, transcript.failed_ranges[0].end), (16.0, 16.5));
+    }
+
+    #[test]
+    fn verbose_json_words_become_transcript_words() {
+        let whisper = parse_whisper_response(VERBOSE_JSON).unwrap();
+        let transcript = whisper_to_transcript(whisper, "clip".to_string(), 0.0, Vec::new());
+
+        let words: Vec<(&str, f64, f64)> = transcript.words.iter().map(|w| (w.word.as_str(), w.start, w.end)).collect();
+        assert_eq!(words, [("Hello", 0.0, 0.6), ("world", 0.7, 1.5), ("Thanks", 2.0, 3.25)]);
+        assert_eq!(transcript.segments.len(), 2);
+
+        // Without word granularity the response has no "words" at all
+        let mut segments_only: serde_json::Value = serde_json::from_str(VERBOSE_JSON).unwrap();
+        segments_only.as_object_mut().unwrap().remove("words");
+        let whisper = parse_whisper_response(&segments_only.to_string()).unwrap();
+        assert!(whisper_to_transcript(whisper, "clip".to_string(), 0.0, Vec::new()).words.is_empty());
+    }
+
+    #[test]
+    fn srt_time_carries_rounding_into_the_hour() {
+        assert_eq!(format_srt_time(3599.9995), "01:00:00,000");
+        assert_eq!(format_vtt_time(3599.9995), "01:00:00.000");
+        assert_eq!(format_srt_time(61.25), "00:01:01,250");
+    }
+
+    #[test]
+    fn api_error_message_prefers_json_message() {
+        let body = r#"{"error": {"message": "Invalid file format.", "type": "invalid_request_error"}}"#;
+        assert_eq!(api_error_message(body), "Invalid file format.");
+    }
+
+    #[test]
+    fn api_error_message_truncates_other_bodies() {
+        let body = "<html>".repeat(200);
+        let message = api_error_message(&body);
+        assert!(message.starts_with("<html>"));
+        assert!(message.ends_with("(1200 bytes total)"));
+        assert!(message.len() < body.len());
+    }
+
+    #[test]
+    fn openai_audio_url_appends_to_base_path() {
+        let client = OpenAIClient::new("key".to_string());
+        assert_eq!(
+            client.audio_url("transcriptions").unwrap().as_str(),
+            "https://api.openai.com/v1/audio/transcriptions"
+        );
+    }
+
+    #[test]
+    fn azure_urls_use_deployment_and_api_version() {
+        let client = OpenAIClient::with_base_url("key".to_string(), "https://res.openai.azure.com/".to_string())
+            .with_azure_deployment(AzureDeployment {
+                deployment: "my whisper".to_string(),
+                api_version: "2024-06-01".to_string(),
+            });
+        assert_eq!(
+            client.audio_url("translations").unwrap().as_str(),
+            "https://res.openai.azure.com/openai/deployments/my%20whisper/audio/translations?api-version=2024-06-01"
+        );
+        assert_eq!(
+            client.models_url().unwrap().as_str(),
+            "https://res.openai.azure.com/openai/models?api-version=2024-06-01"
+        );
+    }
+}
@@ -0,0 +1,347 @@
+// whisper.cpp Module
+//
+// Local transcription backend that runs a bundled whisper.cpp binary, so audio
+// never leaves the machine and no API key is needed. Produces the same
+// Transcript structure as the OpenAI backend.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use serde::Deserialize;
+use chrono::Utc;
+use crate::error::Error;
+use crate::transcription::{Transcript, TranscriptSegment, TranscriptWord, TranscriptionConfig};
+
+/// whisper.cpp CLI names, newest first (older Homebrew installs call it `whisper-cpp`)
+const BINARY_NAMES: [&str; 2] = ["whisper-cli", "whisper-cpp"];
+
+/// Shortest time whisper.cpp gets before it's killed, however short the audio
+const MIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Time allowed per second of audio; generous enough for large models on a slow CPU
+const TIMEOUT_PER_AUDIO_SECOND: f64 = 3.0;
+
+// whisper.cpp JSON output (`-ojf`); offsets are in milliseconds
+
+#[derive(Debug, Deserialize)]
+pub struct WhisperCppOutput {
+    result: WhisperCppResult,
+    transcription: Vec<WhisperCppSegment>,
+    /// Set when run with `-tr`; the text is English and `result.language` is the source
+    #[serde(skip)]
+    translated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperCppResult {
+    language: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperCppSegment {
+    offsets: WhisperCppOffsets,
+    text: String,
+    #[serde(default)]
+    tokens: Vec<WhisperCppToken>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperCppToken {
+    text: String,
+    offsets: WhisperCppOffsets,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperCppOffsets {
+    from: i64,
+    to: i64,
+}
+
+#[derive(Clone)]
+pub struct WhisperCpp {
+    binary_path: PathBuf,
+    model_path: PathBuf,
+}
+
+impl WhisperCpp {
+    /// Locate the whisper.cpp binary and a ggml model
+    /// WHISPER_CPP_PATH/WHISPER_MODEL_PATH take precedence; otherwise the same places as
+    /// FFmpeg are searched: production bundle -> development -> system PATH, with models
+    /// in a `models` folder next to the binaries
+    pub fn new() -> Result<Self, Error> {
+        let binary_path = match std::env::var_os("WHISPER_CPP_PATH") {
+            Some(path) => PathBuf::from(path),
+            None => find_binary().ok_or_else(|| {
+                Error::WhisperNotFound(
+                    "whisper.cpp not found. Bundle whisper-cli with the app, install it on your PATH, \
+                     or set WHISPER_CPP_PATH (or provide an OpenAI API key instead)"
+                        .to_string(),
+                )
+            })?,
+        };
+        if !binary_path.is_file() {
+            return Err(Error::WhisperNotFound(format!("whisper.cpp binary not found at {}", binary_path.display())));
+        }
+
+        let model_path = match std::env::var_os("WHISPER_MODEL_PATH") {
+            Some(path) => PathBuf::from(path),
+            None => find_model(&binary_path).ok_or_else(|| {
+                Error::WhisperNotFound(
+                    "No whisper.cpp model found. Put a ggml-*.bin model in binaries/models \
+                     or set WHISPER_MODEL_PATH"
+                        .to_string(),
+                )
+            })?,
+        };
+        if !model_path.is_file() {
+            return Err(Error::WhisperNotFound(format!("whisper.cpp model not found at {}", model_path.display())));
+        }
+
+        Ok(Self { binary_path, model_path })
+    }
+
+    /// Transcribe a 16 kHz mono WAV file that is `duration` seconds long
+    /// whisper.cpp is killed and Error::Timeout returned if it runs far longer than
+    /// audio of that length should take
+    pub async fn transcribe(
+        &self,
+        wav_path: &Path,
+        duration: f64,
+        config: &TranscriptionConfig,
+    ) -> Result<WhisperCppOutput, Error> {
+        // whisper.cpp appends ".json" to the output base name
+        let output_base = std::env::temp_dir().join(format!("whisper_cpp_{}", uuid::Uuid::new_v4()));
+        let json_path = output_base.with_extension("json");
+
+        let timeout = Duration::try_from_secs_f64(duration * TIMEOUT_PER_AUDIO_SECOND)
+            .unwrap_or_default()
+            .max(MIN_TIMEOUT);
+        let output = Command::new(&self.binary_path)
+            .arg("-m").arg(&self.model_path)
+            .arg("-f").arg(wav_path)
+            .args(["-l", config.language.as_deref().unwrap_or("auto")])
+            .args(["-tp", &config.temperature.to_string()])
+            .args(["-ojf", "-np"])
+            .args(config.translate.then_some("-tr"))
+            .arg("-of").arg(&output_base)
+            .kill_on_drop(true)
+            .output();
+        let output = match tokio::time::timeout(timeout, output).await {
+            Ok(output) => output.map_err(Error::io("whisper.cpp execution failed"))?,
+            Err(_) => {
+                let _ = tokio::fs::remove_file(&json_path).await;
+                return Err(Error::Timeout(format!(
+                    "whisper.cpp didn't finish within {}s",
+                    timeout.as_secs()
+                )));
+            }
+        };
+
+        if !output.status.success() {
+            let _ = tokio::fs::remove_file(&json_path).await;
+            return Err(Error::process("whisper.cpp transcription failed", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        let json = tokio::fs::read_to_string(&json_path)
+            .await
+            .map_err(Error::io("Failed to read whisper.cpp output"));
+        let _ = tokio::fs::remove_file(&json_path).await;
+
+        let mut parsed: WhisperCppOutput = serde_json::from_str(&json?)
+            .map_err(|e| Error::process("Failed to parse whisper.cpp output", e.to_string()))?;
+        parsed.translated = config.translate;
+        Ok(parsed)
+    }
+}
+
+/// Folders that may hold bundled binaries, in search order
+fn binary_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(exe_path) = std::env::current_exe() {
+        // Contents/MacOS/app -> Contents/Resources/binaries
+        if let Some(contents) = exe_path.parent().and_then(|p| p.parent()) {
+            dirs.push(contents.join("Resources").join("binaries"));
+        }
+    }
+    if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
+        dirs.push(PathBuf::from(manifest_dir).join("binaries"));
+    }
+    dirs
+}
+
+fn find_binary() -> Option<PathBuf> {
+    let mut bundled = binary_dirs().into_iter().flat_map(|dir| {
+        BINARY_NAMES.map(|name| dir.join(format!("{}{}", name, std::env::consts::EXE_SUFFIX)))
+    });
+    bundled
+        .find(|path| path.is_file())
+        .or_else(|| BINARY_NAMES.iter().find_map(|name| which::which(name).ok()))
+}
+
+/// First ggml model (by name) in a `models` folder next to the binary or in the bundle
+fn find_model(binary_path: &Path) -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = binary_path.parent().map(|dir| dir.join("models")).into_iter().collect();
+    dirs.extend(binary_dirs().into_iter().map(|dir| dir.join("models")));
+
+    dirs.iter().find_map(|dir| {
+        let mut models: Vec<PathBuf> = std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                name.starts_with("ggml-") && name.ends_with(".bin")
+            })
+            .collect();
+        models.sort();
+        models.into_iter().next()
+    })
+}
+
+/// Convert whisper.cpp output to our Transcript format
+/// Like whisper_to_transcript, `offset` is added to every time; `duration` is the
+/// length of the audio that was transcribed
+pub fn whisper_cpp_to_transcript(
+    output: WhisperCppOutput,
+    clip_id: String,
+    offset: f64,
+    duration: f64,
+) -> Transcript {
+    let seconds = |ms: i64| ms as f64 / 1000.0 + offset;
+
+    let segments: Vec<TranscriptSegment> = output
+        .transcription
+        .iter()
+        .map(|s| TranscriptSegment {
+            id: uuid::Uuid::new_v4().to_string(),
+            text: s.text.trim().to_string(),
+            start: seconds(s.offsets.from),
+            end: seconds(s.offsets.to),
+            confidence: None,
+        })
+        .filter(|s| !s.text.is_empty())
+        .collect();
+
+    // Tokens are word pieces; one starting with a space begins a new word.
+    // Special tokens such as "[_BEG_]" carry no text
+    let mut words: Vec<TranscriptWord> = Vec::new();
+    for segment in &output.transcription {
+        let mut starts_word = true;
+        for token in &segment.tokens {
+            if token.text.starts_with("[_") {
+                continue;
+            }
+            match words.last_mut() {
+                Some(word) if !starts_word && !token.text.starts_with(' ') => {
+                    word.word.push_str(&token.text);
+                    word.end = seconds(token.offsets.to);
+                }
+                _ => words.push(TranscriptWord {
+                    word: token.text.trim_start().to_string(),
+                    start: seconds(token.offsets.from),
+                    end: seconds(token.offsets.to),
+                    confidence: None,
+                }),
+            }
+            starts_word = false;
+        }
+    }
+    words.retain(|w| !w.word.trim().is_empty());
+
+    let full_text = segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let (language, source_language) = if output.translated {
+        ("en".to_string(), Some(output.result.language))
+    } else {
+        (output.result.language, None)
+    };
+
+    Transcript {
+        id: uuid::Uuid::new_v4().to_string(),
+        clip_id,
+        language,
+        source_language,
+        hashtags: None,
+        segments,
+        words,
+        full_text,
+        duration,
+        created_at: Utc::now().to_rfc3339(),
+        failed_ranges: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `-ojf` output for 4 seconds of speech, with the model details shortened
+    /// Tokens are word pieces, and each segment's are bracketed by special tokens
+    const FULL_JSON: &str = r#"{
+        "systeminfo": "AVX = 1 | AVX2 = 1 | NEON = 0 | ARM_FMA = 0 | METAL = 0",
+        "model": {"type": "base", "multilingual": true, "vocab": 51865, "mels": 80, "ftype": 1},
+        "params": {"model": "models/ggml-base.bin", "language": "auto", "translate": false},
+        "result": {"language": "en"},
+        "transcription": [
+            {
+                "timestamps": {"from": "00:00:00,000", "to": "00:00:01,500"},
+                "offsets": {"from": 0, "to": 1500},
+                "text": " Hello world, transcribed.",
+                "tokens": [
+                    {"text": "[_BEG_]", "timestamps": {"from": "00:00:00,000", "to": "00:00:00,000"}, "offsets": {"from": 0, "to": 0}, "id": 50364, "p": 0.98, "t_dtw": -1},
+                    {"text": " Hello", "timestamps": {"from": "00:00:00,000", "to": "00:00:00,420"}, "offsets": {"from": 0, "to": 420}, "id": 2425, "p": 0.91, "t_dtw": -1},
+                    {"text": " world", "timestamps": {"from": "00:00:00,420", "to": "00:00:00,800"}, "offsets": {"from": 420, "to": 800}, "id": 1002, "p": 0.95, "t_dtw": -1},
+                    {"text": ",", "timestamps": {"from": "00:00:00,800", "to": "00:00:00,810"}, "offsets": {"from": 800, "to": 810}, "id": 11, "p": 0.74, "t_dtw": -1},
+                    {"text": " trans", "timestamps": {"from": "00:00:00,810", "to": "00:00:01,100"}, "offsets": {"from": 810, "to": 1100}, "id": 1145, "p": 0.88, "t_dtw": -1},
+                    {"text": "cribed", "timestamps": {"from": "00:00:01,100", "to": "00:00:01,450"}, "offsets": {"from": 1100, "to": 1450}, "id": 34008, "p": 0.9, "t_dtw": -1},
+                    {"text": ".", "timestamps": {"from": "00:00:01,450", "to": "00:00:01,500"}, "offsets": {"from": 1450, "to": 1500}, "id": 13, "p": 0.8, "t_dtw": -1},
+                    {"text": "[_TT_75]", "timestamps": {"from": "00:00:01,500", "to": "00:00:01,500"}, "offsets": {"from": 1500, "to": 1500}, "id": 50439, "p": 0.6, "t_dtw": -1}
+                ]
+            },
+            {
+                "timestamps": {"from": "00:00:02,000", "to": "00:00:03,250"},
+                "offsets": {"from": 2000, "to": 3250},
+                "text": " Thanks.",
+                "tokens": [
+                    {"text": "[_BEG_]", "timestamps": {"from": "00:00:02,000", "to": "00:00:02,000"}, "offsets": {"from": 2000, "to": 2000}, "id": 50364, "p": 0.97, "t_dtw": -1},
+                    {"text": "Thanks", "timestamps": {"from": "00:00:02,000", "to": "00:00:03,000"}, "offsets": {"from": 2000, "to": 3000}, "id": 8846, "p": 0.93, "t_dtw": -1},
+                    {"text": ".", "timestamps": {"from": "00:00:03,000", "to": "00:00:03,250"}, "offsets": {"from": 3000, "to": 3250}, "id": 13, "p": 0.85, "t_dtw": -1}
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn full_json_tokens_merge_into_shifted_words() {
+        let output: WhisperCppOutput = serde_json::from_str(FULL_JSON).unwrap();
+        let transcript = whisper_cpp_to_transcript(output, "clip-1".to_string(), 12.5, 4.0);
+
+        let words: Vec<(&str, f64, f64)> = transcript
+            .words
+            .iter()
+            .map(|w| (w.word.as_str(), w.start, w.end))
+            .collect();
+        assert_eq!(
+            words,
+            [
+                ("Hello", 12.5, 12.92),
+                ("world,", 12.92, 13.31),
+                ("transcribed.", 13.31, 14.0),
+                // A segment's first token starts a word even without a leading space
+                ("Thanks.", 14.5, 15.75),
+            ]
+        );
+
+        let segments: Vec<(&str, f64, f64)> = transcript
+            .segments
+            .iter()
+            .map(|s| (s.text.as_str(), s.start, s.end))
+            .collect();
+        assert_eq!(segments, [("Hello world, transcribed.", 12.5, 14.0), ("Thanks.", 14.5, 15.75)]);
+        assert_eq!(transcript.full_text, "Hello world, transcribed. Thanks.");
+        assert_eq!(transcript.language, "en");
+    }
+}
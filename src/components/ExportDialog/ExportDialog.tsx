@@ -92,7 +92,7 @@ export const ExportDialog: React.FC<ExportDialogProps> = ({ isOpen, onClose }) =
         setExportProgress(currentProgress);
       }, 300); // Update every 300ms
       
-      await videoService.exportVideo(allClips, outputPath, resolution, fps, compositionLength);
+      const warnings = await videoService.exportVideo(allClips, outputPath, resolution, fps, compositionLength);
       
       // Clean up interval and set to 100%
       if (progressIntervalRef.current) {
@@ -100,7 +100,11 @@ export const ExportDialog: React.FC<ExportDialogProps> = ({ isOpen, onClose }) =
         progressIntervalRef.current = null;
       }
       setExportProgress(100);
-      alert('Export completed successfully!');
+      alert(
+        warnings.length > 0
+          ? `Export completed with warnings:\n\n${warnings.join('\n')}`
+          : 'Export completed successfully!'
+      );
       onClose();
       
     } catch (error) {
@@ -5,7 +5,7 @@
 import React, { useState, useEffect } from 'react';
 import { useAppStore } from '../../store/appStore';
 import { useMediaStore } from '../../store/mediaStore';
-import { recordingService, CameraInfo } from '../../services/recordingService';
+import { recordingService, CameraInfo, CameraMode } from '../../services/recordingService';
 import { videoService } from '../../services/videoService';
 import { toAppError } from '../../utils/errors';
 
@@ -32,6 +32,8 @@ export const RecordingPanel: React.FC<RecordingPanelProps> = ({ onClose }) => {
   const [cameras, setCameras] = useState<CameraInfo[]>([]);
   const [selectedCameraIndex, setSelectedCameraIndex] = useState<number>(0);
   const [isLoadingCameras, setIsLoadingCameras] = useState<boolean>(false);
+  // Modes of the selected camera; null when unknown, so every option stays available
+  const [cameraModes, setCameraModes] = useState<CameraMode[] | null>(null);
 
   // Load cameras when webcam recording type is selected
   useEffect(() => {
@@ -57,6 +59,32 @@ export const RecordingPanel: React.FC<RecordingPanelProps> = ({ onClose }) => {
     }
   }, [recordingType]);
 
+  // Load the selected camera's modes so only settings it can record are offered
+  useEffect(() => {
+    if (recordingType !== 'webcam' || cameras.length === 0) {
+      setCameraModes(null);
+      return;
+    }
+
+    let cancelled = false;
+    recordingService.getCameraCapabilities(selectedCameraIndex)
+      .then((modes) => {
+        if (!cancelled) setCameraModes(modes);
+      })
+      .catch((error) => {
+        console.error('Failed to load camera modes:', error);
+        if (!cancelled) setCameraModes(null);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [recordingType, cameras, selectedCameraIndex]);
+
+  const supportsSize = (width: number, height: number): boolean =>
+    !cameraModes || cameraModes.some((mode) => mode.width >= width && mode.height >= height);
+  const supportsFps = (rate: number): boolean =>
+    !cameraModes || cameraModes.some((mode) => Math.round(mode.fps) >= rate);
+
   // Timer effect
   useEffect(() => {
     if (!isRecording) {
@@ -68,6 +96,13 @@ export const RecordingPanel: React.FC<RecordingPanelProps> = ({ onClose }) => {
       try {
         const status = await recordingService.getRecordingStatus();
         setRecordingElapsed(status.elapsed);
+        if (status.crashed) {
+          // FFmpeg died without being stopped; stopping keeps what was recorded
+          // and reports the failure
+          clearInterval(interval);
+          handleStopRecording();
+          return;
+        }
         if (!status.isRecording && isRecording) {
           // Recording stopped externally
           setIsRecording(false);
@@ -236,8 +271,8 @@ export const RecordingPanel: React.FC<RecordingPanelProps> = ({ onClose }) => {
                   onChange={(e) => setResolution(e.target.value as '720p' | '1080p' | 'source')}
                   className="w-full px-3 py-2 bg-gray-700 text-white rounded-lg border border-gray-600 focus:border-blue-500 focus:outline-none"
                 >
-                  <option value="720p">720p</option>
-                  <option value="1080p">1080p</option>
+                  <option value="720p" disabled={!supportsSize(1280, 720)}>720p</option>
+                  <option value="1080p" disabled={!supportsSize(1920, 1080)}>1080p</option>
                   <option value="source">Source</option>
                 </select>
               </div>
@@ -252,9 +287,9 @@ export const RecordingPanel: React.FC<RecordingPanelProps> = ({ onClose }) => {
                   onChange={(e) => setFps(Number(e.target.value))}
                   className="w-full px-3 py-2 bg-gray-700 text-white rounded-lg border border-gray-600 focus:border-blue-500 focus:outline-none"
                 >
-                  <option value="24">24</option>
-                  <option value="30">30</option>
-                  <option value="60">60</option>
+                  <option value="24" disabled={!supportsFps(24)}>24</option>
+                  <option value="30" disabled={!supportsFps(30)}>30</option>
+                  <option value="60" disabled={!supportsFps(60)}>60</option>
                 </select>
               </div>
             </div>
@@ -87,6 +87,7 @@ export const HashtagPanel: React.FC = () => {
           model: 'whisper-1',
           responseFormat: 'verbose_json',
           temperature: 0.0,
+          wordTimestamps: true,
         },
         (progress) => {
           setProgress(progress);
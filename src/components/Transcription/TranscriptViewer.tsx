@@ -10,6 +10,9 @@ import { useTranscriptionStore } from '../../store/transcriptionStore';
 import { useTimelineStore } from '../../store/timelineStore';
 import { transcriptionService } from '../../services/transcriptionService';
 
+/** Segments below this confidence are flagged for review */
+const LOW_CONFIDENCE = 0.5;
+
 interface TranscriptViewerProps {
   clipId: string;
 }
@@ -19,6 +22,7 @@ export const TranscriptViewer: React.FC<TranscriptViewerProps> = ({ clipId }) =>
   const transcript = getTranscript(clipId);
   const { playheadPosition, setPlayheadPosition } = useTimelineStore();
   const [searchQuery, setSearchQuery] = useState('');
+  const [wordCues, setWordCues] = useState(false);
   const [activeSegmentId, setActiveSegmentId] = useState<string | null>(null);
   const containerRef = useRef<HTMLDivElement>(null);
 
@@ -55,9 +59,14 @@ export const TranscriptViewer: React.FC<TranscriptViewerProps> = ({ clipId }) =>
     setPlayheadPosition(start);
   };
 
-  const handleExport = async (format: 'txt' | 'srt' | 'vtt' | 'json') => {
+  const handleExport = async (format: 'txt' | 'srt' | 'vtt' | 'json' | 'csv') => {
     try {
-      await transcriptionService.exportTranscript(transcript, format);
+      await transcriptionService.exportTranscript(
+        transcript,
+        format,
+        transcript.words.length > 0,
+        wordCues ? 'word' : 'segment'
+      );
     } catch (error) {
       console.error('Export failed:', error);
     }
@@ -70,6 +79,17 @@ export const TranscriptViewer: React.FC<TranscriptViewerProps> = ({ clipId }) =>
         <div className="flex items-center justify-between mb-3">
           <h3 className="text-lg font-bold">Transcript</h3>
           <div className="flex gap-2">
+            {transcript.words.length > 0 && (
+              <label className="flex items-center gap-1 text-xs text-gray-400 cursor-pointer" title="SRT/VTT with one caption per word">
+                <input
+                  type="checkbox"
+                  checked={wordCues}
+                  onChange={(e) => setWordCues(e.target.checked)}
+                  className="w-3 h-3 rounded bg-gray-700 border-gray-600 text-blue-600 focus:ring-blue-500"
+                />
+                Word cues
+              </label>
+            )}
             <button
               onClick={() => handleExport('txt')}
               className="px-3 py-1 bg-gray-700 hover:bg-gray-600 rounded text-sm"
@@ -88,6 +108,12 @@ export const TranscriptViewer: React.FC<TranscriptViewerProps> = ({ clipId }) =>
             >
               VTT
             </button>
+            <button
+              onClick={() => handleExport('csv')}
+              className="px-3 py-1 bg-gray-700 hover:bg-gray-600 rounded text-sm"
+            >
+              CSV
+            </button>
           </div>
         </div>
 
@@ -102,9 +128,20 @@ export const TranscriptViewer: React.FC<TranscriptViewerProps> = ({ clipId }) =>
 
         {/* Info */}
         <div className="mt-2 text-xs text-gray-400">
-          Language: {transcript.language} • Duration: {transcript.duration.toFixed(1)}s
+          Language: {transcript.language}
+          {transcript.sourceLanguage && ` (translated from ${transcript.sourceLanguage})`} • Duration: {transcript.duration.toFixed(1)}s
         </div>
 
+        {/* Chunks that failed to transcribe */}
+        {transcript.failedRanges && transcript.failedRanges.length > 0 && (
+          <div className="mt-2 text-xs text-yellow-400">
+            Missing from transcript:{' '}
+            {transcript.failedRanges
+              .map((range) => `${range.start.toFixed(1)}s–${range.end.toFixed(1)}s`)
+              .join(', ')}
+          </div>
+        )}
+
         {/* Hashtags */}
         {transcript.hashtags && transcript.hashtags.length > 0 && (
           <div className="mt-3 flex flex-wrap gap-2">
@@ -124,6 +161,7 @@ export const TranscriptViewer: React.FC<TranscriptViewerProps> = ({ clipId }) =>
       <div ref={containerRef} className="flex-1 overflow-y-auto p-4 space-y-2">
         {filteredSegments.map((segment) => {
           const isActive = segment.id === activeSegmentId;
+          const isLowConfidence = segment.confidence != null && segment.confidence < LOW_CONFIDENCE;
           return (
             <div
               key={segment.id}
@@ -139,8 +177,12 @@ export const TranscriptViewer: React.FC<TranscriptViewerProps> = ({ clipId }) =>
                 <span className="text-xs text-gray-400 font-mono">
                   {formatTime(segment.start)} → {formatTime(segment.end)}
                 </span>
-                {isActive && (
+                {isActive ? (
                   <span className="text-xs text-blue-400 font-semibold">▶ Playing</span>
+                ) : isLowConfidence && (
+                  <span className="text-xs text-yellow-400" title={`Confidence ${Math.round(segment.confidence! * 100)}%`}>
+                    ⚠ Review
+                  </span>
                 )}
               </div>
               <p className="text-sm leading-relaxed">{segment.text}</p>
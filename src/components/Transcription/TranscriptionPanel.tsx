@@ -6,11 +6,12 @@
  * Handles clip selection and transcription progress.
  */
 
-import React, { useState } from 'react';
+import React, { useState, useEffect } from 'react';
 import { useTranscriptionStore } from '../../store/transcriptionStore';
 import { useMediaStore } from '../../store/mediaStore';
 import { useTimelineStore } from '../../store/timelineStore';
 import { transcriptionService } from '../../services/transcriptionService';
+import { videoService } from '../../services/videoService';
 import { toAppError } from '../../utils/errors';
 
 /**
@@ -41,17 +42,37 @@ export const TranscriptionPanel: React.FC<TranscriptionPanelProps> = ({ onClose
   const [transcribeMode, setTranscribeMode] = useState<'clip' | 'timeline'>('clip');
   const [selectedClipId, setSelectedClipId] = useState<string | null>(null);
   const [language, setLanguage] = useState<string>('');
+  const [translate, setTranslate] = useState(false);
   const [error, setError] = useState<string | null>(null);
 
   // Get all clips from timeline
   const allClips = tracks.flatMap((track) => track.clips);
 
+  // Silent clips (e.g. screen recordings without a microphone) can't be transcribed;
+  // null while unknown so the button isn't blocked on the probe
+  const [selectedClipHasAudio, setSelectedClipHasAudio] = useState<boolean | null>(null);
+  const selectedMediaPath = files.find(
+    (f) => f.id === allClips.find((c) => c.id === selectedClipId)?.mediaFileId
+  )?.path;
+
+  useEffect(() => {
+    setSelectedClipHasAudio(null);
+    if (!selectedMediaPath) return;
+
+    let cancelled = false;
+    videoService.hasAudio(selectedMediaPath)
+      .then((hasAudio) => {
+        if (!cancelled) setSelectedClipHasAudio(hasAudio);
+      })
+      .catch((error) => console.error('Failed to check for audio:', error));
+    return () => {
+      cancelled = true;
+    };
+  }, [selectedMediaPath]);
+
   const handleTranscribe = async () => {
+    // Without a key, clips and the timeline are transcribed locally with whisper.cpp
     const apiKey = getApiKey();
-    if (!apiKey) {
-      setError('OpenAI API key not found. Please set VITE_OPENAI_API_KEY in your .env file.');
-      return;
-    }
 
     setError(null);
     setIsTranscribing(true);
@@ -98,29 +119,33 @@ export const TranscriptionPanel: React.FC<TranscriptionPanelProps> = ({ onClose
             model: 'whisper-1',
             responseFormat: 'verbose_json',
             temperature: 0.0,
+            translate,
+            wordTimestamps: true,
           },
           (progress) => {
             setProgress(progress);
           }
         );
 
-        // Generate hashtags for timeline transcript
-        setProgress({
-          clipId: 'timeline',
-          stage: 'processing',
-          percent: 95,
-          message: 'Generating hashtags...',
-        });
-
-        try {
-          const hashtags = await transcriptionService.generateHashtags(
-            transcript.fullText,
-            apiKey,
-            10
-          );
-          transcript.hashtags = hashtags;
-        } catch (error) {
-          console.error('Failed to generate hashtags:', error);
+        // Generate hashtags for timeline transcript (needs the OpenAI API)
+        if (apiKey) {
+          setProgress({
+            clipId: 'timeline',
+            stage: 'processing',
+            percent: 95,
+            message: 'Generating hashtags...',
+          });
+
+          try {
+            const hashtags = await transcriptionService.generateHashtags(
+              transcript.fullText,
+              apiKey,
+              10
+            );
+            transcript.hashtags = hashtags;
+          } catch (error) {
+            console.error('Failed to generate hashtags:', error);
+          }
         }
 
         // Store with clipId "timeline"
@@ -158,29 +183,33 @@ export const TranscriptionPanel: React.FC<TranscriptionPanelProps> = ({ onClose
             model: 'whisper-1',
             responseFormat: 'verbose_json',
             temperature: 0.0,
+            translate,
+            wordTimestamps: true,
           },
           (progress) => {
             setProgress(progress);
           }
         );
 
-        // Generate hashtags from transcript content
-        setProgress({
-          clipId: clip.id,
-          stage: 'processing',
-          percent: 95,
-          message: 'Generating hashtags...',
-        });
-
-        try {
-          const hashtags = await transcriptionService.generateHashtags(
-            transcript.fullText,
-            apiKey,
-            10
-          );
-          transcript.hashtags = hashtags;
-        } catch (error) {
-          console.error('Failed to generate hashtags:', error);
+        // Generate hashtags from transcript content (needs the OpenAI API)
+        if (apiKey) {
+          setProgress({
+            clipId: clip.id,
+            stage: 'processing',
+            percent: 95,
+            message: 'Generating hashtags...',
+          });
+
+          try {
+            const hashtags = await transcriptionService.generateHashtags(
+              transcript.fullText,
+              apiKey,
+              10
+            );
+            transcript.hashtags = hashtags;
+          } catch (error) {
+            console.error('Failed to generate hashtags:', error);
+          }
         }
 
         addTranscript(transcript);
@@ -225,9 +254,9 @@ export const TranscriptionPanel: React.FC<TranscriptionPanelProps> = ({ onClose
             {/* API Key Status */}
             {!getApiKey() && (
               <div className="p-3 bg-yellow-600/20 border border-yellow-600 rounded text-yellow-200">
-                <p className="font-semibold mb-1">API Key Required</p>
+                <p className="font-semibold mb-1">No API Key</p>
                 <p className="text-sm">
-                  Please set <code className="bg-gray-700 px-1 rounded">VITE_OPENAI_API_KEY</code> in your <code className="bg-gray-700 px-1 rounded">.env</code> file.
+                  Clips and the timeline will be transcribed locally with whisper.cpp. Hashtags need <code className="bg-gray-700 px-1 rounded">VITE_OPENAI_API_KEY</code> in your <code className="bg-gray-700 px-1 rounded">.env</code> file.
                 </p>
                 <p className="text-xs mt-2 opacity-75">
                   Example: <code className="bg-gray-700 px-1 rounded">VITE_OPENAI_API_KEY=sk-...</code>
@@ -295,6 +324,11 @@ export const TranscriptionPanel: React.FC<TranscriptionPanelProps> = ({ onClose
                     );
                   })}
                 </select>
+                {selectedClipHasAudio === false && (
+                  <p className="mt-2 text-xs text-yellow-300">
+                    This clip has no audio track to transcribe
+                  </p>
+                )}
               </div>
             )}
 
@@ -308,15 +342,27 @@ export const TranscriptionPanel: React.FC<TranscriptionPanelProps> = ({ onClose
                 value={language}
                 onChange={(e) => setLanguage(e.target.value)}
                 placeholder="en, es, fr, etc. (leave blank for auto-detect)"
-                className="w-full px-3 py-2 bg-gray-700 text-white rounded-lg border border-gray-600 focus:border-blue-500 focus:outline-none"
+                disabled={translate}
+                className="w-full px-3 py-2 bg-gray-700 text-white rounded-lg border border-gray-600 focus:border-blue-500 focus:outline-none disabled:opacity-50"
               />
             </div>
 
+            {/* Translate */}
+            <label className="flex items-center gap-3 cursor-pointer">
+              <input
+                type="checkbox"
+                checked={translate}
+                onChange={(e) => setTranslate(e.target.checked)}
+                className="w-4 h-4 rounded bg-gray-700 border-gray-600 text-blue-600 focus:ring-blue-500"
+              />
+              <span className="text-gray-300">Translate to English</span>
+            </label>
+
             {/* Actions */}
             <div className="flex gap-4">
               <button
                 onClick={handleTranscribe}
-                disabled={(transcribeMode === 'clip' && !selectedClipId) || (transcribeMode === 'timeline' && allClips.length === 0) || !getApiKey()}
+                disabled={(transcribeMode === 'clip' && (!selectedClipId || selectedClipHasAudio === false)) || (transcribeMode === 'timeline' && allClips.length === 0)}
                 className="flex-1 px-4 py-2 bg-blue-600 hover:bg-blue-700 disabled:bg-gray-600 disabled:cursor-not-allowed text-white font-semibold rounded-lg transition-colors"
               >
                 {transcribeMode === 'timeline' ? 'Transcribe Timeline' : 'Transcribe Clip'}
@@ -8,9 +8,15 @@ import { handleError, toAppError } from '../utils/errors';
 
 export interface RecordingStatus {
   isRecording: boolean;
+  isPaused: boolean;
+  /** FFmpeg exited on its own while recording; stop the recording to keep what was written */
+  crashed: boolean;
   elapsed: number;
   outputPath: string | null;
-  recordingType: 'screen' | { type: 'webcam'; cameraIndex: number };
+  recordingType:
+    | 'screen'
+    | { type: 'webcam'; cameraIndex: number }
+    | { type: 'pictureInPicture'; cameraIndex: number };
 }
 
 export interface CameraInfo {
@@ -18,6 +24,13 @@ export interface CameraInfo {
   name: string;
 }
 
+export interface CameraMode {
+  width: number;
+  height: number;
+  /** Highest frame rate the camera delivers at this size */
+  fps: number;
+}
+
 export class RecordingService {
   /**
    * Start screen recording
@@ -138,6 +151,18 @@ export class RecordingService {
       throw toAppError(error);
     }
   }
+
+  /**
+   * Get the sizes and frame rates a camera supports, largest first
+   */
+  async getCameraCapabilities(index: number): Promise<CameraMode[]> {
+    try {
+      return await invoke<CameraMode[]>('get_camera_capabilities', { index });
+    } catch (error) {
+      handleError(error, 'RecordingService.getCameraCapabilities');
+      throw toAppError(error);
+    }
+  }
 }
 
 // Export singleton instance
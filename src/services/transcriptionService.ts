@@ -8,7 +8,7 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { save } from '@tauri-apps/plugin-dialog';
-import { Transcript, TranscriptionProgress, TranscriptionConfig } from '../types/transcription';
+import { ClipTranscription, Transcript, TranscriptionProgress, TranscriptionConfig, CaptionGranularity } from '../types/transcription';
 import { handleError, toAppError, AppError, ErrorCode } from '../utils/errors';
 
 export class TranscriptionService {
@@ -16,13 +16,14 @@ export class TranscriptionService {
 
   /**
    * Transcribe a video clip
+   * Without an API key the clip is transcribed locally with whisper.cpp
    */
   async transcribeClip(
     clipId: string,
     filePath: string,
     trimStart: number,
     duration: number,
-    apiKey: string,
+    apiKey: string | null,
     config: TranscriptionConfig,
     onProgress?: (progress: TranscriptionProgress) => void
   ): Promise<Transcript> {
@@ -97,10 +98,14 @@ export class TranscriptionService {
 
   /**
    * Export transcript to file
+   * For CSV, includeWords also writes word timestamps to a .words.csv file alongside
+   * For SRT/VTT, granularity 'word' emits one cue per word (falls back to segments without word timings)
    */
   async exportTranscript(
     transcript: Transcript,
-    format: 'txt' | 'srt' | 'vtt' | 'json' = 'txt'
+    format: 'txt' | 'srt' | 'vtt' | 'json' | 'csv' = 'txt',
+    includeWords = false,
+    granularity: CaptionGranularity = 'segment'
   ): Promise<void> {
     try {
       // Open save dialog
@@ -120,6 +125,8 @@ export class TranscriptionService {
         transcript,
         outputPath,
         format,
+        includeWords,
+        granularity,
       });
     } catch (error) {
       handleError(error, 'TranscriptionService.exportTranscript');
@@ -146,8 +153,67 @@ export class TranscriptionService {
     ) || null;
   }
 
+  /**
+   * Transcribe several clips in one backend call, a few at a time
+   * Returns one result per clip, in order; clips that fail carry their error instead of a
+   * transcript and are also reported through onProgress with stage 'error'
+   */
+  async transcribeClips(
+    clips: Array<{
+      clipId: string;
+      filePath: string;
+      trimStart: number;
+      duration: number;
+    }>,
+    apiKey: string | null,
+    config: TranscriptionConfig,
+    onProgress?: (progress: TranscriptionProgress) => void,
+    maxConcurrent?: number
+  ): Promise<ClipTranscription[]> {
+    const clipIds = new Set(clips.map((clip) => clip.clipId));
+
+    try {
+      // Set up progress listener
+      if (onProgress) {
+        this.progressListener = await listen<TranscriptionProgress>(
+          'transcription-progress',
+          (event) => {
+            if (clipIds.has(event.payload.clipId)) {
+              onProgress(event.payload);
+            }
+          }
+        );
+      }
+
+      const results = await invoke<ClipTranscription[]>('transcribe_clips', {
+        clips,
+        apiKey,
+        config,
+        maxConcurrent,
+      });
+
+      // Clean up listener
+      if (this.progressListener) {
+        this.progressListener();
+        this.progressListener = null;
+      }
+
+      return results;
+    } catch (error) {
+      // Clean up listener on error
+      if (this.progressListener) {
+        this.progressListener();
+        this.progressListener = null;
+      }
+
+      handleError(error, 'TranscriptionService.transcribeClips');
+      throw toAppError(error, 'TranscriptionService.transcribeClips');
+    }
+  }
+
   /**
    * Transcribe entire timeline (all clips combined)
+   * Without an API key the timeline is transcribed locally with whisper.cpp
    */
   async transcribeTimeline(
     clips: Array<{
@@ -158,7 +224,7 @@ export class TranscriptionService {
       trimEnd: number;
     }>,
     compositionLength: number,
-    apiKey: string,
+    apiKey: string | null,
     config: TranscriptionConfig,
     onProgress?: (progress: TranscriptionProgress) => void
   ): Promise<Transcript> {
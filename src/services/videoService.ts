@@ -7,7 +7,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-dialog';
 import { MediaFile, MediaMetadata } from '../types/media';
 import { v4 as uuidv4 } from 'uuid';
-import { handleError, createFFmpegError, toAppError } from '../utils/errors';
+import { handleError, createFFmpegError, toAppError, getErrorMessage } from '../utils/errors';
 
 export class VideoService {
   /**
@@ -93,7 +93,7 @@ export class VideoService {
       };
     } catch (error) {
       handleError(error, 'VideoService.createMediaFile');
-      const errorMsg = error instanceof Error ? error.message : String(error);
+      const errorMsg = getErrorMessage(error);
       const errorStderr = error && typeof error === 'object' && 'stderr' in error 
         ? String((error as any).stderr) 
         : errorMsg;
@@ -101,6 +101,18 @@ export class VideoService {
     }
   }
   
+  /**
+   * Check whether a file has an audio track, without fetching full metadata
+   */
+  async hasAudio(filePath: string): Promise<boolean> {
+    try {
+      return await invoke<boolean>('has_audio', { filePath });
+    } catch (error) {
+      handleError(error, 'VideoService.hasAudio');
+      throw createFFmpegError(getErrorMessage(error));
+    }
+  }
+  
   /**
    * Generate thumbnail for video at specific timestamp
    * Returns base64 data URL
@@ -115,12 +127,13 @@ export class VideoService {
       return `data:image/jpeg;base64,${base64Image}`;
     } catch (error) {
       handleError(error, 'VideoService.generateThumbnail');
-      throw createFFmpegError(String(error));
+      throw createFFmpegError(getErrorMessage(error));
     }
   }
   
   /**
    * Export timeline to video file
+   * Resolves with warnings that didn't stop the export (e.g. frame rate mismatches)
    */
   async exportVideo(
     clips: Array<{
@@ -134,9 +147,9 @@ export class VideoService {
     resolution: '720p' | '1080p' | 'source',
     fps: number,
     compositionLength: number
-  ): Promise<void> {
+  ): Promise<string[]> {
     try {
-      await invoke('export_video', {
+      return await invoke<string[]>('export_video', {
         clips,
         outputPath,
         resolution,
@@ -145,7 +158,7 @@ export class VideoService {
       });
     } catch (error) {
       handleError(error, 'VideoService.exportVideo');
-      throw createFFmpegError(String(error));
+      throw createFFmpegError(getErrorMessage(error));
     }
   }
   
@@ -160,7 +173,7 @@ export class VideoService {
       return metadata.duration;
     } catch (error) {
       handleError(error, 'VideoService.getVideoDuration');
-      throw createFFmpegError(String(error));
+      throw createFFmpegError(getErrorMessage(error));
     }
   }
 
@@ -178,7 +191,7 @@ export class VideoService {
       return mediaFile;
     } catch (error) {
       handleError(error, 'VideoService.createMediaFileFromPath');
-      throw createFFmpegError(String(error));
+      throw createFFmpegError(getErrorMessage(error));
     }
   }
 }
@@ -5,6 +5,8 @@
  * All time values are in seconds.
  */
 
+import { BackendError } from '../utils/errors';
+
 /**
  * A segment of transcribed text with timestamps
  */
@@ -42,6 +44,20 @@ export interface TranscriptWord {
   confidence?: number;
 }
 
+/**
+ * A stretch of long audio whose chunk failed to transcribe
+ */
+export interface FailedRange {
+  /** Start time in seconds */
+  start: number;
+  
+  /** End time in seconds */
+  end: number;
+  
+  /** Why the chunk failed */
+  message: string;
+}
+
 /**
  * Complete transcript for a video clip
  */
@@ -52,9 +68,12 @@ export interface Transcript {
   /** ID of the clip this transcript belongs to */
   clipId: string;
   
-  /** Detected or specified language (ISO 639-1 code) */
+  /** Detected or specified language (ISO 639-1 code); "en" for translations */
   language: string;
   
+  /** Language of the audio when it was translated to English */
+  sourceLanguage?: string;
+  
   /** Segments of transcribed text */
   segments: TranscriptSegment[];
   
@@ -72,6 +91,23 @@ export interface Transcript {
   
   /** AI-generated hashtags based on transcription content (up to 10) */
   hashtags?: string[];
+  
+  /** Parts of the audio missing from the transcript because their chunk failed */
+  failedRanges?: FailedRange[];
+}
+
+/**
+ * Result for one clip of a batch transcription: the transcript, or why it failed
+ */
+export interface ClipTranscription {
+  /** ID of the clip this result belongs to */
+  clipId: string;
+  
+  /** The transcript, or null if the clip failed */
+  transcript: Transcript | null;
+  
+  /** Why the clip failed, or null if it succeeded */
+  error: BackendError | null;
 }
 
 /**
@@ -91,6 +127,11 @@ export interface TranscriptionProgress {
   message: string;
 }
 
+/**
+ * Subtitle cue size for SRT/VTT export: one cue per segment or per word
+ */
+export type CaptionGranularity = 'segment' | 'word';
+
 /**
  * Configuration for transcription
  */
@@ -106,5 +147,11 @@ export interface TranscriptionConfig {
   
   /** Temperature for sampling (0-1, default: 0.0) */
   temperature: number;
+  
+  /** Translate the audio to English instead of transcribing it as spoken */
+  translate?: boolean;
+  
+  /** Request word-level timestamps (needed for word cues; not available when translating) */
+  wordTimestamps?: boolean;
 }
 
@@ -15,6 +15,7 @@ export enum ErrorCode {
   VALIDATION_ERROR = 'VALIDATION_ERROR',
   TIMELINE_ERROR = 'TIMELINE_ERROR',
   MEDIA_ERROR = 'MEDIA_ERROR',
+  CANCELLED = 'CANCELLED',
   UNKNOWN = 'UNKNOWN',
 }
 
@@ -34,6 +35,42 @@ export class AppError extends Error {
   }
 }
 
+/**
+ * Structured error returned by Tauri commands (see src-tauri/src/error.rs)
+ */
+export type BackendErrorKind =
+  | 'ffmpegNotFound'
+  | 'processFailed'
+  | 'permissionDenied'
+  | 'invalidInput'
+  | 'timeout'
+  | 'cancelled'
+  | 'processNotRunning'
+  | 'whisperNotFound'
+  | 'api'
+  | 'io';
+
+export interface BackendError {
+  kind: BackendErrorKind;
+  message: string;
+  stderr?: string;
+}
+
+export const isBackendError = (error: unknown): error is BackendError =>
+  !!error &&
+  typeof error === 'object' &&
+  typeof (error as BackendError).kind === 'string' &&
+  typeof (error as BackendError).message === 'string';
+
+/**
+ * Readable message for anything thrown or rejected (Error, backend error object, or string)
+ */
+export const getErrorMessage = (error: unknown): string => {
+  if (error instanceof Error) return error.message;
+  if (isBackendError(error)) return error.message;
+  return String(error);
+};
+
 /**
  * Convert unknown errors to AppError with full debug details
  */
@@ -42,7 +79,7 @@ export const toAppError = (error: unknown, context?: string): AppError => {
     return error;
   }
   
-  const errorMessage = error instanceof Error ? error.message : String(error);
+  const errorMessage = getErrorMessage(error);
   const errorStack = error instanceof Error ? error.stack : undefined;
   
   // Build debug information
@@ -57,6 +94,87 @@ export const toAppError = (error: unknown, context?: string): AppError => {
     debugInfo += `\n\nCause: ${error.cause}`;
   }
   
+  // Backend errors say what went wrong; no need to guess from the message
+  if (isBackendError(error)) {
+    switch (error.kind) {
+      case 'ffmpegNotFound':
+        return new AppError(
+          errorMessage,
+          'FFmpeg could not be found or started. Please reinstall the app.',
+          ErrorCode.FFMPEG_FAILED,
+          false,
+          { context, originalError: error, debug: debugInfo }
+        );
+      case 'permissionDenied':
+        return new AppError(
+          errorMessage,
+          'Permission denied. Please check file permissions and privacy settings and try again.',
+          ErrorCode.PERMISSION_DENIED,
+          true,
+          { context, originalError: error, debug: debugInfo }
+        );
+      case 'processFailed':
+        return new AppError(
+          errorMessage,
+          `Video processing failed: ${errorMessage}`,
+          ErrorCode.FFMPEG_FAILED,
+          true,
+          { context, originalError: error, debug: `${debugInfo}\n\nFFmpeg stderr:\n${error.stderr ?? ''}` }
+        );
+      case 'invalidInput':
+        return new AppError(
+          errorMessage,
+          errorMessage,
+          ErrorCode.VALIDATION_ERROR,
+          true,
+          { context, originalError: error, debug: debugInfo }
+        );
+      case 'timeout':
+        return new AppError(
+          errorMessage,
+          'The file took too long to read. It may be corrupt or on a slow network drive.',
+          ErrorCode.MEDIA_ERROR,
+          true,
+          { context, originalError: error, debug: debugInfo }
+        );
+      case 'cancelled':
+        return new AppError(
+          errorMessage,
+          errorMessage,
+          ErrorCode.CANCELLED,
+          true,
+          { context, originalError: error, debug: debugInfo }
+        );
+      case 'processNotRunning':
+        return new AppError(
+          errorMessage,
+          `Nothing to stop: ${errorMessage}`,
+          ErrorCode.MEDIA_ERROR,
+          true,
+          { context, originalError: error, debug: debugInfo }
+        );
+      case 'whisperNotFound':
+        return new AppError(
+          errorMessage,
+          errorMessage,
+          ErrorCode.VALIDATION_ERROR,
+          false,
+          { context, originalError: error, debug: debugInfo }
+        );
+      case 'api':
+        return new AppError(
+          errorMessage,
+          `Transcription service error: ${errorMessage}`,
+          ErrorCode.NETWORK_ERROR,
+          true,
+          { context, originalError: error, debug: debugInfo }
+        );
+      case 'io':
+        // Fall through to the message patterns below (e.g. missing files)
+        break;
+    }
+  }
+  
   // Parse common error patterns
   if (errorMessage.includes('No such file') || errorMessage.includes('FILE_NOT_FOUND')) {
     return new AppError(